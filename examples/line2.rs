@@ -0,0 +1,61 @@
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    color::Rgb,
+    line::{LineCap, LineJoin, LineMaterial},
+    winit::{event_loop::EventLoop, window::WindowBuilder},
+    Line2, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let size = window.inner_size();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.1, 0.2, 0.3));
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., -4., 2.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_z(),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+
+    // helix trajectory with a color gradient
+    let n = 200;
+    let (points, colors): (Vec<_>, Vec<_>) = (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let angle = t * std::f32::consts::PI * 6.;
+            (
+                [angle.cos(), angle.sin(), t * 2. - 1.],
+                Rgba::new(t, 0.5, 1. - t, 1.),
+            )
+        })
+        .unzip();
+
+    scene.add_mesh(Line2::with_colors(
+        &points,
+        &colors,
+        LineMaterial::new(Rgba::new(1., 1., 1., 1.), 6.),
+    ));
+
+    scene.add_mesh(Line2::new(
+        &[[-1.5, -1.5, -1.], [1.5, -1.5, -1.], [1.5, 1.5, -1.]],
+        LineMaterial::new(Rgba::new(1., 1., 0., 1.), 10.)
+            .cap(LineCap::Square)
+            .join(LineJoin::Miter)
+            .dashed(0.2, 0.1),
+    ));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
@@ -0,0 +1,46 @@
+use san::{
+    color::Rgb,
+    geometry::Geometry,
+    material::LineBasicMaterial,
+    winit::{event_loop::EventLoop, window::WindowBuilder},
+    Mesh, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.1, 0.2, 0.3));
+
+    // axes
+    scene.add_mesh(Mesh::new(
+        Geometry::lines(&[[-1., 0., 0.], [1., 0., 0.], [0., -1., 0.], [0., 1., 0.]]),
+        LineBasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+
+    let wave: Vec<_> = (0..=100)
+        .map(|i| {
+            let x = i as f32 / 50. - 1.;
+            [x, 0.5 * (x * std::f32::consts::PI * 2.).sin(), 0.]
+        })
+        .collect();
+
+    scene.add_mesh(Mesh::new(
+        Geometry::polyline(&wave),
+        LineBasicMaterial::new(Rgba::new(0.8, 0.8, 0., 1.)),
+    ));
+
+    scene.add_mesh(Mesh::new(
+        Geometry::points(&wave),
+        LineBasicMaterial::new(Rgba::new(0.8, 0., 0., 1.)),
+    ));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
@@ -0,0 +1,93 @@
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Deg, Point3, Quaternion, Rotation3, Vector3},
+    color::Rgb,
+    geometry::Geometry,
+    light::{DirectionalLight, DirectionalShadow, HemisphereLight, PointLight, SpotLight},
+    material::{LambertMaterial, PhongMaterial},
+    winit::{event_loop::EventLoop, window::WindowBuilder},
+    Background, Instance, Mesh, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let size = window.inner_size();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    let mut scene = renderer.create_scene();
+    scene.set_background(Background::gradient(
+        Rgb::new(0.2, 0.25, 0.35),
+        Rgb::new(0.05, 0.05, 0.05),
+    ));
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 1., 5.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+    scene.add_light(HemisphereLight::new(
+        Rgb::new(0.3, 0.3, 0.4),
+        Rgb::new(0.1, 0.08, 0.05),
+        1.,
+    ));
+    scene.add_light(DirectionalLight {
+        shadow: Some(DirectionalShadow::default()),
+        ..DirectionalLight::new(Rgb::new(1., 1., 1.), 1., Vector3::new(-1., -1., -1.))
+    });
+    scene.add_light(PointLight {
+        range: 4.,
+        ..PointLight::new(Rgb::new(1., 0.6, 0.2), 3., Point3::new(0., 1.5, 1.))
+    });
+    scene.add_light(SpotLight {
+        inner_angle: 10.,
+        ..SpotLight::new(
+            Rgb::new(0.2, 1., 0.4),
+            20.,
+            Point3::new(0., 3., 2.),
+            Vector3::new(0., -3., -2.),
+            15.,
+        )
+    });
+
+    let at = |x: f32| {
+        vec![Instance {
+            position: Vector3::new(x, 0., 0.),
+            ..Default::default()
+        }]
+    };
+
+    scene.add_mesh(Mesh::with_instances(
+        Geometry::plane(6., 6.),
+        LambertMaterial::new(Rgba::new(0.8, 0.8, 0.8, 1.)),
+        vec![Instance {
+            position: Vector3::new(0., -0.6, 0.),
+            rotation: Quaternion::from_angle_x(Deg(-90.)),
+            ..Default::default()
+        }],
+    ));
+
+    scene.add_mesh(Mesh::with_instances(
+        Geometry::sphere(0.6, 32, 16),
+        LambertMaterial::new(Rgba::new(0.8, 0.2, 0.2, 1.)),
+        at(-0.8),
+    ));
+
+    scene.add_mesh(Mesh::with_instances(
+        Geometry::sphere(0.6, 32, 16),
+        PhongMaterial::new(Rgba::new(0.2, 0.2, 0.8, 1.))
+            .specular(Rgb::new(1., 1., 1.))
+            .shininess(50.),
+        at(0.8),
+    ));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
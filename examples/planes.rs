@@ -1,9 +1,9 @@
 use san::{
     color::Rgb,
     geometry::Geometry,
-    material::BasicMaterial,
+    material::{BasicMaterial, RenderState, Side},
     winit::{event_loop::EventLoop, window::WindowBuilder},
-    Mesh, Rgba, WGPURenderer, WGPURendererOption,
+    Mesh, Rgba, Transparency, WGPURenderer, WGPURendererOption,
 };
 
 #[async_std::main]
@@ -13,18 +13,23 @@ async fn main() {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new().build(&event_loop).unwrap();
 
-    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    // the planes intersect, so neither can be sorted in front of the other
+    let option = WGPURendererOption::default().transparency(Transparency::WeightedBlended);
+    let mut renderer = WGPURenderer::new(window, option).await;
     let mut scene = renderer.create_scene();
     scene.set_background(Rgb::new(0.1, 0.2, 0.3));
 
+    // visible from behind as well
+    let double_sided = RenderState::default().with_side(Side::Double);
+
     scene.add_mesh(Mesh::new(
         Geometry::plane(1.0, 1.0),
-        BasicMaterial::new(Rgba::new(0.8, 0., 0., 0.5)),
+        BasicMaterial::new(Rgba::new(0.8, 0., 0., 0.5)).with_render_state(double_sided),
     ));
 
     scene.add_mesh(Mesh::new(
         Geometry::plane(1.5, 0.5),
-        BasicMaterial::new(Rgba::new(0., 0.8, 0., 0.5)),
+        BasicMaterial::new(Rgba::new(0., 0.8, 0., 0.5)).with_render_state(double_sided),
     ));
 
     event_loop.run(move |event, _, control_flow| {
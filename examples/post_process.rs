@@ -0,0 +1,66 @@
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    color::Rgb,
+    effect::{Bloom, Fxaa, ToneMapping, ToneMappingOperator, Vignette},
+    geometry::Geometry,
+    light::{DirectionalLight, PointLight},
+    material::{BasicMaterial, LambertMaterial},
+    winit::{event_loop::EventLoop, window::WindowBuilder},
+    Instance, Mesh, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let size = window.inner_size();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    renderer.add_effect(Bloom::new(1., 0.8));
+    renderer.add_effect(ToneMapping::new(ToneMappingOperator::Aces));
+    renderer.add_effect(Vignette::new(0.5));
+    renderer.add_effect(Fxaa::new());
+
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.02, 0.02, 0.03));
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 1., 5.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+    scene.add_light(DirectionalLight::new(
+        Rgb::new(1., 1., 1.),
+        0.5,
+        Vector3::new(-1., -1., -1.),
+    ));
+    scene.add_light(PointLight::new(
+        Rgb::new(1., 0.6, 0.2),
+        6.,
+        Point3::new(0., 1.5, 1.),
+    ));
+
+    scene.add_mesh(Mesh::new(
+        Geometry::sphere(0.8, 32, 16),
+        LambertMaterial::new(Rgba::new(0.8, 0.8, 0.8, 1.)),
+    ));
+    // brighter than white, only the bloom shows its extent
+    scene.add_mesh(Mesh::with_instances(
+        Geometry::sphere(0.15, 16, 8),
+        BasicMaterial::new(Rgba::new(4., 2.4, 0.8, 1.)),
+        vec![Instance {
+            position: Vector3::new(0., 1.5, 1.),
+            ..Default::default()
+        }],
+    ));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
@@ -0,0 +1,57 @@
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    color::Rgb,
+    geometry::Geometry,
+    light::DirectionalLight,
+    material::{BasicMaterial, LambertMaterial},
+    winit::{event::Event, event_loop::EventLoop, window::WindowBuilder},
+    Mesh, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+
+    // a lit cube seen from the side, rendered offscreen every frame
+    let mut inner = renderer.create_scene();
+    inner.set_background(Rgb::new(0.2, 0.2, 0.2));
+    inner.add_light(DirectionalLight::new(
+        Rgb::new(1., 1., 1.),
+        1.,
+        Vector3::new(-1., -1., -1.),
+    ));
+    inner.add_mesh(Mesh::new(
+        Geometry::cuboid(1., 1., 1.),
+        LambertMaterial::new(Rgba::new(0.8, 0.4, 0.1, 1.)),
+    ));
+    let mut target = renderer.create_render_target(256, 256);
+    target.set_camera(&PerspectiveCamera {
+        eye: Point3::new(2., 1.5, 2.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.1, 0.2, 0.3));
+    scene.add_mesh(Mesh::new(
+        Geometry::plane(1., 1.),
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)).with_map(target.texture()),
+    ));
+
+    event_loop.run(move |event, _, control_flow| {
+        if let Event::RedrawRequested(_) = event {
+            renderer.render_to_target(&inner, &target);
+        }
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
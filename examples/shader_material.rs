@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    color::Rgb,
+    geometry::Geometry,
+    material::ShaderMaterial,
+    mesh::Mesh,
+    winit::{event::Event, event_loop::EventLoop, window::WindowBuilder},
+    Rgba, WGPURenderer, WGPURendererOption,
+};
+
+const SOURCE: &str = "
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let wave = 1. + 0.1 * sin(8. * model.position.y + 3. * uniforms.time);
+
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * model_matrix(instance)
+        * vec4<f32>(model.position * wave, 1.);
+    out.world_normal = normalize(normal_matrix(instance) * model.normal);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let rim = 1. - abs(in.world_normal.z);
+    return vec4<f32>(mix(uniforms.color.rgb, vec3<f32>(1.), rim * rim), 1.);
+}
+";
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let size = window.inner_size();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.05, 0.05, 0.1));
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 0., 4.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+
+    let material = ShaderMaterial::new("wave", SOURCE)
+        .with_uniform("color", Rgba::new(0.2, 0.4, 0.9, 1.))
+        .with_uniform("time", 0f32);
+    let id = scene.add_mesh(Mesh::new(Geometry::sphere(1., 64, 32), material));
+
+    let start = Instant::now();
+    event_loop.run(move |event, _, control_flow| {
+        if let Event::RedrawRequested(_) = event {
+            let mesh = scene.get_mesh_ref(&id);
+            mesh.material()
+                .set_uniform("time", start.elapsed().as_secs_f32());
+        }
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
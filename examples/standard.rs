@@ -0,0 +1,81 @@
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    color::Rgb,
+    geometry::Geometry,
+    light::DirectionalLight,
+    material::StandardMaterial,
+    texture::{ColorSpace, Texture},
+    winit::{event_loop::EventLoop, window::WindowBuilder},
+    Instance, Mesh, Rgba, WGPURenderer, WGPURendererOption,
+};
+
+// sky above the horizon, checkered ground below
+fn environment() -> Texture {
+    let (width, height) = (64, 32);
+
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let color = if y < height / 2 {
+                [120, 170, 255]
+            } else if (x / 8 + y / 8) % 2 == 0 {
+                [150, 130, 100]
+            } else {
+                [90, 70, 50]
+            };
+            data.extend([color[0], color[1], color[2], 255]);
+        }
+    }
+
+    Texture::from_rgba8(width, height, data, ColorSpace::Srgb)
+}
+
+#[async_std::main]
+async fn main() {
+    env_logger::init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let size = window.inner_size();
+
+    let mut renderer = WGPURenderer::new(window, WGPURendererOption::default()).await;
+    let mut scene = renderer.create_scene();
+    scene.set_background(Rgb::new(0.1, 0.1, 0.1));
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 0., 6.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: size.width as f32 / size.height as f32,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    });
+    scene.add_light(DirectionalLight::new(
+        Rgb::new(1., 1., 1.),
+        1.,
+        Vector3::new(-1., -1., -1.),
+    ));
+
+    let env = environment();
+    for (i, (metallic, roughness)) in [(0., 0.2), (0., 0.8), (1., 0.2), (1., 0.6)]
+        .into_iter()
+        .enumerate()
+    {
+        scene.add_mesh(Mesh::with_instances(
+            Geometry::sphere(0.6, 32, 16),
+            StandardMaterial::new(Rgba::new(0.9, 0.6, 0.2, 1.))
+                .metallic(metallic)
+                .roughness(roughness)
+                .env_map(env.clone(), 1.),
+            vec![Instance {
+                position: Vector3::new(-2.25 + i as f32 * 1.5, 0., 0.),
+                ..Default::default()
+            }],
+        ));
+    }
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = renderer.handle_event(&event, &scene);
+    });
+}
@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use cgmath::{Matrix4, SquareMatrix};
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    pipeline::PipelineCache,
+    shader::ShaderFeatures,
+    texture::{self, ColorSpace, CubeTexture, Texture},
+    Rgb, Rgba,
+};
+
+static BACKGROUND_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
+/// What is drawn behind all meshes of a [`Scene`](crate::Scene).
+#[derive(Debug, Clone)]
+pub enum Background {
+    Color(wgpu::Color),
+    /// Vertical gradient from the top to the bottom of the viewport.
+    Gradient {
+        top: wgpu::Color,
+        bottom: wgpu::Color,
+    },
+    /// Cube map seen from the camera position, following only its rotation.
+    Skybox(CubeTexture),
+}
+
+impl Background {
+    pub fn gradient<T, B>(top: T, bottom: B) -> Self
+    where
+        T: Into<wgpu::Color>,
+        B: Into<wgpu::Color>,
+    {
+        Self::Gradient {
+            top: top.into(),
+            bottom: bottom.into(),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Color(wgpu::Color::WHITE)
+    }
+}
+
+impl From<wgpu::Color> for Background {
+    fn from(color: wgpu::Color) -> Self {
+        Self::Color(color)
+    }
+}
+
+impl From<Rgb> for Background {
+    fn from(color: Rgb) -> Self {
+        Self::Color(color.into())
+    }
+}
+
+impl From<Rgba> for Background {
+    fn from(color: Rgba) -> Self {
+        Self::Color(color.into())
+    }
+}
+
+impl From<CubeTexture> for Background {
+    fn from(cube: CubeTexture) -> Self {
+        Self::Skybox(cube)
+    }
+}
+
+/// Clears the target and draws the non-flat backgrounds as a fullscreen triangle.
+pub(crate) struct BackgroundPass {
+    background: Background,
+    buffer: wgpu::Buffer,
+    // created on first use, the skybox upload needs the queue
+    bind_group: OnceCell<wgpu::BindGroup>,
+    // gradient and skybox, per color and depth format of the render pass
+    pipelines: PipelineCache<AttachmentFormats, [wgpu::RenderPipeline; 2]>,
+}
+
+type AttachmentFormats = (wgpu::TextureFormat, Option<wgpu::TextureFormat>);
+
+impl BackgroundPass {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Background Params Buffer"),
+            contents: bytemuck::cast_slice(&[BackgroundParams {
+                inv_view_proj: Matrix4::identity().into(),
+                top: [0.; 4],
+                bottom: [0.; 4],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            background: Background::default(),
+            buffer,
+            bind_group: OnceCell::new(),
+            pipelines: PipelineCache::new(),
+        }
+    }
+
+    pub(crate) fn background(&self) -> &Background {
+        &self.background
+    }
+
+    pub(crate) fn set_background(&mut self, background: Background) {
+        self.background = background;
+        self.bind_group = OnceCell::new();
+    }
+
+    pub(crate) fn clear_color(&self) -> wgpu::Color {
+        match self.background {
+            Background::Color(color) => color,
+            _ => wgpu::Color::BLACK,
+        }
+    }
+
+    /// Uploads the parameters for this frame and returns the pipelines to `draw` with
+    /// into a render pass with the given attachment formats.
+    pub(crate) fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_proj: Matrix4<f32>,
+        formats: AttachmentFormats,
+    ) -> Option<Arc<[wgpu::RenderPipeline; 2]>> {
+        let (top, bottom) = match self.background {
+            Background::Color(_) => return None,
+            Background::Gradient { top, bottom } => (color_array(top), color_array(bottom)),
+            Background::Skybox(_) => ([0.; 4], [0.; 4]),
+        };
+        let params = BackgroundParams {
+            inv_view_proj: view_proj.invert().unwrap_or(Matrix4::identity()).into(),
+            top,
+            bottom,
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[params]));
+
+        self.bind_group.get_or_init(|| {
+            let skybox = match &self.background {
+                Background::Skybox(cube) => cube.clone(),
+                // the gradient doesn't read it, but the layout is shared
+                _ => CubeTexture::from_faces(
+                    [(); 6].map(|_| Texture::solid([0; 4], ColorSpace::Linear)),
+                )
+                .unwrap(),
+            };
+            let texture = skybox.gpu_data(device, queue, formats.0);
+            let sampler = skybox.sampler().gpu_data(device, queue, formats.0);
+
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Background Bind Group"),
+                layout: layout(device),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                ],
+            })
+        });
+
+        Some(self.pipelines.get_or_create(formats, || {
+            [
+                pipeline(device, formats, "fs_gradient"),
+                pipeline(device, formats, "fs_skybox"),
+            ]
+        }))
+    }
+
+    pub(crate) fn draw<'a>(
+        &'a self,
+        pipelines: &'a [wgpu::RenderPipeline; 2],
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        let index = match self.background {
+            Background::Color(_) => return,
+            Background::Gradient { .. } => 0,
+            Background::Skybox(_) => 1,
+        };
+        let Some(bind_group) = self.bind_group.get() else {
+            return;
+        };
+
+        render_pass.set_pipeline(&pipelines[index]);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn color_array(color: wgpu::Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a].map(|v| v as f32)
+}
+
+fn layout(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+    BACKGROUND_LAYOUT.get_or_init(|| {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Background Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture::sampler_layout_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        })
+    })
+}
+
+fn pipeline(
+    device: &wgpu::Device,
+    (format, depth_format): AttachmentFormats,
+    entry_point: &str,
+) -> wgpu::RenderPipeline {
+    let label = "san::background::Background";
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: crate::shader::builtin("background.wgsl", ShaderFeatures::empty()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout(device)],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        // drawn first, behind everything
+        depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point,
+            targets: &[Some(format.into())],
+        }),
+        multiview: None,
+    })
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct BackgroundParams {
+    inv_view_proj: [[f32; 4]; 4],
+    top: [f32; 4],
+    bottom: [f32; 4],
+}
@@ -1,4 +1,4 @@
-use cgmath::{Deg, Matrix4, Point3, Vector3};
+use cgmath::{Deg, Matrix4, Point3, SquareMatrix, Transform, Vector3, Vector4};
 use wgpu::util::DeviceExt;
 
 use crate::gpu::ToGpuBuffer;
@@ -26,8 +26,38 @@ impl ToGpuBuffer for CameraUniform {
 }
 
 pub trait Camera {
+    /// Projection times the [view matrix](Self::view_matrix), mapping world space to clip
+    /// space.
     fn projection_matrix(&self) -> Matrix4<f32>;
 
+    /// Maps world space to view space, where the camera looks down the negative z axis.
+    /// Derived from the [projection matrix](Self::projection_matrix) by default.
+    fn view_matrix(&self) -> Matrix4<f32> {
+        let Some(inverse) = self.projection_matrix().invert() else {
+            return Matrix4::identity();
+        };
+        // centers of the far plane and of its top edge
+        let target = inverse.transform_point(Point3::new(0., 0., 1.));
+        let top = inverse.transform_point(Point3::new(0., 1., 1.));
+
+        Matrix4::look_at_rh(self.position(), target, top - target)
+    }
+
+    /// Derived from the [projection matrix](Self::projection_matrix) by default, the center
+    /// of the near plane for orthographic projections.
+    fn position(&self) -> Point3<f32> {
+        let Some(inverse) = self.projection_matrix().invert() else {
+            return Point3::new(0., 0., 0.);
+        };
+        // the eye is projected to a point at infinity
+        let eye = inverse * Vector4::unit_z();
+        if eye.w.abs() > f32::EPSILON {
+            Point3::from_homogeneous(eye)
+        } else {
+            inverse.transform_point(Point3::new(0., 0., 0.))
+        }
+    }
+
     fn uniform(&self) -> CameraUniform {
         CameraUniform(self.projection_matrix().into())
     }
@@ -46,8 +76,62 @@ pub struct PerspectiveCamera {
 
 impl Camera for PerspectiveCamera {
     fn projection_matrix(&self) -> Matrix4<f32> {
-        let view = Matrix4::look_at_rh(self.eye, self.target, self.up);
         let proj = cgmath::perspective(Deg(self.fovy), self.aspect, self.znear, self.zfar);
-        OPENGL_TO_WGPU_MATRIX * proj * view
+        OPENGL_TO_WGPU_MATRIX * proj * self.view_matrix()
+    }
+
+    fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    fn position(&self) -> Point3<f32> {
+        self.eye
+    }
+}
+
+/// Matrices of a camera as a frame is rendered with them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CameraView {
+    pub(crate) view: Matrix4<f32>,
+    pub(crate) view_proj: Matrix4<f32>,
+    pub(crate) eye: Point3<f32>,
+}
+
+impl CameraView {
+    pub(crate) fn new<C>(camera: &C) -> Self
+    where
+        C: Camera + ?Sized,
+    {
+        Self {
+            view: camera.view_matrix(),
+            view_proj: camera.projection_matrix(),
+            eye: camera.position(),
+        }
+    }
+
+    /// The camera placed relative to a node with the world matrix `world`.
+    pub(crate) fn placed(self, world: &Matrix4<f32>) -> Self {
+        let inverse = world.invert().unwrap_or_else(Matrix4::identity);
+        Self {
+            view: self.view * inverse,
+            view_proj: self.view_proj * inverse,
+            eye: world.transform_point(self.eye),
+        }
+    }
+
+    /// Distance of `point` in front of the camera along its view direction, the negated
+    /// view space z.
+    pub(crate) fn depth(&self, point: Point3<f32>) -> f32 {
+        -self.view.transform_point(point).z
+    }
+}
+
+impl Default for CameraView {
+    fn default() -> Self {
+        Self {
+            view: Matrix4::identity(),
+            view_proj: Matrix4::identity(),
+            eye: Point3::new(0., 0., 0.),
+        }
     }
 }
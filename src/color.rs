@@ -25,6 +25,12 @@ impl From<Rgb> for wgpu::Color {
     }
 }
 
+impl From<Rgb> for [f32; 3] {
+    fn from(c: Rgb) -> Self {
+        [c.r, c.g, c.b]
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct Rgba {
@@ -0,0 +1,418 @@
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    common::AsAny,
+    pipeline::PipelineCache,
+    render_target::Attachments,
+    shader::{self, Composer, ShaderError},
+    texture, Scene, Transparency,
+};
+
+mod bloom;
+pub use bloom::Bloom;
+
+mod color_grading;
+pub use color_grading::ColorGrading;
+
+mod fxaa;
+pub use fxaa::Fxaa;
+
+mod shader_effect;
+pub use shader_effect::ShaderEffect;
+
+mod tone_mapping;
+use tone_mapping::ToneMappingParams;
+pub use tone_mapping::{ToneMapping, ToneMappingOperator};
+
+mod vignette;
+pub use vignette::Vignette;
+
+/// Format the window frame is rendered in before tone mapping, and that the effects read
+/// and write. It is wide enough for lighting above 1.
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// A fullscreen step of the post-processing stack of a
+/// [`WGPURenderer`](crate::WGPURenderer), run in the order the effects were added.
+pub trait Effect: AsAny {
+    /// Reads the frame from `input` and writes the result to all of `output`. Both are
+    /// [`HDR_FORMAT`] textures of `context.size`.
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    );
+}
+
+pub struct EffectContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    // width, height in physical pixels
+    pub size: (u32, u32),
+}
+
+/// Texture rendered into by an effect, with its only view.
+pub(crate) type TargetTexture = (wgpu::Texture, wgpu::TextureView);
+
+/// The scene rendered into an HDR texture, passed through the effects and tone mapped
+/// to the surface.
+pub(crate) struct PostProcess {
+    pub(crate) effects: Vec<Box<dyn Effect>>,
+    pub(crate) tone_mapping: ToneMappingOperator,
+    pub(crate) exposure: f32,
+    pub(crate) transparency: Transparency,
+    // ping-pong targets of the surface size, recreated on resize
+    targets: Option<((u32, u32), [TargetTexture; 2])>,
+    present: FullscreenPass,
+}
+
+impl PostProcess {
+    pub(crate) fn new(
+        tone_mapping: ToneMappingOperator,
+        exposure: f32,
+        transparency: Transparency,
+    ) -> Self {
+        Self {
+            effects: Vec::new(),
+            tone_mapping,
+            exposure,
+            transparency,
+            targets: None,
+            present: FullscreenPass::builtin(
+                "san::effect::Present",
+                "tone_mapping.wgsl",
+                "fs_main",
+                0,
+                None,
+            ),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+        view: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        if !matches!(self.targets, Some((target_size, _)) if target_size == size) {
+            let targets = [(); 2].map(|_| create_target(device, size));
+            self.targets = Some((size, targets));
+        }
+        let Some((_, targets)) = &self.targets else {
+            unreachable!()
+        };
+
+        scene.render(
+            queue,
+            &Attachments {
+                color: &targets[0].1,
+                format: HDR_FORMAT,
+                depth: None,
+                size,
+                camera: None,
+                transparency: self.transparency,
+            },
+            encoder,
+        );
+
+        let context = EffectContext {
+            device,
+            queue,
+            size,
+        };
+        let mut input = 0;
+        for effect in &self.effects {
+            effect.render(&context, &targets[input].1, &targets[1 - input].1, encoder);
+            input = 1 - input;
+        }
+
+        let params = ToneMappingParams {
+            exposure: self.exposure,
+            operator: self.tone_mapping.id(),
+            encode_srgb: u32::from(!format.describe().srgb),
+            _padding: 0,
+        };
+        self.present.draw(
+            &context,
+            bytemuck::bytes_of(&params),
+            &targets[input].1,
+            &[],
+            view,
+            format,
+            encoder,
+        );
+    }
+}
+
+fn create_target(device: &wgpu::Device, (width, height): (u32, u32)) -> TargetTexture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Post Process Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    (texture, view)
+}
+
+/// Drawn instead of a fragment shader that fails to compile.
+const PASS_THROUGH: &str = "
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+}
+";
+
+/// One fragment shader drawn over the whole target. The source is composed after the
+/// `effect` chunk, which binds the input texture and sampler, and may declare a uniform at
+/// binding 2 and further textures from binding 3.
+pub(crate) struct FullscreenPass {
+    label: String,
+    // built-in shader the source is read from, reloaded when watched
+    file: Option<&'static str>,
+    source: String,
+    entry_point: &'static str,
+    textures: u32,
+    // blended onto the existing contents of the target instead of replacing them
+    blend: Option<wgpu::BlendState>,
+    layout: OnceCell<wgpu::BindGroupLayout>,
+    sampler: OnceCell<wgpu::Sampler>,
+    pipelines: PipelineCache<wgpu::TextureFormat>,
+}
+
+impl FullscreenPass {
+    pub(crate) fn new(
+        label: &str,
+        source: &str,
+        entry_point: &'static str,
+        textures: u32,
+        blend: Option<wgpu::BlendState>,
+    ) -> Self {
+        Self {
+            label: label.to_owned(),
+            file: None,
+            source: source.to_owned(),
+            entry_point,
+            textures,
+            blend,
+            layout: OnceCell::new(),
+            sampler: OnceCell::new(),
+            pipelines: PipelineCache::new(),
+        }
+    }
+
+    /// Pass of the built-in shader `file`.
+    pub(crate) fn builtin(
+        label: &str,
+        file: &'static str,
+        entry_point: &'static str,
+        textures: u32,
+        blend: Option<wgpu::BlendState>,
+    ) -> Self {
+        Self {
+            file: Some(file),
+            ..Self::new(label, "", entry_point, textures, blend)
+        }
+    }
+
+    /// Draws `input` and the extra `textures` into `output` with `params` at binding 2.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw(
+        &self,
+        context: &EffectContext,
+        params: &[u8],
+        input: &wgpu::TextureView,
+        textures: &[&wgpu::TextureView],
+        output: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        debug_assert_eq!(textures.len(), self.textures as usize);
+        let device = context.device;
+
+        // a new buffer per draw, a pass may be drawn several times into one encoder
+        let mut contents = params.to_vec();
+        contents.resize(params.len().max(16), 0);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Effect Params Buffer"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let sampler = self.sampler.get_or_init(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Effect Sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: buffer.as_entire_binding(),
+            },
+        ];
+        entries.extend(
+            textures
+                .iter()
+                .enumerate()
+                .map(|(i, view)| wgpu::BindGroupEntry {
+                    binding: i as u32 + 3,
+                    resource: wgpu::BindingResource::TextureView(view),
+                }),
+        );
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Effect Bind Group"),
+            layout: self.layout(device),
+            entries: &entries,
+        });
+
+        let pipeline = self.pipeline(device, format);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&self.label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: match self.blend {
+                        Some(_) => wgpu::LoadOp::Load,
+                        None => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    },
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    pub(crate) fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The composed and validated source.
+    pub(crate) fn compile(&self) -> Result<String, ShaderError> {
+        match self.file {
+            Some(file) => compose(file, &shader::builtin_source(file)),
+            None => compose(&self.label, &self.source),
+        }
+    }
+
+    fn layout(&self, device: &wgpu::Device) -> &wgpu::BindGroupLayout {
+        self.layout.get_or_init(|| {
+            let mut entries = vec![
+                texture::texture_layout_entry(0),
+                texture::sampler_layout_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ];
+            entries.extend((0..self.textures).map(|i| texture::texture_layout_entry(i + 3)));
+
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Effect Bind Group Layout"),
+                entries: &entries,
+            })
+        })
+    }
+
+    fn pipeline(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Arc<wgpu::RenderPipeline> {
+        self.pipelines
+            .get_or_create(format, || self.create_pipeline(device, format))
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let (source, entry_point) = match self.compile() {
+            Ok(source) => (source, self.entry_point),
+            Err(e) => {
+                log::error!("{e}");
+                let source = compose("pass through", PASS_THROUGH);
+                (source.expect("invalid pass through shader"), "fs_main")
+            }
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&self.label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&self.label),
+            bind_group_layouts: &[self.layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&self.label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: self.blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+fn compose(file: &str, source: &str) -> Result<String, ShaderError> {
+    let mut composer = Composer::new([]);
+    composer.include("effect");
+    composer.append(file, source)?;
+    composer.finish()
+}
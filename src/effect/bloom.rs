@@ -0,0 +1,154 @@
+use std::{any::Any, sync::Mutex};
+
+use super::{Effect, EffectContext, FullscreenPass, TargetTexture, HDR_FORMAT};
+use crate::common::AsAny;
+
+const MAX_LEVELS: usize = 6;
+
+/// Glow around the parts of the frame brighter than `threshold`, blurred over a chain of
+/// downsampled textures and added back onto the frame.
+pub struct Bloom {
+    pub threshold: f32,
+    /// Width of the soft transition below the threshold.
+    pub knee: f32,
+    pub intensity: f32,
+    prefilter: FullscreenPass,
+    downsample: FullscreenPass,
+    upsample: FullscreenPass,
+    composite: FullscreenPass,
+    levels: Mutex<Option<Levels>>,
+}
+
+// half size and smaller for a frame size, recreated when it changes
+type Levels = ((u32, u32), Vec<TargetTexture>);
+
+impl Bloom {
+    pub fn new(threshold: f32, intensity: f32) -> Self {
+        let file = "bloom.wgsl";
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+
+        Self {
+            threshold,
+            knee: 0.5,
+            intensity,
+            prefilter: FullscreenPass::builtin("san::effect::Bloom", file, "fs_prefilter", 0, None),
+            downsample: FullscreenPass::builtin(
+                "san::effect::Bloom",
+                file,
+                "fs_downsample",
+                0,
+                None,
+            ),
+            upsample: FullscreenPass::builtin(
+                "san::effect::Bloom",
+                file,
+                "fs_upsample",
+                0,
+                Some(additive),
+            ),
+            composite: FullscreenPass::builtin("san::effect::Bloom", file, "fs_composite", 1, None),
+            levels: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self::new(1., 0.5)
+    }
+}
+
+impl AsAny for Bloom {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for Bloom {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let mut levels = self.levels.lock().unwrap();
+        if !matches!(*levels, Some((size, _)) if size == context.size) {
+            *levels = Some((context.size, create_levels(context.device, context.size)));
+        }
+        let Some((_, levels)) = &*levels else {
+            unreachable!()
+        };
+
+        let params = BloomParams {
+            threshold: self.threshold,
+            knee: self.knee,
+            intensity: self.intensity,
+            _padding: 0.,
+        };
+        let params = bytemuck::bytes_of(&params);
+        let draw = |pass: &FullscreenPass,
+                    input: &wgpu::TextureView,
+                    textures: &[&wgpu::TextureView],
+                    output: &wgpu::TextureView,
+                    encoder: &mut wgpu::CommandEncoder| {
+            pass.draw(
+                context, params, input, textures, output, HDR_FORMAT, encoder,
+            );
+        };
+
+        draw(&self.prefilter, input, &[], &levels[0].1, encoder);
+        for pair in levels.windows(2) {
+            draw(&self.downsample, &pair[0].1, &[], &pair[1].1, encoder);
+        }
+        for pair in levels.windows(2).rev() {
+            draw(&self.upsample, &pair[1].1, &[], &pair[0].1, encoder);
+        }
+        draw(&self.composite, input, &[&levels[0].1], output, encoder);
+    }
+}
+
+fn create_levels(device: &wgpu::Device, (width, height): (u32, u32)) -> Vec<TargetTexture> {
+    (1..=MAX_LEVELS as u32)
+        .take_while(|&level| level == 1 || (width >> level).min(height >> level) >= 2)
+        .map(|level| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Bloom Texture"),
+                size: wgpu::Extent3d {
+                    width: (width >> level).max(1),
+                    height: (height >> level).max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: HDR_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            (texture, view)
+        })
+        .collect()
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomParams {
+    threshold: f32,
+    knee: f32,
+    intensity: f32,
+    _padding: f32,
+}
@@ -0,0 +1,100 @@
+use std::any::Any;
+
+use super::{Effect, EffectContext, FullscreenPass, HDR_FORMAT};
+use crate::{
+    common::AsAny,
+    texture::{ColorSpace, Texture},
+};
+
+/// Remaps colors through a lookup table, as exported by image editors for grading.
+///
+/// The table is a strip of `size` slices of `size` × `size` texels, `size`² wide and
+/// `size` high. Red increases along x within a slice, green along y and blue from slice to
+/// slice. Colors are clamped to 0..1 first, so it belongs after
+/// [`ToneMapping`](super::ToneMapping).
+pub struct ColorGrading {
+    lut: Texture,
+    /// Blend between the original (0) and the graded (1) colors.
+    pub intensity: f32,
+    pass: FullscreenPass,
+}
+
+impl ColorGrading {
+    pub fn new(lut: Texture) -> Self {
+        assert_eq!(lut.width(), lut.height() * lut.height());
+
+        Self {
+            lut,
+            intensity: 1.,
+            pass: FullscreenPass::builtin(
+                "san::effect::ColorGrading",
+                "color_grading.wgsl",
+                "fs_main",
+                1,
+                None,
+            ),
+        }
+    }
+
+    /// Table mapping every color to itself, a starting point for grading.
+    pub fn neutral_lut(size: u32) -> Texture {
+        assert!(size >= 2);
+
+        let max = (size - 1) as f32;
+        let channel = |v: u32| (v as f32 / max * 255.).round() as u8;
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size * size {
+                data.extend([channel(x % size), channel(y), channel(x / size), 255]);
+            }
+        }
+
+        Texture::from_rgba8(size * size, size, data, ColorSpace::Linear).with_mipmaps(false)
+    }
+
+    pub fn lut(&self) -> &Texture {
+        &self.lut
+    }
+}
+
+impl AsAny for ColorGrading {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for ColorGrading {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let lut = self.lut.gpu_data(context.device, context.queue, HDR_FORMAT);
+        let params = ColorGradingParams {
+            intensity: self.intensity,
+            _padding: [0.; 3],
+        };
+        self.pass.draw(
+            context,
+            bytemuck::bytes_of(&params),
+            input,
+            &[&lut.view],
+            output,
+            HDR_FORMAT,
+            encoder,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColorGradingParams {
+    intensity: f32,
+    _padding: [f32; 3],
+}
@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use super::{Effect, EffectContext, FullscreenPass, HDR_FORMAT};
+use crate::common::AsAny;
+
+/// Fast approximate anti-aliasing, blurring along the edges found from the luma contrast.
+/// It works best after tone mapping.
+pub struct Fxaa {
+    /// Contrast below which a pixel is never treated as an edge.
+    pub edge_threshold_min: f32,
+    /// Contrast relative to the brightest neighbor for a pixel to be an edge.
+    pub edge_threshold: f32,
+    /// Longest blur along an edge in pixels.
+    pub span_max: f32,
+    pass: FullscreenPass,
+}
+
+impl Fxaa {
+    pub fn new() -> Self {
+        Self {
+            edge_threshold_min: 1. / 32.,
+            edge_threshold: 1. / 8.,
+            span_max: 8.,
+            pass: FullscreenPass::builtin("san::effect::Fxaa", "fxaa.wgsl", "fs_main", 0, None),
+        }
+    }
+}
+
+impl Default for Fxaa {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsAny for Fxaa {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for Fxaa {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let params = FxaaParams {
+            edge_threshold_min: self.edge_threshold_min,
+            edge_threshold: self.edge_threshold,
+            span_max: self.span_max,
+            _padding: 0.,
+        };
+        self.pass.draw(
+            context,
+            bytemuck::bytes_of(&params),
+            input,
+            &[],
+            output,
+            HDR_FORMAT,
+            encoder,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct FxaaParams {
+    edge_threshold_min: f32,
+    edge_threshold: f32,
+    span_max: f32,
+    _padding: f32,
+}
@@ -0,0 +1,125 @@
+use std::any::Any;
+
+use super::{Effect, EffectContext, FullscreenPass, HDR_FORMAT};
+use crate::{common::AsAny, shader::ShaderError, texture::Texture};
+
+/// Effect from a user WGSL fragment shader.
+///
+/// The source is composed after the `effect` chunk of the [`shader`](crate::shader)
+/// preprocessor, which declares `input_texture` at binding 0,
+/// `input_sampler` at binding 1 of group 0, the fullscreen vertex shader output
+/// `VertexOutput { clip_position, uv }` and `input_texel_size()`. It has to define
+/// `fs_main(in: VertexOutput) -> @location(0) vec4<f32>`, may declare a uniform at
+/// binding 2 filled with [`set_params`](Self::set_params) and reads the textures added
+/// with [`with_texture`](Self::with_texture) from binding 3 on. A source that fails to
+/// compile is logged and the input passed through unchanged.
+///
+/// ```wgsl
+/// @group(0) @binding(2)
+/// var<uniform> strength: vec4<f32>;
+///
+/// @fragment
+/// fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///     let color = textureSample(input_texture, input_sampler, in.uv);
+///     let gray = vec3<f32>(dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722)));
+///     return vec4<f32>(mix(color.rgb, gray, strength.x), color.a);
+/// }
+/// ```
+pub struct ShaderEffect {
+    source: String,
+    params: Vec<u8>,
+    textures: Vec<Texture>,
+    pass: FullscreenPass,
+}
+
+impl ShaderEffect {
+    pub fn new(label: &str, source: &str) -> Self {
+        Self {
+            source: source.to_owned(),
+            params: Vec::new(),
+            textures: Vec::new(),
+            pass: FullscreenPass::new(label, source, "fs_main", 0, None),
+        }
+    }
+
+    pub fn with_params<T>(mut self, params: T) -> Self
+    where
+        T: bytemuck::Pod,
+    {
+        self.set_params(params);
+        self
+    }
+
+    /// Contents of the uniform at binding 2, uploaded every frame.
+    pub fn set_params<T>(&mut self, params: T)
+    where
+        T: bytemuck::Pod,
+    {
+        self.params = bytemuck::bytes_of(&params).to_vec();
+    }
+
+    /// Adds a filterable 2D texture at the next binding from 3 on.
+    pub fn with_texture(self, texture: Texture) -> Self {
+        let mut textures = self.textures;
+        textures.push(texture);
+        let pass = FullscreenPass::new(
+            self.pass.label(),
+            &self.source,
+            "fs_main",
+            textures.len() as u32,
+            None,
+        );
+
+        Self {
+            textures,
+            pass,
+            ..self
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Compiles the source without a device, reporting where it is invalid.
+    pub fn validate(&self) -> Result<(), ShaderError> {
+        self.pass.compile().map(|_| ())
+    }
+}
+
+impl AsAny for ShaderEffect {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for ShaderEffect {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let textures: Vec<_> = self
+            .textures
+            .iter()
+            .map(|texture| texture.gpu_data(context.device, context.queue, HDR_FORMAT))
+            .collect();
+        let views: Vec<_> = textures.iter().map(|texture| &texture.view).collect();
+
+        self.pass.draw(
+            context,
+            &self.params,
+            input,
+            &views,
+            output,
+            HDR_FORMAT,
+            encoder,
+        );
+    }
+}
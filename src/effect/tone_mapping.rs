@@ -0,0 +1,107 @@
+use std::any::Any;
+
+use super::{Effect, EffectContext, FullscreenPass, HDR_FORMAT};
+use crate::common::AsAny;
+
+/// Curve compressing the unbounded lighting into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMappingOperator {
+    /// Only scales by the exposure, values above 1 are clipped.
+    None,
+    #[default]
+    Reinhard,
+    /// Filmic curve of the Academy Color Encoding System, with more contrast.
+    Aces,
+    /// Curve of Blender's AgX, desaturating bright colors towards white instead of
+    /// shifting their hue.
+    AgX,
+}
+
+impl ToneMappingOperator {
+    pub(crate) fn id(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Reinhard => 1,
+            Self::Aces => 2,
+            Self::AgX => 3,
+        }
+    }
+}
+
+/// Maps the HDR frame to 0..1 after scaling it by `exposure`. Effects working on
+/// displayable colors, such as [`Fxaa`](super::Fxaa) or [`ColorGrading`](super::ColorGrading),
+/// go after it, and the tone mapping of the renderer has to be
+/// [`ToneMappingOperator::None`] then.
+pub struct ToneMapping {
+    pub operator: ToneMappingOperator,
+    pub exposure: f32,
+    pass: FullscreenPass,
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMappingOperator) -> Self {
+        Self {
+            operator,
+            exposure: 1.,
+            pass: FullscreenPass::builtin(
+                "san::effect::ToneMapping",
+                "tone_mapping.wgsl",
+                "fs_main",
+                0,
+                None,
+            ),
+        }
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self::new(ToneMappingOperator::default())
+    }
+}
+
+impl AsAny for ToneMapping {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for ToneMapping {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let params = ToneMappingParams {
+            exposure: self.exposure,
+            operator: self.operator.id(),
+            encode_srgb: 0,
+            _padding: 0,
+        };
+        self.pass.draw(
+            context,
+            bytemuck::bytes_of(&params),
+            input,
+            &[],
+            output,
+            HDR_FORMAT,
+            encoder,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ToneMappingParams {
+    pub(crate) exposure: f32,
+    pub(crate) operator: u32,
+    // non-zero when the output format does not convert to sRGB itself
+    pub(crate) encode_srgb: u32,
+    pub(crate) _padding: u32,
+}
@@ -0,0 +1,87 @@
+use std::any::Any;
+
+use super::{Effect, EffectContext, FullscreenPass, HDR_FORMAT};
+use crate::{common::AsAny, Rgb};
+
+/// Blends the frame towards a color in the corners.
+pub struct Vignette {
+    pub color: Rgb,
+    /// How much of `color` reaches the corners, from 0 to 1.
+    pub intensity: f32,
+    /// Distance from the center, 1 in the corners, beyond which `color` is fully applied.
+    pub radius: f32,
+    /// Width of the transition inside `radius`.
+    pub smoothness: f32,
+    pass: FullscreenPass,
+}
+
+impl Vignette {
+    pub fn new(intensity: f32) -> Self {
+        Self {
+            color: Rgb::new(0., 0., 0.),
+            intensity,
+            radius: 1.,
+            smoothness: 0.6,
+            pass: FullscreenPass::builtin(
+                "san::effect::Vignette",
+                "vignette.wgsl",
+                "fs_main",
+                0,
+                None,
+            ),
+        }
+    }
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl AsAny for Vignette {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Effect for Vignette {
+    fn render(
+        &self,
+        context: &EffectContext,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let params = VignetteParams {
+            color: [self.color.r, self.color.g, self.color.b, 1.],
+            intensity: self.intensity,
+            radius: self.radius,
+            smoothness: self.smoothness,
+            _padding: 0.,
+        };
+        self.pass.draw(
+            context,
+            bytemuck::bytes_of(&params),
+            input,
+            &[],
+            output,
+            HDR_FORMAT,
+            encoder,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct VignetteParams {
+    color: [f32; 4],
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+    _padding: f32,
+}
@@ -0,0 +1,153 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use once_cell::sync::{Lazy, OnceCell};
+use wgpu::util::DeviceExt;
+
+use crate::{pipeline::PipelineCache, shader::ShaderFeatures, texture};
+
+static EQUIRECT_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
+static EQUIRECT_PIPELINES: Lazy<PipelineCache<wgpu::TextureFormat>> = Lazy::new(PipelineCache::new);
+
+/// Renders the equirectangular `source` into the six layers of the cube `target`.
+pub(crate) fn project(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    source: &wgpu::TextureView,
+    target: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+) {
+    let pipeline = pipeline(device, format);
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Equirect Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Equirect Encoder"),
+    });
+
+    for face in 0..6u32 {
+        let params = EquirectParams {
+            face,
+            _padding: [0; 3],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Equirect Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Equirect Bind Group"),
+            layout: layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+            ],
+        });
+
+        let view = target.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Equirect Face View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: face,
+            array_layer_count: NonZeroU32::new(1),
+            ..Default::default()
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Equirect Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+}
+
+fn layout(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+    EQUIRECT_LAYOUT.get_or_init(|| {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Equirect Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture::sampler_layout_entry(1),
+                texture::texture_layout_entry(2),
+            ],
+        })
+    })
+}
+
+fn pipeline(device: &wgpu::Device, format: wgpu::TextureFormat) -> Arc<wgpu::RenderPipeline> {
+    EQUIRECT_PIPELINES.get_or_create(format, || {
+        let label = "san::equirect::Equirect";
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: crate::shader::builtin("equirect.wgsl", ShaderFeatures::empty()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+        })
+    })
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct EquirectParams {
+    face: u32,
+    _padding: [u32; 3],
+}
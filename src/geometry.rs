@@ -1,3 +1,5 @@
+use cgmath::{ElementWise, EuclideanSpace, Point3, Vector3};
+
 use crate::{
     gpu::{ToGpu, ToGpuBuffer},
     Vertex, VertexIndex,
@@ -7,19 +9,32 @@ use crate::{
 pub struct Geometry {
     pub(crate) vertices: Vec<Vertex>,
     pub(crate) indices: Option<Vec<VertexIndex>>,
+    pub(crate) topology: wgpu::PrimitiveTopology,
 }
 
 impl Geometry {
+    pub fn new(
+        vertices: Vec<Vertex>,
+        indices: Option<Vec<VertexIndex>>,
+        topology: wgpu::PrimitiveTopology,
+    ) -> Self {
+        Self {
+            vertices,
+            indices,
+            topology,
+        }
+    }
+
     pub fn plane(w: f32, h: f32) -> Self {
         let x = w * 0.5;
         let y = h * 0.5;
 
         #[rustfmt::skip]
         let vertices = vec![
-            Vertex::new([-x, -y, 0.], [0., 0., 1.]),  // top left
-            Vertex::new([ x, -y, 0.], [0., 0., 1.]),  // top right
-            Vertex::new([ x,  y, 0.], [0., 0., 1.]),  // bottom right
-            Vertex::new([-x,  y, 0.], [0., 0., 1.]),  // bottom left
+            Vertex::with_uv([-x, -y, 0.], [0., 0., 1.], [0., 1.]),  // top left
+            Vertex::with_uv([ x, -y, 0.], [0., 0., 1.], [1., 1.]),  // top right
+            Vertex::with_uv([ x,  y, 0.], [0., 0., 1.], [1., 0.]),  // bottom right
+            Vertex::with_uv([-x,  y, 0.], [0., 0., 1.], [0., 0.]),  // bottom left
         ];
 
         #[rustfmt::skip]
@@ -28,17 +43,132 @@ impl Geometry {
             0, 2, 3,
         ];
 
-        Self {
+        Self::new(
             vertices,
-            indices: Some(indices),
+            Some(indices),
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+
+    pub fn cuboid(w: f32, h: f32, d: f32) -> Self {
+        let half = Vector3::new(w * 0.5, h * 0.5, d * 0.5);
+
+        // (normal, u, v) with u x v = normal, so that faces wind counter-clockwise
+        #[rustfmt::skip]
+        let faces = [
+            ([ 1.,  0.,  0.], [ 0., 0., -1.], [0., 1.,  0.]),
+            ([-1.,  0.,  0.], [ 0., 0.,  1.], [0., 1.,  0.]),
+            ([ 0.,  1.,  0.], [ 1., 0.,  0.], [0., 0., -1.]),
+            ([ 0., -1.,  0.], [ 1., 0.,  0.], [0., 0.,  1.]),
+            ([ 0.,  0.,  1.], [ 1., 0.,  0.], [0., 1.,  0.]),
+            ([ 0.,  0., -1.], [-1., 0.,  0.], [0., 1.,  0.]),
+        ];
+
+        let mut vertices = Vec::with_capacity(24);
+        let mut indices = Vec::with_capacity(36);
+        for (normal, u, v) in faces {
+            let (n, u, v) = (Vector3::from(normal), Vector3::from(u), Vector3::from(v));
+            let base = vertices.len() as VertexIndex;
+
+            for (su, sv) in [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)] {
+                let position = (n + u * su + v * sv).mul_element_wise(half);
+                let uv = [(su + 1.) * 0.5, (1. - sv) * 0.5];
+                vertices.push(Vertex::with_uv(position.into(), normal, uv));
+            }
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
         }
+
+        Self::new(
+            vertices,
+            Some(indices),
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+
+    /// UV sphere around the y axis.
+    pub fn sphere(radius: f32, width_segments: u32, height_segments: u32) -> Self {
+        let width_segments = width_segments.max(3);
+        let height_segments = height_segments.max(2);
+
+        let mut vertices = Vec::new();
+        for iy in 0..=height_segments {
+            let v = iy as f32 / height_segments as f32;
+            let theta = v * std::f32::consts::PI;
+            for ix in 0..=width_segments {
+                let u = ix as f32 / width_segments as f32;
+                let phi = u * std::f32::consts::TAU;
+                let normal = [
+                    -phi.cos() * theta.sin(),
+                    theta.cos(),
+                    phi.sin() * theta.sin(),
+                ];
+                let position = normal.map(|n| n * radius);
+                vertices.push(Vertex::with_uv(position, normal, [u, v]));
+            }
+        }
+
+        let row = width_segments + 1;
+        let mut indices = Vec::new();
+        for iy in 0..height_segments {
+            for ix in 0..width_segments {
+                let a = iy * row + ix + 1;
+                let b = iy * row + ix;
+                let c = (iy + 1) * row + ix;
+                let d = (iy + 1) * row + ix + 1;
+
+                if iy != 0 {
+                    indices.extend([a, b, d]);
+                }
+                if iy != height_segments - 1 {
+                    indices.extend([b, c, d]);
+                }
+            }
+        }
+
+        Self::new(
+            vertices,
+            Some(indices),
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+
+    /// Each pair of points composes a separate line segment.
+    pub fn lines(points: &[[f32; 3]]) -> Self {
+        Self::from_points(points, wgpu::PrimitiveTopology::LineList)
+    }
+
+    /// Consecutive points are connected into a single open line.
+    pub fn polyline(points: &[[f32; 3]]) -> Self {
+        Self::from_points(points, wgpu::PrimitiveTopology::LineStrip)
+    }
+
+    pub fn points(points: &[[f32; 3]]) -> Self {
+        Self::from_points(points, wgpu::PrimitiveTopology::PointList)
+    }
+
+    fn from_points(points: &[[f32; 3]], topology: wgpu::PrimitiveTopology) -> Self {
+        let vertices = points
+            .iter()
+            .map(|&p| Vertex::new(p, [0., 0., 0.]))
+            .collect();
+
+        Self::new(vertices, None, topology)
+    }
+
+    pub fn topology(&self) -> wgpu::PrimitiveTopology {
+        self.topology
     }
 }
 
 impl ToGpu for Geometry {
     type Target = GeometryGpuData;
 
-    fn to_gpu(&self, device: &wgpu::Device, _format: wgpu::TextureFormat) -> Self::Target {
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
         Self::Target {
             vertices: self.vertices.as_slice().to_gpu_buffer(device),
             vertices_len: self.vertices.len() as u32,
@@ -47,6 +177,7 @@ impl ToGpu for Geometry {
                 .as_ref()
                 .map(|i| i.as_slice().to_gpu_buffer(device)),
             indices_len: self.indices.as_ref().map(|i| i.len()).unwrap_or_default() as u32,
+            center: bounds_center(self.vertices.iter().map(Vertex::position)),
         }
     }
 }
@@ -56,4 +187,25 @@ pub struct GeometryGpuData {
     pub(crate) vertices_len: u32,
     pub(crate) indices: Option<wgpu::Buffer>,
     pub(crate) indices_len: u32,
+    /// Center of the bounding box, used to sort meshes by depth.
+    pub(crate) center: Point3<f32>,
+}
+
+/// Center of the axis aligned box around `points`, the origin if there are none.
+pub(crate) fn bounds_center<I>(points: I) -> Point3<f32>
+where
+    I: IntoIterator<Item = [f32; 3]>,
+{
+    let mut points = points.into_iter().map(Point3::from);
+    let Some(first) = points.next() else {
+        return Point3::new(0., 0., 0.);
+    };
+    let (min, max) = points.fold((first, first), |(min, max), p| {
+        (
+            Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+            Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+        )
+    });
+
+    min.midpoint(max)
 }
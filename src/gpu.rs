@@ -1,4 +1,7 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
 
 use wgpu::util::DeviceExt;
 
@@ -7,7 +10,12 @@ use crate::{InstanceRaw, Vertex, VertexIndex};
 pub trait ToGpu {
     type Target;
 
-    fn to_gpu(&self, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self::Target;
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Self::Target;
 }
 
 pub struct GpuCached<T>
@@ -29,7 +37,20 @@ where
         }
     }
 
-    pub fn to_gpu(&self, device: &wgpu::Device, format: wgpu::TextureFormat) -> Arc<T::Target> {
+    /// Already uploaded data, `to_gpu` returns `gpu_data` until the base is modified.
+    pub fn with_gpu_data(base: T, gpu_data: Arc<T::Target>) -> Self {
+        Self {
+            base,
+            gpu_data: RwLock::new(Some(gpu_data)),
+        }
+    }
+
+    pub fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Arc<T::Target> {
         {
             let gpu_data = self.gpu_data.read().unwrap();
             if let Some(v) = gpu_data.as_ref() {
@@ -41,12 +62,29 @@ where
         match gpu_data_mut.as_ref() {
             Some(v) => Arc::clone(v),
             None => {
-                let data = Arc::new(self.base.to_gpu(device, format));
+                let data = Arc::new(self.base.to_gpu(device, queue, format));
                 *gpu_data_mut = Some(Arc::clone(&data));
                 data
             }
         }
     }
+
+    /// Mutable access to the base data drops the cached GPU data.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.gpu_data.get_mut().unwrap().take();
+        &mut self.base
+    }
+}
+
+impl<T> Deref for GpuCached<T>
+where
+    T: ToGpu,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
 }
 
 pub trait ToGpuBuffer {
@@ -78,7 +116,8 @@ impl ToGpuBuffer for &[InstanceRaw] {
         device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Instance Buffer"),
             contents: bytemuck::cast_slice(self),
-            usage: wgpu::BufferUsages::VERTEX,
+            // rewritten when the world matrix changes
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         })
     }
 }
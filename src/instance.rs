@@ -1,4 +1,8 @@
-use cgmath::{Matrix4, Quaternion, Vector3, Zero};
+use std::sync::Mutex;
+
+use cgmath::{Matrix, Matrix3, Matrix4, Quaternion, SquareMatrix, Vector3, Zero};
+
+use crate::gpu::{ToGpu, ToGpuBuffer};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Instance {
@@ -13,14 +17,41 @@ impl Instance {
         self
     }
 
+    /// Scale, then rotation, then translation.
+    pub fn matrix(self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.0, self.scale.1, self.scale.2)
+    }
+
     pub fn to_raw(self) -> InstanceRaw {
         InstanceRaw {
-            model: (Matrix4::from_translation(self.position)
-                * Matrix4::from(self.rotation)
-                * Matrix4::from_nonuniform_scale(self.scale.0, self.scale.1, self.scale.2))
+            model: self.matrix().into(),
+            // inverse transpose of the upper 3x3 of the model matrix
+            normal: (Matrix3::from(self.rotation)
+                * Matrix3::from_diagonal(Vector3::new(
+                    1. / self.scale.0,
+                    1. / self.scale.1,
+                    1. / self.scale.2,
+                )))
             .into(),
         }
     }
+
+    /// Like [`to_raw`](Self::to_raw) for an instance placed by `world` afterwards.
+    pub(crate) fn to_world_raw(self, world: &Matrix4<f32>) -> InstanceRaw {
+        let raw = self.to_raw();
+        let world_normal =
+            Matrix3::from_cols(world.x.truncate(), world.y.truncate(), world.z.truncate())
+                .invert()
+                .map(|m| m.transpose())
+                .unwrap_or_else(Matrix3::identity);
+
+        InstanceRaw {
+            model: (world * Matrix4::from(raw.model)).into(),
+            normal: (world_normal * Matrix3::from(raw.normal)).into(),
+        }
+    }
 }
 
 impl Default for Instance {
@@ -36,12 +67,20 @@ impl Default for Instance {
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceRaw {
-    model: [[f32; 4]; 4],
+    pub(crate) model: [[f32; 4]; 4],
+    normal: [[f32; 3]; 3],
 }
 
 impl InstanceRaw {
-    const ATTRIBS: [wgpu::VertexAttribute; 4] =
-        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4, 3 => Float32x4];
+    const ATTRIBS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+        9 => Float32x3,
+        10 => Float32x3,
+        11 => Float32x3,
+    ];
 
     pub(crate) fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
@@ -51,3 +90,81 @@ impl InstanceRaw {
         }
     }
 }
+
+/// Instances of a mesh, uploaded in the space of the node it is attached to.
+pub(crate) struct Instances(pub(crate) Vec<Instance>);
+
+impl ToGpu for Instances {
+    type Target = InstancesGpuData;
+
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        let raw: Vec<_> = self.0.iter().map(|i| i.to_raw()).collect();
+        let sum = self
+            .0
+            .iter()
+            .fold(Matrix4::zero(), |sum, instance| sum + instance.matrix());
+
+        Self::Target::new(
+            raw.as_slice().to_gpu_buffer(device),
+            raw.len() as u32,
+            sum / self.0.len().max(1) as f32,
+        )
+    }
+}
+
+impl Instances {
+    /// Writes the instances placed by `world` into the buffer of `gpu_data`, unless it
+    /// already holds them.
+    pub(crate) fn write_world(
+        &self,
+        queue: &wgpu::Queue,
+        gpu_data: &InstancesGpuData,
+        world: Matrix4<f32>,
+    ) {
+        gpu_data.write_world(queue, world, |world| {
+            self.0.iter().map(|i| i.to_world_raw(world)).collect()
+        });
+    }
+}
+
+/// Vertex buffer stepped per instance, bound to slot 1.
+pub struct InstancesGpuData {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) len: u32,
+    /// Average of the instance matrices before the world matrix, it maps a point of the
+    /// geometry to the average of its instanced positions.
+    pub(crate) mean: Matrix4<f32>,
+    // world matrix the buffer was written with
+    world: Mutex<Matrix4<f32>>,
+}
+
+impl InstancesGpuData {
+    /// Buffer written with the identity world matrix.
+    pub(crate) fn new(buffer: wgpu::Buffer, len: u32, mean: Matrix4<f32>) -> Self {
+        Self {
+            buffer,
+            len,
+            mean,
+            world: Mutex::new(Matrix4::identity()),
+        }
+    }
+
+    /// Writes the instances returned by `placed` for `world`, unless the buffer was last
+    /// written with the same world matrix.
+    pub(crate) fn write_world<T, F>(&self, queue: &wgpu::Queue, world: Matrix4<f32>, placed: F)
+    where
+        T: bytemuck::Pod,
+        F: FnOnce(&Matrix4<f32>) -> Vec<T>,
+    {
+        let mut written = self.world.lock().unwrap();
+        if *written != world {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&placed(&world)));
+            *written = world;
+        }
+    }
+}
@@ -1,4 +1,7 @@
 #[warn(clippy::all)]
+mod background;
+pub use background::Background;
+
 pub mod camera;
 
 pub mod color;
@@ -7,30 +10,60 @@ pub use color::{Rgb, Rgba};
 mod common;
 pub use common::AsAny;
 
+pub mod effect;
+pub use effect::Effect;
+
+mod equirect;
+
 pub mod geometry;
 
 pub(crate) mod gpu;
 
 mod instance;
-pub use instance::{Instance, InstanceRaw};
+pub use instance::{Instance, InstanceRaw, InstancesGpuData};
+
+pub mod light;
+pub mod line;
+pub use line::Line2;
 
 pub mod material;
 
 pub mod mesh;
 pub use mesh::{Mesh, MeshBase};
 
+mod mipmap;
+
+pub mod node;
+
+mod oit;
+pub use oit::Transparency;
+
 mod params;
 
 mod pipeline;
+pub use pipeline::PipelineKey;
+
+mod render_target;
+pub use render_target::RenderTarget;
 
 mod renderer;
 pub use renderer::{WGPURenderer, WGPURendererOption};
 
 mod scene;
-pub use scene::Scene;
+pub use scene::{Scene, SceneError};
+
+pub mod shader;
+
+mod shadow;
+
+mod slots;
+
+pub mod texture;
+pub use texture::{CubeTexture, Sampler, Texture};
 
 mod vertex;
 pub use vertex::{Vertex, VertexIndex};
 // re-export
+pub use cgmath;
 pub use wgpu;
 pub use winit;
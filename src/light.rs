@@ -0,0 +1,618 @@
+use std::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use cgmath::{
+    Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform, Vector3, Vector4,
+};
+
+use crate::{camera::OPENGL_TO_WGPU_MATRIX, common::AsAny, scene::SceneID, Rgb};
+
+const KIND_AMBIENT: u32 = 0;
+const KIND_DIRECTIONAL: u32 = 1;
+const KIND_POINT: u32 = 2;
+const KIND_SPOT: u32 = 3;
+const KIND_HEMISPHERE: u32 = 4;
+
+pub trait Light: AsAny {
+    fn to_raw(&self) -> LightRaw;
+
+    /// Shadow maps of the light as seen from a camera with `view_proj` at `eye`.
+    fn to_shadow_raws(&self, _view_proj: &Matrix4<f32>, _eye: Point3<f32>) -> Vec<ShadowRaw> {
+        Vec::new()
+    }
+}
+
+/// Shadow settings of a [`DirectionalLight`], rendered as cascaded shadow maps.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalShadow {
+    /// Subtracted from the receiver depth in light clip space.
+    pub bias: f32,
+    /// Offset of the receiver along its normal in world units.
+    pub normal_bias: f32,
+    /// Number of cascades, each using one shadow map.
+    pub cascades: u32,
+    /// Distance from the camera up to which shadows are rendered.
+    pub max_distance: f32,
+    /// Blend between uniform (0) and logarithmic (1) cascade splits.
+    pub split_lambda: f32,
+}
+
+impl Default for DirectionalShadow {
+    fn default() -> Self {
+        Self {
+            bias: 0.0005,
+            normal_bias: 0.02,
+            cascades: 3,
+            max_distance: 50.,
+            split_lambda: 0.7,
+        }
+    }
+}
+
+/// Shadow settings of a [`SpotLight`].
+#[derive(Debug, Clone, Copy)]
+pub struct SpotShadow {
+    /// Subtracted from the receiver depth in light clip space.
+    pub bias: f32,
+    /// Offset of the receiver along its normal in world units.
+    pub normal_bias: f32,
+    pub near: f32,
+    /// Used when the light has no range.
+    pub far: f32,
+}
+
+/// Shadow settings of a [`PointLight`], rendered as six cube faces storing linear distance.
+#[derive(Debug, Clone, Copy)]
+pub struct PointShadow {
+    /// Subtracted from the receiver distance to the light in world units.
+    pub bias: f32,
+    /// Offset of the receiver along its normal in world units.
+    pub normal_bias: f32,
+    pub near: f32,
+    /// Used when the light has no range.
+    pub far: f32,
+}
+
+impl Default for PointShadow {
+    fn default() -> Self {
+        Self {
+            bias: 0.05,
+            normal_bias: 0.02,
+            near: 0.1,
+            far: 100.,
+        }
+    }
+}
+
+impl Default for SpotShadow {
+    fn default() -> Self {
+        Self {
+            bias: 0.0001,
+            normal_bias: 0.02,
+            near: 0.1,
+            far: 100.,
+        }
+    }
+}
+
+/// Lights every surface equally, regardless of its orientation.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientLight {
+    pub color: Rgb,
+    pub intensity: f32,
+}
+
+impl AmbientLight {
+    pub fn new(color: Rgb, intensity: f32) -> Self {
+        Self { color, intensity }
+    }
+}
+
+/// Infinitely far away light, such as the sun.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    pub color: Rgb,
+    pub intensity: f32,
+    /// Direction the light travels in.
+    pub direction: Vector3<f32>,
+    pub shadow: Option<DirectionalShadow>,
+}
+
+impl DirectionalLight {
+    pub fn new(color: Rgb, intensity: f32, direction: Vector3<f32>) -> Self {
+        Self {
+            color,
+            intensity,
+            direction,
+            shadow: None,
+        }
+    }
+}
+
+/// Light emitted from a single point in all directions.
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub color: Rgb,
+    pub intensity: f32,
+    pub position: Point3<f32>,
+    /// Distance where the light fades out completely, 0 for no limit.
+    pub range: f32,
+    /// Exponent of the distance falloff, 2 is physically correct.
+    pub decay: f32,
+    pub shadow: Option<PointShadow>,
+}
+
+impl PointLight {
+    pub fn new(color: Rgb, intensity: f32, position: Point3<f32>) -> Self {
+        Self {
+            color,
+            intensity,
+            position,
+            range: 0.,
+            decay: 2.,
+            shadow: None,
+        }
+    }
+}
+
+/// Point light restricted to a cone.
+#[derive(Debug, Clone, Copy)]
+pub struct SpotLight {
+    pub color: Rgb,
+    pub intensity: f32,
+    pub position: Point3<f32>,
+    pub direction: Vector3<f32>,
+    /// Distance where the light fades out completely, 0 for no limit.
+    pub range: f32,
+    /// Exponent of the distance falloff, 2 is physically correct.
+    pub decay: f32,
+    /// Half angle of the fully lit cone in degrees.
+    pub inner_angle: f32,
+    /// Half angle of the cone in degrees, outside of which nothing is lit.
+    pub outer_angle: f32,
+    pub shadow: Option<SpotShadow>,
+}
+
+impl SpotLight {
+    pub fn new(
+        color: Rgb,
+        intensity: f32,
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        angle: f32,
+    ) -> Self {
+        Self {
+            color,
+            intensity,
+            position,
+            direction,
+            range: 0.,
+            decay: 2.,
+            inner_angle: angle,
+            outer_angle: angle,
+            shadow: None,
+        }
+    }
+}
+
+/// Ambient light blended between a sky and a ground color by the surface orientation.
+#[derive(Debug, Clone, Copy)]
+pub struct HemisphereLight {
+    pub sky_color: Rgb,
+    pub ground_color: Rgb,
+    pub intensity: f32,
+    pub up: Vector3<f32>,
+}
+
+impl HemisphereLight {
+    pub fn new(sky_color: Rgb, ground_color: Rgb, intensity: f32) -> Self {
+        Self {
+            sky_color,
+            ground_color,
+            intensity,
+            up: Vector3::unit_y(),
+        }
+    }
+}
+
+macro_rules! impl_as_any {
+    ($($t:ty),*) => {
+        $(
+            impl AsAny for $t {
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_as_any!(
+    AmbientLight,
+    DirectionalLight,
+    PointLight,
+    SpotLight,
+    HemisphereLight
+);
+
+impl Light for AmbientLight {
+    fn to_raw(&self) -> LightRaw {
+        LightRaw {
+            kind: KIND_AMBIENT,
+            ..LightRaw::new(self.color, self.intensity)
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn to_raw(&self) -> LightRaw {
+        LightRaw {
+            direction: self.direction.normalize().into(),
+            kind: KIND_DIRECTIONAL,
+            ..LightRaw::new(self.color, self.intensity)
+        }
+    }
+
+    fn to_shadow_raws(&self, view_proj: &Matrix4<f32>, eye: Point3<f32>) -> Vec<ShadowRaw> {
+        let Some(shadow) = self.shadow else {
+            return Vec::new();
+        };
+        let Some(inv_view_proj) = view_proj.invert() else {
+            return Vec::new();
+        };
+
+        let unproject = |x: f32, y: f32, z: f32| {
+            let p = inv_view_proj * Vector4::new(x, y, z, 1.);
+            p.truncate() / p.w
+        };
+        let edges = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)]
+            .map(|(x, y)| (unproject(x, y, 0.), unproject(x, y, 1.)));
+
+        let eye = eye.to_vec();
+        let center = |t: usize| edges.iter().map(|e| [e.0, e.1][t]).sum::<Vector3<f32>>() / 4.;
+        let near = (center(0) - eye).magnitude();
+        let far = (center(1) - eye).magnitude();
+        let max_distance = shadow.max_distance.min(far);
+
+        let cascades = shadow.cascades.max(1);
+        let split = |i: u32| {
+            let ratio = i as f32 / cascades as f32;
+            let log = near * (max_distance / near).powf(ratio);
+            let uniform = near + (max_distance - near) * ratio;
+            shadow.split_lambda * log + (1. - shadow.split_lambda) * uniform
+        };
+
+        let direction = self.direction.normalize();
+        let up = if direction.y.abs() > 0.99 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+
+        (0..cascades)
+            .map(|i| {
+                // depth grows linearly along each frustum edge
+                let corners: Vec<_> = [split(i), split(i + 1)]
+                    .into_iter()
+                    .flat_map(|d| {
+                        let t = (d - near) / (far - near);
+                        edges.map(|(n, f)| n + (f - n) * t)
+                    })
+                    .collect();
+
+                let center = corners.iter().sum::<Vector3<f32>>() / corners.len() as f32;
+                let radius = corners
+                    .iter()
+                    .map(|c| (c - center).magnitude())
+                    .fold(0., f32::max);
+
+                let view = Matrix4::look_at_rh(
+                    Point3::from_vec(center - direction * radius),
+                    Point3::from_vec(center),
+                    up,
+                );
+                // casters up to max_distance in front of the cascade are kept
+                let proj = cgmath::ortho(
+                    -radius,
+                    radius,
+                    -radius,
+                    radius,
+                    -shadow.max_distance,
+                    2. * radius,
+                );
+
+                ShadowRaw::new(
+                    OPENGL_TO_WGPU_MATRIX * proj * view,
+                    shadow.bias,
+                    shadow.normal_bias,
+                )
+            })
+            .collect()
+    }
+}
+
+impl Light for PointLight {
+    fn to_raw(&self) -> LightRaw {
+        LightRaw {
+            position: self.position.into(),
+            range: self.range,
+            decay: self.decay,
+            kind: KIND_POINT,
+            ..LightRaw::new(self.color, self.intensity)
+        }
+    }
+
+    fn to_shadow_raws(&self, _view_proj: &Matrix4<f32>, _eye: Point3<f32>) -> Vec<ShadowRaw> {
+        let Some(shadow) = self.shadow else {
+            return Vec::new();
+        };
+
+        let far = if self.range > 0. {
+            self.range
+        } else {
+            shadow.far
+        };
+        let proj = cgmath::perspective(Deg(90.), 1., shadow.near, far);
+
+        // +X, -X, +Y, -Y, +Z, -Z, the order the shader picks faces in
+        let faces = [
+            (Vector3::unit_x(), -Vector3::unit_y()),
+            (-Vector3::unit_x(), -Vector3::unit_y()),
+            (Vector3::unit_y(), Vector3::unit_z()),
+            (-Vector3::unit_y(), -Vector3::unit_z()),
+            (Vector3::unit_z(), -Vector3::unit_y()),
+            (-Vector3::unit_z(), -Vector3::unit_y()),
+        ];
+
+        faces
+            .into_iter()
+            .map(|(direction, up)| {
+                let view = Matrix4::look_at_rh(self.position, self.position + direction, up);
+                ShadowRaw {
+                    light_position: self.position.into(),
+                    far,
+                    ..ShadowRaw::new(
+                        OPENGL_TO_WGPU_MATRIX * proj * view,
+                        shadow.bias,
+                        shadow.normal_bias,
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+impl Light for SpotLight {
+    fn to_raw(&self) -> LightRaw {
+        let inner = self.inner_angle.min(self.outer_angle);
+
+        LightRaw {
+            position: self.position.into(),
+            range: self.range,
+            direction: self.direction.normalize().into(),
+            decay: self.decay,
+            kind: KIND_SPOT,
+            // nudge the inner cone so that the smoothstep stays defined
+            cone_cos: [
+                inner.to_radians().cos() + 1e-4,
+                self.outer_angle.to_radians().cos(),
+            ],
+            ..LightRaw::new(self.color, self.intensity)
+        }
+    }
+
+    fn to_shadow_raws(&self, _view_proj: &Matrix4<f32>, _eye: Point3<f32>) -> Vec<ShadowRaw> {
+        let Some(shadow) = self.shadow else {
+            return Vec::new();
+        };
+
+        let direction = self.direction.normalize();
+        let up = if direction.y.abs() > 0.99 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+        let far = if self.range > 0. {
+            self.range
+        } else {
+            shadow.far
+        };
+
+        let view = Matrix4::look_at_rh(self.position, self.position + direction, up);
+        let fovy = Deg((2. * self.outer_angle).clamp(1., 179.));
+        let proj = cgmath::perspective(fovy, 1., shadow.near, far);
+
+        vec![ShadowRaw::new(
+            OPENGL_TO_WGPU_MATRIX * proj * view,
+            shadow.bias,
+            shadow.normal_bias,
+        )]
+    }
+}
+
+impl Light for HemisphereLight {
+    fn to_raw(&self) -> LightRaw {
+        LightRaw {
+            direction: self.up.normalize().into(),
+            ground_color: self.ground_color.into(),
+            kind: KIND_HEMISPHERE,
+            ..LightRaw::new(self.sky_color, self.intensity)
+        }
+    }
+}
+
+/// Raws of `light` attached to a node with the `world` matrix. Shadows of point lights only
+/// move with the node, since the shader picks their faces along the world axes.
+pub(crate) fn world_raws(
+    light: &dyn Light,
+    world: &Matrix4<f32>,
+    view_proj: &Matrix4<f32>,
+    eye: Point3<f32>,
+) -> (LightRaw, Vec<ShadowRaw>) {
+    let mut raw = light.to_raw();
+    let local_position = Point3::from(raw.position);
+    let position = world.transform_point(local_position);
+    raw.position = position.into();
+    raw.direction = world
+        .transform_vector(Vector3::from(raw.direction))
+        .normalize()
+        .into();
+
+    let Some(inverse) = world.invert() else {
+        return (raw, Vec::new());
+    };
+    let shadows = if raw.is_point() {
+        let offset = Matrix4::from_translation(local_position - position);
+        light
+            .to_shadow_raws(view_proj, eye)
+            .into_iter()
+            .map(|shadow| ShadowRaw {
+                view_proj: (Matrix4::from(shadow.view_proj) * offset).into(),
+                light_position: position.into(),
+                ..shadow
+            })
+            .collect()
+    } else {
+        // computed in the space of the node
+        light
+            .to_shadow_raws(&(view_proj * world), inverse.transform_point(eye))
+            .into_iter()
+            .map(|shadow| ShadowRaw {
+                view_proj: (Matrix4::from(shadow.view_proj) * inverse).into(),
+                ..shadow
+            })
+            .collect()
+    };
+
+    (raw, shadows)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightRaw {
+    color: [f32; 3],
+    intensity: f32,
+    position: [f32; 3],
+    range: f32,
+    direction: [f32; 3],
+    decay: f32,
+    ground_color: [f32; 3],
+    kind: u32,
+    // cosines of the inner and outer cone angles
+    cone_cos: [f32; 2],
+    // range of the light's entries in the shadow buffer
+    pub(crate) shadow_index: u32,
+    pub(crate) shadow_count: u32,
+}
+
+impl LightRaw {
+    fn new(color: Rgb, intensity: f32) -> Self {
+        Self {
+            color: color.into(),
+            intensity,
+            position: [0.; 3],
+            range: 0.,
+            direction: [0., 0., -1.],
+            decay: 0.,
+            ground_color: [0.; 3],
+            kind: KIND_AMBIENT,
+            cone_cos: [0.; 2],
+            shadow_index: 0,
+            shadow_count: 0,
+        }
+    }
+
+    pub(crate) fn is_point(&self) -> bool {
+        self.kind == KIND_POINT
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowRaw {
+    pub(crate) view_proj: [[f32; 4]; 4],
+    // offset and scale of the shadow map tile in the atlas
+    pub(crate) atlas_rect: [f32; 4],
+    // the tile stores distance to the light divided by `far`, unless `far` is 0
+    pub(crate) light_position: [f32; 3],
+    pub(crate) far: f32,
+    bias: f32,
+    normal_bias: f32,
+    _padding: [f32; 2],
+}
+
+impl ShadowRaw {
+    fn new(view_proj: Matrix4<f32>, bias: f32, normal_bias: f32) -> Self {
+        Self {
+            view_proj: view_proj.into(),
+            atlas_rect: [0., 0., 1., 1.],
+            light_position: [0.; 3],
+            far: 0.,
+            bias,
+            normal_bias,
+            _padding: [0.; 2],
+        }
+    }
+}
+
+/// Handle of a light of type `L` in a [`Scene`](crate::Scene), invalid once the light is
+/// removed, see [`Scene::contains_light`](crate::Scene::contains_light).
+pub struct LightID<L> {
+    pub(crate) scene_id: SceneID,
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+    pub(crate) _phantom: PhantomData<L>,
+}
+
+impl<L> LightID<L> {
+    pub(crate) fn new(scene_id: SceneID, index: usize, generation: u32) -> Self {
+        Self {
+            scene_id,
+            index,
+            generation,
+            _phantom: Default::default(),
+        }
+    }
+}
+
+// not derived, which would require the bounds of `L`
+impl<L> Clone for LightID<L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L> Copy for LightID<L> {}
+
+impl<L> PartialEq for LightID<L> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.scene_id, self.index, self.generation)
+            == (other.scene_id, other.index, other.generation)
+    }
+}
+
+impl<L> Eq for LightID<L> {}
+
+impl<L> Hash for LightID<L> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.scene_id, self.index, self.generation).hash(state);
+    }
+}
+
+impl<L> fmt::Debug for LightID<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LightID")
+            .field("scene_id", &self.scene_id)
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
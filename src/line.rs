@@ -0,0 +1,422 @@
+use std::any::Any;
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform, Vector3};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    common::AsAny,
+    geometry::{bounds_center, Geometry},
+    gpu::{GpuCached, ToGpu, ToGpuBuffer},
+    material::{Material, RenderState, Side},
+    mesh::{MeshBase, MeshGpuData},
+    params::LocalParams,
+    pipeline::PipelineCache,
+    shader::ShaderFeatures,
+    Instance, InstancesGpuData, PipelineKey, Rgba, Transparency, Vertex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    Round,
+    /// Falls back to bevel where the miter would exceed the miter limit.
+    Miter,
+    Bevel,
+}
+
+/// Material for [`Line2`]. Width is in physical pixels, dash and gap sizes are in the units
+/// of the points, measured along the line before its transform and node place it.
+#[derive(Debug, Clone)]
+pub struct LineMaterial {
+    params: LineMaterialParams,
+    render_state: RenderState,
+}
+
+impl LineMaterial {
+    pub fn new(color: Rgba, width: f32) -> Self {
+        Self {
+            params: LineMaterialParams {
+                color: color.into(),
+                width,
+                dash_size: 0.,
+                gap_size: 0.,
+                dash_offset: 0.,
+                cap: LineCap::Round as u32,
+                join: LineJoin::Round as u32,
+                miter_limit: 4.,
+                dashed: 0,
+            },
+            render_state: RenderState::default().with_side(Side::Double),
+        }
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.params.cap = cap as u32;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.params.join = join as u32;
+        self
+    }
+
+    pub fn miter_limit(mut self, miter_limit: f32) -> Self {
+        self.params.miter_limit = miter_limit;
+        self
+    }
+
+    /// Panics unless `dash_size` is positive and `gap_size` is not negative, the dash
+    /// pattern repeats every `dash_size + gap_size`.
+    pub fn dashed(mut self, dash_size: f32, gap_size: f32) -> Self {
+        assert!(
+            dash_size > 0. && gap_size >= 0.,
+            "invalid dash size {dash_size} and gap size {gap_size}"
+        );
+        self.params.dash_size = dash_size;
+        self.params.gap_size = gap_size;
+        self.params.dashed = 1;
+        self
+    }
+
+    pub fn dash_offset(mut self, dash_offset: f32) -> Self {
+        self.params.dash_offset = dash_offset;
+        self
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+impl Material for LineMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline::<LineMaterialParams>(
+            device,
+            key,
+            "san::line::LineMaterial",
+            crate::shader::builtin("line2.wgsl", key.features),
+            &[Vertex::desc(), LineSegmentRaw::desc()],
+        )
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        self.params.buffer_bind_group(device)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineMaterialParams {
+    color: [f32; 4],
+    width: f32,
+    dash_size: f32,
+    gap_size: f32,
+    dash_offset: f32,
+    cap: u32,
+    join: u32,
+    miter_limit: f32,
+    dashed: u32,
+}
+
+impl LocalParams for LineMaterialParams {}
+
+/// Polyline drawn as screen-space quads, one instance per segment.
+pub struct Line2 {
+    geometry: GpuCached<Geometry>,
+    segments: GpuCached<LineSegments>,
+    material: GpuCached<LineMaterial>,
+    pipelines: PipelineCache<PipelineKey>,
+    render_order: i32,
+    transform: Matrix4<f32>,
+}
+
+impl Line2 {
+    pub fn new(points: &[[f32; 3]], material: LineMaterial) -> Self {
+        Self::with_colors(points, &[], material)
+    }
+
+    /// `colors` are per point and multiplied by the material color. Missing colors are
+    /// treated as white. A line whose last point equals the first is closed, joining its
+    /// last segment to the first.
+    pub fn with_colors(points: &[[f32; 3]], colors: &[Rgba], material: LineMaterial) -> Self {
+        Self {
+            geometry: GpuCached::new(Self::segment_geometry()),
+            segments: GpuCached::new(LineSegments::new(points, colors)),
+            material: GpuCached::new(material),
+            pipelines: PipelineCache::new(),
+            render_order: 0,
+            transform: Matrix4::identity(),
+        }
+    }
+
+    pub fn set_transform(&mut self, transform: Instance) {
+        self.transform = transform.matrix();
+    }
+
+    pub fn segments(&self) -> &[LineSegmentRaw] {
+        &self.segments.0
+    }
+
+    pub fn material(&self) -> &LineMaterial {
+        &self.material
+    }
+
+    /// Mutable access uploads the material parameters again, keeping its pipelines.
+    pub fn material_mut(&mut self) -> &mut LineMaterial {
+        self.material.get_mut()
+    }
+
+    pub fn render_order(&self) -> i32 {
+        self.render_order
+    }
+
+    /// See [`Mesh::set_render_order`](crate::Mesh::set_render_order).
+    pub fn set_render_order(&mut self, render_order: i32) {
+        self.render_order = render_order;
+    }
+
+    fn segment_geometry() -> Geometry {
+        #[rustfmt::skip]
+        let vertices = vec![
+            Vertex::new([-1.,  2., 0.], [0., 0., 1.]),
+            Vertex::new([ 1.,  2., 0.], [0., 0., 1.]),
+            Vertex::new([-1.,  1., 0.], [0., 0., 1.]),
+            Vertex::new([ 1.,  1., 0.], [0., 0., 1.]),
+            Vertex::new([-1.,  0., 0.], [0., 0., 1.]),
+            Vertex::new([ 1.,  0., 0.], [0., 0., 1.]),
+            Vertex::new([-1., -1., 0.], [0., 0., 1.]),
+            Vertex::new([ 1., -1., 0.], [0., 0., 1.]),
+        ];
+
+        #[rustfmt::skip]
+        let indices = vec![
+            0, 2, 1,
+            2, 3, 1,
+            2, 4, 3,
+            4, 5, 3,
+            4, 6, 5,
+            6, 7, 5,
+        ];
+
+        Geometry::new(
+            vertices,
+            Some(indices),
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+}
+
+impl AsAny for Line2 {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl MeshBase for Line2 {
+    fn gpu_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: Matrix4<f32>,
+        format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        transparency: Transparency,
+    ) -> MeshGpuData {
+        let geometry = self.geometry.to_gpu(device, queue, format);
+        let material = self.material.to_gpu(device, queue, format);
+        self.material.update_buffer(queue, &material.buffer);
+        let model = world * self.transform;
+        let instances = self.segments.to_gpu(device, queue, format);
+        self.segments.write_world(queue, &instances, model);
+
+        let render_state = self.material.render_state();
+        let mut features = ShaderFeatures::empty();
+        features.set(
+            ShaderFeatures::OIT,
+            render_state.transparent && transparency == Transparency::WeightedBlended,
+        );
+        let key = PipelineKey::new(format, self.geometry.topology)
+            .with_depth_format(depth_format)
+            .with_features(features)
+            .with_render_state(render_state);
+        let pipeline = self
+            .pipelines
+            .get_or_create(key, || self.material.render_pipeline(device, &key));
+
+        let center = (model * instances.mean).transform_point(Point3::origin());
+
+        MeshGpuData {
+            geometry,
+            material,
+            pipeline,
+            instances,
+            topology: self.geometry.topology,
+            cast_shadow: false,
+            receive_shadow: false,
+            transparent: render_state.transparent,
+            oit: features.contains(ShaderFeatures::OIT),
+            render_order: self.render_order,
+            center,
+        }
+    }
+}
+
+struct LineSegments(Vec<LineSegmentRaw>);
+
+impl LineSegments {
+    fn new(points: &[[f32; 3]], colors: &[Rgba]) -> Self {
+        let color = |i: usize| colors.get(i).copied().map(Into::into).unwrap_or([1.; 4]);
+
+        let n = points.len();
+        let closed = n > 2 && points[0] == points[n - 1];
+
+        let mut distance = 0.;
+        let segments = (0..n.saturating_sub(1))
+            .map(|i| {
+                let length = (Vector3::from(points[i + 1]) - Vector3::from(points[i])).magnitude();
+                let has_prev = i > 0 || closed;
+                let has_next = i + 2 < n || closed;
+                // the closing point is both points[0] and points[n - 1], its neighbours
+                // are points[n - 2] and points[1]
+                let prev = match i {
+                    0 if closed => n - 2,
+                    0 => 0,
+                    _ => i - 1,
+                };
+                let next = match i + 2 {
+                    j if j < n => j,
+                    _ if closed => 1,
+                    _ => i + 1,
+                };
+
+                let segment = LineSegmentRaw {
+                    prev: points[prev],
+                    start: points[i],
+                    end: points[i + 1],
+                    next: points[next],
+                    color_start: color(i),
+                    color_end: color(i + 1),
+                    distances: [distance, distance + length],
+                    flags: has_prev as u32 | (has_next as u32) << 1,
+                };
+                distance += length;
+                segment
+            })
+            .collect();
+
+        Self(segments)
+    }
+
+    /// Writes the segments with their points placed by `model` into the buffer of
+    /// `gpu_data`, unless it already holds them. Distances stay the ones along the
+    /// untransformed line.
+    fn write_world(&self, queue: &wgpu::Queue, gpu_data: &InstancesGpuData, model: Matrix4<f32>) {
+        gpu_data.write_world(queue, model, |model| {
+            let place = |p: [f32; 3]| model.transform_point(Point3::from(p)).into();
+            self.0
+                .iter()
+                .map(|s| LineSegmentRaw {
+                    prev: place(s.prev),
+                    start: place(s.start),
+                    end: place(s.end),
+                    next: place(s.next),
+                    ..*s
+                })
+                .collect()
+        });
+    }
+}
+
+impl ToGpu for LineSegments {
+    type Target = InstancesGpuData;
+
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        let points = self.0.iter().flat_map(|s| [s.start, s.end]);
+
+        // written with the points placed by the model matrix before drawing
+        Self::Target::new(
+            self.0.as_slice().to_gpu_buffer(device),
+            self.0.len() as u32,
+            Matrix4::from_translation(bounds_center(points).to_vec()),
+        )
+    }
+}
+
+/// Segment of a [`Line2`] as uploaded, with the neighbouring points used for the joins.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineSegmentRaw {
+    /// `start` if there is no segment before.
+    pub prev: [f32; 3],
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    /// `end` if there is no segment after.
+    pub next: [f32; 3],
+    pub color_start: [f32; 4],
+    pub color_end: [f32; 4],
+    /// Distance along the line at `start` and `end`, for dashing.
+    pub distances: [f32; 2],
+    /// Bit 0 is set if there is a segment before and bit 1 if there is one after.
+    pub flags: u32,
+}
+
+impl LineSegmentRaw {
+    const ATTRIBS: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        3 => Float32x3,
+        4 => Float32x3,
+        5 => Float32x3,
+        6 => Float32x3,
+        7 => Float32x4,
+        8 => Float32x4,
+        9 => Float32x2,
+        10 => Uint32,
+    ];
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as _,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+impl ToGpuBuffer for &[LineSegmentRaw] {
+    fn to_gpu_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Line Segment Buffer"),
+            contents: bytemuck::cast_slice(self),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        })
+    }
+}
@@ -1,16 +1,55 @@
-use crate::gpu::ToGpu;
+use crate::{gpu::ToGpu, shader::ShaderFeatures, PipelineKey};
 
 mod basic_material;
 pub use basic_material::BasicMaterial;
 
+mod lambert_material;
+pub use lambert_material::LambertMaterial;
+
+mod line_basic_material;
+pub use line_basic_material::LineBasicMaterial;
+
+mod phong_material;
+pub use phong_material::PhongMaterial;
+
+mod render_state;
+pub use render_state::{Blending, RenderState, Side};
+
+mod shader_material;
+pub use shader_material::{ShaderMaterial, UniformValue};
+
+mod standard_material;
+pub use standard_material::StandardMaterial;
+
 pub trait Material {
-    fn render_pipeline(
+    /// Creates the pipeline of the variant `key.features`.
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline;
+
+    /// Shader variant the material is drawn with.
+    fn features(&self) -> ShaderFeatures {
+        ShaderFeatures::empty()
+    }
+
+    fn render_state(&self) -> RenderState {
+        RenderState::default()
+    }
+
+    /// Whether the shader returns the `FragmentOutput` of the `output` chunk, so that
+    /// transparent meshes can be drawn with [`Transparency::WeightedBlended`](crate::Transparency::WeightedBlended).
+    fn supports_oit(&self) -> bool {
+        false
+    }
+
+    fn buffer_bind_group(
         &self,
         device: &wgpu::Device,
-        format: wgpu::TextureFormat,
-    ) -> wgpu::RenderPipeline;
+        queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup);
 
-    fn buffer_bind_group(&self, device: &wgpu::Device) -> (wgpu::Buffer, wgpu::BindGroup);
+    /// Called before every frame with the buffer returned by
+    /// [`buffer_bind_group`](Self::buffer_bind_group), to write parameters changed since
+    /// without recreating the bind group.
+    fn update_buffer(&self, _queue: &wgpu::Queue, _buffer: &wgpu::Buffer) {}
 }
 
 impl<M> ToGpu for M
@@ -19,31 +58,22 @@ where
 {
     type Target = MaterialGpuData;
 
-    fn to_gpu(&self, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self::Target {
-        let pipeline = self.render_pipeline(device, format);
-        let (buffer, bind_group) = self.buffer_bind_group(device);
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        let (buffer, bind_group) = self.buffer_bind_group(device, queue);
 
-        Self::Target::new(pipeline, buffer, bind_group)
+        Self::Target { buffer, bind_group }
     }
 }
 
+/// Parameters of a material on the GPU. The pipelines are cached by the mesh instead, so
+/// that they outlive uploading the parameters again.
 #[derive(Debug)]
 pub struct MaterialGpuData {
-    pub(crate) pipeline: wgpu::RenderPipeline,
-    _buffer: wgpu::Buffer,
+    pub(crate) buffer: wgpu::Buffer,
     pub(crate) bind_group: wgpu::BindGroup,
 }
-
-impl MaterialGpuData {
-    fn new(
-        pipeline: wgpu::RenderPipeline,
-        _buffer: wgpu::Buffer,
-        bind_group: wgpu::BindGroup,
-    ) -> Self {
-        Self {
-            pipeline,
-            _buffer,
-            bind_group,
-        }
-    }
-}
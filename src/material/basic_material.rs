@@ -1,9 +1,20 @@
-use super::Material;
-use crate::{params::LocalParams, Rgba};
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
 
+use super::{Material, RenderState};
+use crate::{
+    params::LocalParams,
+    shader::ShaderFeatures,
+    texture::{self, ColorSpace, Texture},
+    PipelineKey, Rgba,
+};
+
+/// Unlit material, optionally multiplied by a color map sampled with the vertex uvs.
 #[derive(Debug, Clone)]
 pub struct BasicMaterial {
     params: BasicMaterialParams,
+    map: Option<Texture>,
+    render_state: RenderState,
 }
 
 impl BasicMaterial {
@@ -12,33 +23,121 @@ impl BasicMaterial {
             params: BasicMaterialParams {
                 color: color.into(),
             },
+            map: None,
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn with_map(self, map: Texture) -> Self {
+        Self {
+            map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
         }
     }
 }
 
 impl Material for BasicMaterial {
-    fn render_pipeline(
-        &self,
-        device: &wgpu::Device,
-        format: wgpu::TextureFormat,
-    ) -> wgpu::RenderPipeline {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
         crate::pipeline::create_render_pipeline_common::<BasicMaterialParams>(
             device,
-            format,
+            key,
             "san::mesh::MeshBasicMaterial",
-            wgpu::ShaderSource::Wgsl(include_str!("../shaders/basic_mesh.wgsl").into()),
+            crate::shader::builtin("basic.wgsl", key.features),
         )
     }
 
-    fn buffer_bind_group(&self, device: &wgpu::Device) -> (wgpu::Buffer, wgpu::BindGroup) {
-        self.params.buffer_bind_group(device)
+    fn features(&self) -> ShaderFeatures {
+        let mut features = ShaderFeatures::empty();
+        features.set(ShaderFeatures::MAP, self.map.is_some());
+        features
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let map = self
+            .map
+            .clone()
+            .unwrap_or_else(|| Texture::solid([255; 4], ColorSpace::Srgb));
+        let texture = map.gpu_data(device, queue, wgpu::TextureFormat::Rgba8Unorm);
+        let sampler = map
+            .sampler()
+            .gpu_data(device, queue, wgpu::TextureFormat::Rgba8Unorm);
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Basic Material Params Buffer"),
+            contents: bytemuck::cast_slice(&[self.params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Basic Material Bind Group"),
+            layout: BasicMaterialParams::desc(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+            ],
+        });
+
+        (buffer, bind_group)
     }
 }
 
+static BASIC_MATERIAL_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct BasicMaterialParams {
     color: [f32; 4],
 }
 
-impl LocalParams for BasicMaterialParams {}
+impl LocalParams for BasicMaterialParams {
+    fn desc(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+        BASIC_MATERIAL_LAYOUT.get_or_init(|| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Basic Material Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    texture::sampler_layout_entry(1),
+                    texture::texture_layout_entry(2),
+                ],
+            })
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use super::{Material, RenderState};
+use crate::{params::LocalParams, PipelineKey, Rgb, Rgba};
+
+/// Diffuse only material lit by the scene lights.
+#[derive(Debug, Clone)]
+pub struct LambertMaterial {
+    params: LambertMaterialParams,
+    render_state: RenderState,
+}
+
+impl LambertMaterial {
+    pub fn new(color: Rgba) -> Self {
+        Self {
+            params: LambertMaterialParams {
+                color: color.into(),
+                emissive: [0.; 4],
+            },
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn emissive(mut self, emissive: Rgb) -> Self {
+        self.params.emissive = Rgba::new(emissive.r, emissive.g, emissive.b, 1.).into();
+        self
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+impl Material for LambertMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline_common::<LambertMaterialParams>(
+            device,
+            key,
+            "san::material::LambertMaterial",
+            crate::shader::builtin("lambert.wgsl", key.features),
+        )
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        self.params.buffer_bind_group(device)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LambertMaterialParams {
+    color: [f32; 4],
+    emissive: [f32; 4],
+}
+
+impl LocalParams for LambertMaterialParams {}
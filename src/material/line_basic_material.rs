@@ -0,0 +1,61 @@
+use super::{Material, RenderState};
+use crate::{params::LocalParams, PipelineKey, Rgba};
+
+#[derive(Debug, Clone)]
+pub struct LineBasicMaterial {
+    params: LineBasicMaterialParams,
+    render_state: RenderState,
+}
+
+impl LineBasicMaterial {
+    pub fn new(color: Rgba) -> Self {
+        Self {
+            params: LineBasicMaterialParams {
+                color: color.into(),
+            },
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+impl Material for LineBasicMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline_common::<LineBasicMaterialParams>(
+            device,
+            key,
+            "san::material::LineBasicMaterial",
+            crate::shader::builtin("basic_mesh.wgsl", key.features),
+        )
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        self.params.buffer_bind_group(device)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineBasicMaterialParams {
+    color: [f32; 4],
+}
+
+impl LocalParams for LineBasicMaterialParams {}
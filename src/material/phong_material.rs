@@ -0,0 +1,83 @@
+use super::{Material, RenderState};
+use crate::{params::LocalParams, PipelineKey, Rgb, Rgba};
+
+/// Blinn-Phong material lit by the scene lights.
+#[derive(Debug, Clone)]
+pub struct PhongMaterial {
+    params: PhongMaterialParams,
+    render_state: RenderState,
+}
+
+impl PhongMaterial {
+    pub fn new(color: Rgba) -> Self {
+        Self {
+            params: PhongMaterialParams {
+                color: color.into(),
+                specular: [0.07; 3],
+                shininess: 30.,
+                emissive: [0.; 4],
+            },
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn specular(mut self, specular: Rgb) -> Self {
+        self.params.specular = specular.into();
+        self
+    }
+
+    pub fn shininess(mut self, shininess: f32) -> Self {
+        self.params.shininess = shininess;
+        self
+    }
+
+    pub fn emissive(mut self, emissive: Rgb) -> Self {
+        self.params.emissive = Rgba::new(emissive.r, emissive.g, emissive.b, 1.).into();
+        self
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+impl Material for PhongMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline_common::<PhongMaterialParams>(
+            device,
+            key,
+            "san::material::PhongMaterial",
+            crate::shader::builtin("phong.wgsl", key.features),
+        )
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        self.params.buffer_bind_group(device)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PhongMaterialParams {
+    color: [f32; 4],
+    specular: [f32; 3],
+    shininess: f32,
+    emissive: [f32; 4],
+}
+
+impl LocalParams for PhongMaterialParams {}
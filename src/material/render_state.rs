@@ -0,0 +1,148 @@
+/// How the fragment color is combined with the color already in the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Blending {
+    /// Replaces the target, alpha is ignored.
+    Opaque,
+    /// Mixes by the fragment alpha.
+    #[default]
+    Alpha,
+    /// Like `Alpha` for colors already multiplied by their alpha.
+    Premultiplied,
+    /// Adds the color scaled by its alpha, for glows and particles.
+    Additive,
+    /// Multiplies the target by the color.
+    Multiply,
+    Custom(wgpu::BlendState),
+}
+
+impl Blending {
+    pub fn blend_state(self) -> Option<wgpu::BlendState> {
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        match self {
+            Self::Opaque => None,
+            Self::Alpha => Some(wgpu::BlendState::ALPHA_BLENDING),
+            Self::Premultiplied => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Self::Additive => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+            Self::Multiply => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::Src,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+            Self::Custom(state) => Some(state),
+        }
+    }
+}
+
+/// Faces of the triangles that are drawn, by their counter-clockwise winding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Side {
+    #[default]
+    Front,
+    Back,
+    /// Both faces, lit materials flip the normal of back faces.
+    Double,
+}
+
+impl Side {
+    pub fn cull_mode(self) -> Option<wgpu::Face> {
+        match self {
+            Self::Front => Some(wgpu::Face::Back),
+            Self::Back => Some(wgpu::Face::Front),
+            Self::Double => None,
+        }
+    }
+}
+
+/// Fixed function state a material is drawn with, part of the
+/// [`PipelineKey`](crate::PipelineKey).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderState {
+    pub blending: Blending,
+    pub side: Side,
+    /// `Line` and `Point` need the `POLYGON_MODE_LINE` and `POLYGON_MODE_POINT` device
+    /// features, triangles are filled without them.
+    pub polygon_mode: wgpu::PolygonMode,
+    pub write_mask: wgpu::ColorWrites,
+    /// Drawn after the opaque meshes, back to front and without writing depth. Follows
+    /// the blending, set for all but [`Blending::Opaque`] by
+    /// [`with_blending`](Self::with_blending) and the default.
+    pub transparent: bool,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            blending: Blending::default(),
+            side: Side::default(),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            write_mask: wgpu::ColorWrites::ALL,
+            transparent: Blending::default() != Blending::Opaque,
+        }
+    }
+}
+
+impl RenderState {
+    /// Also makes the state [`transparent`](Self::transparent) unless `blending` is
+    /// `Opaque`.
+    pub fn with_blending(self, blending: Blending) -> Self {
+        Self {
+            blending,
+            transparent: blending != Blending::Opaque,
+            ..self
+        }
+    }
+
+    pub fn with_side(self, side: Side) -> Self {
+        Self { side, ..self }
+    }
+
+    pub fn with_polygon_mode(self, polygon_mode: wgpu::PolygonMode) -> Self {
+        Self {
+            polygon_mode,
+            ..self
+        }
+    }
+
+    pub fn with_write_mask(self, write_mask: wgpu::ColorWrites) -> Self {
+        Self { write_mask, ..self }
+    }
+
+    /// Overrides the transparency derived from the blending, such as for blended meshes
+    /// that should still write depth.
+    pub fn with_transparent(self, transparent: bool) -> Self {
+        Self {
+            transparent,
+            ..self
+        }
+    }
+
+    /// The polygon mode if `device` supports it, else `Fill`.
+    pub(crate) fn supported_polygon_mode(&self, device: &wgpu::Device) -> wgpu::PolygonMode {
+        let feature = match self.polygon_mode {
+            wgpu::PolygonMode::Fill => return wgpu::PolygonMode::Fill,
+            wgpu::PolygonMode::Line => wgpu::Features::POLYGON_MODE_LINE,
+            wgpu::PolygonMode::Point => wgpu::Features::POLYGON_MODE_POINT,
+        };
+        if device.features().contains(feature) {
+            self.polygon_mode
+        } else {
+            log::warn!("{feature:?} is not supported by the device, filling triangles");
+            wgpu::PolygonMode::Fill
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use cgmath::{Matrix4, Point3, Vector2, Vector3, Vector4};
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
+
+use super::{Material, RenderState};
+use crate::{
+    shader::{self, Composer, ShaderError, ShaderFeatures},
+    texture::{self, Texture},
+    PipelineKey, Rgb, Rgba,
+};
+
+const PRELUDE: [&str; 4] = ["camera", "lights", "vertex", "instance"];
+
+/// Value of a uniform declared on a [`ShaderMaterial`], its variant decides the WGSL type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    F32(f32),
+    I32(i32),
+    U32(u32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat4([[f32; 4]; 4]),
+}
+
+impl UniformValue {
+    fn wgsl_type(&self) -> &'static str {
+        match self {
+            Self::F32(_) => "f32",
+            Self::I32(_) => "i32",
+            Self::U32(_) => "u32",
+            Self::Vec2(_) => "vec2<f32>",
+            Self::Vec3(_) => "vec3<f32>",
+            Self::Vec4(_) => "vec4<f32>",
+            Self::Mat4(_) => "mat4x4<f32>",
+        }
+    }
+
+    // alignment in a uniform buffer, from the WGSL memory layout rules
+    fn align(&self) -> usize {
+        match self {
+            Self::F32(_) | Self::I32(_) | Self::U32(_) => 4,
+            Self::Vec2(_) => 8,
+            Self::Vec3(_) | Self::Vec4(_) | Self::Mat4(_) => 16,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::F32(v) => bytemuck::bytes_of(v),
+            Self::I32(v) => bytemuck::bytes_of(v),
+            Self::U32(v) => bytemuck::bytes_of(v),
+            Self::Vec2(v) => bytemuck::bytes_of(v),
+            Self::Vec3(v) => bytemuck::bytes_of(v),
+            Self::Vec4(v) => bytemuck::bytes_of(v),
+            Self::Mat4(v) => bytemuck::bytes_of(v),
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(v: f32) -> Self {
+        Self::F32(v)
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(v: i32) -> Self {
+        Self::I32(v)
+    }
+}
+
+impl From<u32> for UniformValue {
+    fn from(v: u32) -> Self {
+        Self::U32(v)
+    }
+}
+
+impl From<[f32; 2]> for UniformValue {
+    fn from(v: [f32; 2]) -> Self {
+        Self::Vec2(v)
+    }
+}
+
+impl From<[f32; 3]> for UniformValue {
+    fn from(v: [f32; 3]) -> Self {
+        Self::Vec3(v)
+    }
+}
+
+impl From<[f32; 4]> for UniformValue {
+    fn from(v: [f32; 4]) -> Self {
+        Self::Vec4(v)
+    }
+}
+
+impl From<[[f32; 4]; 4]> for UniformValue {
+    fn from(v: [[f32; 4]; 4]) -> Self {
+        Self::Mat4(v)
+    }
+}
+
+impl From<Vector2<f32>> for UniformValue {
+    fn from(v: Vector2<f32>) -> Self {
+        Self::Vec2(v.into())
+    }
+}
+
+impl From<Vector3<f32>> for UniformValue {
+    fn from(v: Vector3<f32>) -> Self {
+        Self::Vec3(v.into())
+    }
+}
+
+impl From<Point3<f32>> for UniformValue {
+    fn from(v: Point3<f32>) -> Self {
+        Self::Vec3(v.into())
+    }
+}
+
+impl From<Vector4<f32>> for UniformValue {
+    fn from(v: Vector4<f32>) -> Self {
+        Self::Vec4(v.into())
+    }
+}
+
+impl From<Matrix4<f32>> for UniformValue {
+    fn from(v: Matrix4<f32>) -> Self {
+        Self::Mat4(v.into())
+    }
+}
+
+impl From<Rgb> for UniformValue {
+    fn from(v: Rgb) -> Self {
+        Self::Vec3(v.into())
+    }
+}
+
+impl From<Rgba> for UniformValue {
+    fn from(v: Rgba) -> Self {
+        Self::Vec4(v.into())
+    }
+}
+
+/// Material drawn by user WGSL, with the vertex layout, globals and pipeline cache of the
+/// built-in materials.
+///
+/// `source` defines `vs_main` and `fs_main`. It is appended to the `camera`, `lights`,
+/// `vertex` and `instance` chunks of the [`shader`](crate::shader) module, and to
+/// declarations of what is declared on the material: the uniforms as fields of `uniforms`
+/// and each texture `name` with a `name_sampler`, in group 1. The source is preprocessed,
+/// so it can include further chunks and have variants selected by
+/// [`with_define`](Self::with_define). A source that fails to compile is logged and drawn
+/// in magenta, [`validate`](Self::validate) reports the error up front.
+///
+/// ```
+/// use san::{material::ShaderMaterial, Rgba};
+///
+/// let material = ShaderMaterial::new(
+///     "pulse",
+///     "
+///     struct VertexOutput {
+///         @builtin(position) clip_position: vec4<f32>,
+///     }
+///
+///     @vertex
+///     fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+///         var out: VertexOutput;
+///         out.clip_position = globals.view_proj * model_matrix(instance)
+///             * vec4<f32>(model.position, 1.);
+///         return out;
+///     }
+///
+///     @fragment
+///     fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///         return uniforms.color * (0.75 + 0.25 * sin(uniforms.time));
+///     }
+///     ",
+/// )
+/// .with_uniform("color", Rgba::new(1., 0.5, 0., 1.))
+/// .with_uniform("time", 0f32);
+///
+/// // every frame, through `Mesh::material` of `Scene::get_mesh_ref`
+/// material.set_uniform("time", 1.5f32);
+/// ```
+#[derive(Debug)]
+pub struct ShaderMaterial {
+    label: String,
+    source: String,
+    uniforms: RwLock<Vec<(String, UniformValue)>>,
+    // uniforms set since the buffer was last written
+    dirty: AtomicBool,
+    textures: Vec<(String, Texture)>,
+    defines: Vec<String>,
+    render_state: RenderState,
+    layout: OnceCell<wgpu::BindGroupLayout>,
+}
+
+impl ShaderMaterial {
+    pub fn new(label: &str, source: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            source: source.to_owned(),
+            uniforms: RwLock::new(Vec::new()),
+            dirty: AtomicBool::new(false),
+            textures: Vec::new(),
+            defines: Vec::new(),
+            render_state: RenderState::default(),
+            layout: OnceCell::new(),
+        }
+    }
+
+    /// Declares a field of `uniforms` with the type of `value`, after the ones declared
+    /// before.
+    pub fn with_uniform<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: Into<UniformValue>,
+    {
+        self.assert_new_name(name);
+        self.uniforms
+            .get_mut()
+            .unwrap()
+            .push((name.to_owned(), value.into()));
+        self
+    }
+
+    /// Declares the texture `name` and its sampler `name_sampler`.
+    pub fn with_texture(mut self, name: &str, texture: Texture) -> Self {
+        self.assert_new_name(name);
+        self.textures.push((name.to_owned(), texture));
+        self
+    }
+
+    /// Defines `name` for the `#ifdef` directives of the source.
+    pub fn with_define(mut self, name: &str) -> Self {
+        self.defines.push(name.to_owned());
+        self
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+
+    /// Sets a uniform without uploading the material again, written to the GPU before the
+    /// next frame.
+    ///
+    /// Panics if `name` was not declared with the type of `value`.
+    pub fn set_uniform<V>(&self, name: &str, value: V)
+    where
+        V: Into<UniformValue>,
+    {
+        let value = value.into();
+        let mut uniforms = self.uniforms.write().unwrap();
+        let Some((_, current)) = uniforms.iter_mut().find(|(n, _)| n == name) else {
+            panic!("{}: no uniform `{}`", self.label, name)
+        };
+        assert_eq!(
+            std::mem::discriminant(current),
+            std::mem::discriminant(&value),
+            "{}: uniform `{}` is a {}, not a {}",
+            self.label,
+            name,
+            current.wgsl_type(),
+            value.wgsl_type(),
+        );
+
+        *current = value;
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Compiles the source without a device, reporting where it is invalid.
+    pub fn validate(&self) -> Result<(), ShaderError> {
+        self.compile().map(|_| ())
+    }
+
+    fn assert_new_name(&self, name: &str) {
+        let uniforms = self.uniforms.read().unwrap();
+        assert!(
+            !uniforms.iter().any(|(n, _)| n == name)
+                && !self.textures.iter().any(|(n, _)| n == name),
+            "{}: `{}` is declared twice",
+            self.label,
+            name
+        );
+    }
+
+    // group 1 as declared on the material
+    fn declarations(&self) -> String {
+        let mut source = String::new();
+
+        let uniforms = self.uniforms.read().unwrap();
+        if !uniforms.is_empty() {
+            source.push_str("struct Uniforms {\n");
+            for (name, value) in uniforms.iter() {
+                writeln!(source, "    {}: {},", name, value.wgsl_type()).unwrap();
+            }
+            source.push_str("}\n\n@group(1) @binding(0)\nvar<uniform> uniforms: Uniforms;\n");
+        }
+        for (i, (name, _)) in self.textures.iter().enumerate() {
+            let binding = 2 * i + 1;
+            writeln!(
+                source,
+                "@group(1) @binding({})\nvar {}_sampler: sampler;\n\
+                 @group(1) @binding({})\nvar {}: texture_2d<f32>;",
+                binding,
+                name,
+                binding + 1,
+                name
+            )
+            .unwrap();
+        }
+
+        source
+    }
+
+    fn compile(&self) -> Result<String, ShaderError> {
+        let declarations = self.declarations();
+        let mut composer = Composer::new(self.defines.iter().map(String::as_str));
+        for chunk in PRELUDE {
+            composer.include(chunk);
+        }
+        composer.append(&format!("{} declarations", self.label), &declarations)?;
+        composer.append(&self.label, &self.source)?;
+        composer.finish()
+    }
+
+    fn shader_source(&self) -> wgpu::ShaderSource<'static> {
+        match self.compile() {
+            Ok(source) => wgpu::ShaderSource::Wgsl(source.into()),
+            Err(e) => {
+                log::error!("{e}");
+                shader::builtin("error.wgsl", ShaderFeatures::empty())
+            }
+        }
+    }
+
+    // uniforms laid out like the generated `Uniforms` struct
+    fn uniform_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (_, value) in self.uniforms.read().unwrap().iter() {
+            bytes.resize(bytes.len().next_multiple_of(value.align()), 0);
+            bytes.extend_from_slice(value.bytes());
+        }
+        // struct size is a multiple of its alignment, 16 at most
+        bytes.resize(bytes.len().next_multiple_of(16).max(16), 0);
+        bytes
+    }
+
+    fn layout(&self, device: &wgpu::Device) -> &wgpu::BindGroupLayout {
+        self.layout.get_or_init(|| {
+            let mut entries = vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }];
+            for i in 0..self.textures.len() as u32 {
+                entries.push(texture::sampler_layout_entry(2 * i + 1));
+                entries.push(texture::texture_layout_entry(2 * i + 2));
+            }
+
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Shader Material Bind Group Layout"),
+                entries: &entries,
+            })
+        })
+    }
+}
+
+impl Clone for ShaderMaterial {
+    fn clone(&self) -> Self {
+        Self {
+            label: self.label.clone(),
+            source: self.source.clone(),
+            uniforms: RwLock::new(self.uniforms.read().unwrap().clone()),
+            dirty: AtomicBool::new(false),
+            textures: self.textures.clone(),
+            defines: self.defines.clone(),
+            render_state: self.render_state,
+            layout: OnceCell::new(),
+        }
+    }
+}
+
+impl Material for ShaderMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline_with_layout(
+            device,
+            key,
+            &self.label,
+            self.shader_source(),
+            &[crate::Vertex::desc(), crate::InstanceRaw::desc()],
+            self.layout(device),
+        )
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        self.dirty.store(false, Ordering::Release);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shader Material Params Buffer"),
+            contents: &self.uniform_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let textures: Vec<_> = self
+            .textures
+            .iter()
+            .map(|(_, texture)| {
+                (
+                    texture.gpu_data(device, queue, wgpu::TextureFormat::Rgba8Unorm),
+                    texture
+                        .sampler()
+                        .gpu_data(device, queue, wgpu::TextureFormat::Rgba8Unorm),
+                )
+            })
+            .collect();
+
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }];
+        for (i, (texture, sampler)) in textures.iter().enumerate() {
+            let binding = 2 * i as u32 + 1;
+            entries.push(wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding + 1,
+                resource: wgpu::BindingResource::TextureView(&texture.view),
+            });
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shader Material Bind Group"),
+            layout: self.layout(device),
+            entries: &entries,
+        });
+
+        (buffer, bind_group)
+    }
+
+    fn update_buffer(&self, queue: &wgpu::Queue, buffer: &wgpu::Buffer) {
+        if self.dirty.swap(false, Ordering::AcqRel) {
+            queue.write_buffer(buffer, 0, &self.uniform_bytes());
+        }
+    }
+}
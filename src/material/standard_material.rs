@@ -0,0 +1,331 @@
+use std::f32::consts::PI;
+
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
+
+use super::{Material, RenderState};
+use crate::{
+    params::LocalParams,
+    shader::ShaderFeatures,
+    texture::{self, ColorSpace, Texture},
+    PipelineKey, Rgb, Rgba,
+};
+
+/// glTF compatible metallic-roughness material.
+///
+/// Maps are multiplied by their factors: base color and emissive maps are expected in sRGB,
+/// the others in linear color space. Metalness is read from the blue and roughness from the
+/// green channel of the metallic-roughness map, occlusion from the red channel.
+#[derive(Debug, Clone)]
+pub struct StandardMaterial {
+    params: StandardMaterialParams,
+    base_color_map: Option<Texture>,
+    metallic_roughness_map: Option<Texture>,
+    normal_map: Option<Texture>,
+    occlusion_map: Option<Texture>,
+    emissive_map: Option<Texture>,
+    env_map: Option<Texture>,
+    render_state: RenderState,
+}
+
+impl StandardMaterial {
+    pub fn new(base_color: Rgba) -> Self {
+        Self {
+            params: StandardMaterialParams {
+                base_color: base_color.into(),
+                emissive: [0.; 3],
+                metallic: 0.,
+                roughness: 1.,
+                normal_scale: 1.,
+                occlusion_strength: 1.,
+                env_intensity: 1.,
+                sh: [[0.; 4]; 9],
+                env_max_lod: 0.,
+                _padding: [0.; 3],
+            },
+            base_color_map: None,
+            metallic_roughness_map: None,
+            normal_map: None,
+            occlusion_map: None,
+            emissive_map: None,
+            env_map: None,
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn metallic(mut self, metallic: f32) -> Self {
+        self.params.metallic = metallic;
+        self
+    }
+
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        self.params.roughness = roughness;
+        self
+    }
+
+    pub fn emissive(mut self, emissive: Rgb) -> Self {
+        self.params.emissive = emissive.into();
+        self
+    }
+
+    pub fn base_color_map(self, map: Texture) -> Self {
+        Self {
+            base_color_map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn metallic_roughness_map(self, map: Texture) -> Self {
+        Self {
+            metallic_roughness_map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn normal_map(mut self, map: Texture, scale: f32) -> Self {
+        self.params.normal_scale = scale;
+        Self {
+            normal_map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn occlusion_map(mut self, map: Texture, strength: f32) -> Self {
+        self.params.occlusion_strength = strength;
+        Self {
+            occlusion_map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn emissive_map(self, map: Texture) -> Self {
+        Self {
+            emissive_map: Some(map),
+            ..self
+        }
+    }
+
+    /// Equirectangular environment used for image based lighting.
+    pub fn env_map(mut self, map: Texture, intensity: f32) -> Self {
+        self.params.sh = irradiance_sh(&map);
+        self.params.env_intensity = intensity;
+        Self {
+            env_map: Some(map),
+            ..self
+        }
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+impl Material for StandardMaterial {
+    fn render_pipeline(&self, device: &wgpu::Device, key: &PipelineKey) -> wgpu::RenderPipeline {
+        crate::pipeline::create_render_pipeline_common::<StandardMaterialParams>(
+            device,
+            key,
+            "san::material::StandardMaterial",
+            crate::shader::builtin("standard.wgsl", key.features),
+        )
+    }
+
+    fn features(&self) -> ShaderFeatures {
+        let mut features = ShaderFeatures::empty();
+        features.set(ShaderFeatures::MAP, self.base_color_map.is_some());
+        features.set(ShaderFeatures::NORMAL_MAP, self.normal_map.is_some());
+        features.set(ShaderFeatures::ENV_MAP, self.env_map.is_some());
+        features
+    }
+
+    fn render_state(&self) -> RenderState {
+        self.render_state
+    }
+
+    fn supports_oit(&self) -> bool {
+        true
+    }
+
+    fn buffer_bind_group(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let map = |map: &Option<Texture>, default: [u8; 4], color_space| {
+            map.clone()
+                .unwrap_or_else(|| Texture::solid(default, color_space))
+                .gpu_data(device, queue, wgpu::TextureFormat::Rgba8Unorm)
+        };
+
+        let base_color = map(&self.base_color_map, [255; 4], ColorSpace::Srgb);
+        let metallic_roughness = map(&self.metallic_roughness_map, [255; 4], ColorSpace::Linear);
+        let normal = map(&self.normal_map, [128, 128, 255, 255], ColorSpace::Linear);
+        let occlusion = map(&self.occlusion_map, [255; 4], ColorSpace::Linear);
+        let emissive = map(&self.emissive_map, [255; 4], ColorSpace::Srgb);
+        let env = map(&self.env_map, [0, 0, 0, 255], ColorSpace::Linear);
+
+        let mut params = self.params;
+        params.env_max_lod = (env.mip_level_count - 1) as f32;
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Standard Material Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Standard Material Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let views = [
+            &base_color,
+            &metallic_roughness,
+            &normal,
+            &occlusion,
+            &emissive,
+            &env,
+        ];
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ];
+        entries.extend(
+            views
+                .iter()
+                .enumerate()
+                .map(|(i, texture)| wgpu::BindGroupEntry {
+                    binding: i as u32 + 2,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                }),
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Standard Material Bind Group"),
+            layout: StandardMaterialParams::desc(device),
+            entries: &entries,
+        });
+
+        (buffer, bind_group)
+    }
+}
+
+static STANDARD_MATERIAL_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct StandardMaterialParams {
+    base_color: [f32; 4],
+    emissive: [f32; 3],
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    env_intensity: f32,
+    // irradiance of the environment map divided by pi, as 9 SH coefficients
+    sh: [[f32; 4]; 9],
+    env_max_lod: f32,
+    _padding: [f32; 3],
+}
+
+impl LocalParams for StandardMaterialParams {
+    fn desc(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+        STANDARD_MATERIAL_LAYOUT.get_or_init(|| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Standard Material Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    texture::sampler_layout_entry(1),
+                    texture::texture_layout_entry(2),
+                    texture::texture_layout_entry(3),
+                    texture::texture_layout_entry(4),
+                    texture::texture_layout_entry(5),
+                    texture::texture_layout_entry(6),
+                    texture::texture_layout_entry(7),
+                ],
+            })
+        })
+    }
+}
+
+/// Projects an equirectangular map onto 9 SH coefficients of the convolved irradiance.
+fn irradiance_sh(map: &Texture) -> [[f32; 4]; 9] {
+    let (width, height) = (map.width() as usize, map.height() as usize);
+
+    let mut sh = [[0.; 4]; 9];
+    for (i, texel) in map.linear_texels().enumerate() {
+        let u = ((i % width) as f32 + 0.5) / width as f32;
+        let v = ((i / width) as f32 + 0.5) / height as f32;
+        let phi = (u - 0.5) * 2. * PI;
+        let theta = v * PI;
+
+        let (x, y, z) = (
+            phi.cos() * theta.sin(),
+            theta.cos(),
+            phi.sin() * theta.sin(),
+        );
+        let solid_angle = (2. * PI / width as f32) * (PI / height as f32) * theta.sin();
+
+        #[rustfmt::skip]
+        let basis = [
+            0.282095,
+            0.488603 * y,
+            0.488603 * z,
+            0.488603 * x,
+            1.092548 * x * y,
+            1.092548 * y * z,
+            0.315392 * (3. * z * z - 1.),
+            1.092548 * x * z,
+            0.546274 * (x * x - y * y),
+        ];
+
+        for (coefficient, b) in sh.iter_mut().zip(basis) {
+            for c in 0..3 {
+                coefficient[c] += texel[c] * b * solid_angle;
+            }
+        }
+    }
+
+    // cosine lobe convolution (A_l / pi) with the basis folded in for evaluation
+    let band = |l: usize| match l {
+        0 => 1.,
+        1..=3 => 2. / 3.,
+        _ => 1. / 4.,
+    };
+    #[rustfmt::skip]
+    let basis_scale = [
+        0.282095,
+        0.488603, 0.488603, 0.488603,
+        1.092548, 1.092548, 0.315392, 1.092548, 0.546274,
+    ];
+    for (l, coefficient) in sh.iter_mut().enumerate() {
+        for c in coefficient.iter_mut().take(3) {
+            *c *= band(l) * basis_scale[l];
+        }
+    }
+
+    sh
+}
@@ -1,19 +1,57 @@
-use std::{any::Any, marker::PhantomData, sync::Arc};
+use std::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use cgmath::{Matrix4, Point3, Transform};
 
 use crate::{
     common::AsAny,
     geometry::{Geometry, GeometryGpuData},
     gpu::GpuCached,
+    instance::Instances,
     material::{Material, MaterialGpuData},
+    pipeline::PipelineCache,
     scene::SceneID,
+    shader::ShaderFeatures,
+    Instance, InstancesGpuData, PipelineKey, Transparency,
 };
 
 pub trait MeshBase: AsAny {
+    /// `world` is the world matrix of the [node](crate::node) the mesh is attached to, or
+    /// the identity. Meshes that ignore it stay in place.
+    ///
+    /// Transparent meshes of materials that [support](Material::supports_oit) it are drawn
+    /// with the `OIT` shader variant when `transparency` is
+    /// [`WeightedBlended`](Transparency::WeightedBlended).
     fn gpu_data(
         &self,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: Matrix4<f32>,
         format: wgpu::TextureFormat,
-    ) -> (Arc<GeometryGpuData>, Arc<MaterialGpuData>);
+        depth_format: Option<wgpu::TextureFormat>,
+        transparency: Transparency,
+    ) -> MeshGpuData;
+}
+
+pub struct MeshGpuData {
+    pub(crate) geometry: Arc<GeometryGpuData>,
+    pub(crate) material: Arc<MaterialGpuData>,
+    pub(crate) pipeline: Arc<wgpu::RenderPipeline>,
+    pub(crate) instances: Arc<InstancesGpuData>,
+    pub(crate) topology: wgpu::PrimitiveTopology,
+    pub(crate) cast_shadow: bool,
+    pub(crate) receive_shadow: bool,
+    pub(crate) transparent: bool,
+    // drawn into the weighted blended transparency targets
+    pub(crate) oit: bool,
+    pub(crate) render_order: i32,
+    /// World space position the mesh is sorted by.
+    pub(crate) center: Point3<f32>,
 }
 
 pub struct Mesh<M>
@@ -22,6 +60,12 @@ where
 {
     geometry: GpuCached<Geometry>,
     material: GpuCached<M>,
+    // kept when the material is uploaded again, the key covers what the pipelines depend on
+    pipelines: PipelineCache<PipelineKey>,
+    instances: GpuCached<Instances>,
+    cast_shadow: bool,
+    receive_shadow: bool,
+    render_order: i32,
 }
 
 impl<M> Mesh<M>
@@ -32,11 +76,67 @@ where
     where
         M: Material,
     {
+        Self::with_instances(geometry, material, vec![Instance::default()])
+    }
+
+    pub fn with_instances(geometry: Geometry, material: M, instances: Vec<Instance>) -> Self {
         Self {
             geometry: GpuCached::new(geometry),
             material: GpuCached::new(material),
+            pipelines: PipelineCache::new(),
+            instances: GpuCached::new(Instances(instances)),
+            cast_shadow: true,
+            receive_shadow: true,
+            render_order: 0,
         }
     }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances.0
+    }
+
+    pub fn instances_mut(&mut self) -> &mut Vec<Instance> {
+        &mut self.instances.get_mut().0
+    }
+
+    pub fn material(&self) -> &M {
+        &self.material
+    }
+
+    /// Mutable access uploads the material parameters again, keeping its pipelines.
+    /// [`ShaderMaterial`](crate::material::ShaderMaterial) uniforms are better set through
+    /// [`material`](Self::material), and its shader cannot be replaced here.
+    pub fn material_mut(&mut self) -> &mut M {
+        self.material.get_mut()
+    }
+
+    pub fn cast_shadow(&self) -> bool {
+        self.cast_shadow
+    }
+
+    /// Only triangle geometry is rendered into shadow maps.
+    pub fn set_cast_shadow(&mut self, cast_shadow: bool) {
+        self.cast_shadow = cast_shadow;
+    }
+
+    pub fn receive_shadow(&self) -> bool {
+        self.receive_shadow
+    }
+
+    pub fn set_receive_shadow(&mut self, receive_shadow: bool) {
+        self.receive_shadow = receive_shadow;
+    }
+
+    pub fn render_order(&self) -> i32 {
+        self.render_order
+    }
+
+    /// Meshes with a lower render order are drawn first, the depth in front of the camera
+    /// only sorts meshes of the same order. Transparent meshes are drawn after all opaque
+    /// ones.
+    pub fn set_render_order(&mut self, render_order: i32) {
+        self.render_order = render_order;
+    }
 }
 
 impl<M> AsAny for Mesh<M>
@@ -59,53 +159,153 @@ where
     fn gpu_data(
         &self,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: Matrix4<f32>,
         format: wgpu::TextureFormat,
-    ) -> (Arc<GeometryGpuData>, Arc<MaterialGpuData>) {
-        (
-            self.geometry.to_gpu(device, format),
-            self.material.to_gpu(device, format),
-        )
+        depth_format: Option<wgpu::TextureFormat>,
+        transparency: Transparency,
+    ) -> MeshGpuData {
+        let geometry = self.geometry.to_gpu(device, queue, format);
+        let material = self.material.to_gpu(device, queue, format);
+        self.material.update_buffer(queue, &material.buffer);
+        let instances = self.instances.to_gpu(device, queue, format);
+        self.instances.write_world(queue, &instances, world);
+
+        let render_state = self.material.render_state();
+        let mut features = self.material.features() | ShaderFeatures::device(device);
+        features.set(
+            ShaderFeatures::OIT,
+            render_state.transparent
+                && transparency == Transparency::WeightedBlended
+                && self.material.supports_oit(),
+        );
+        let key = PipelineKey::new(format, self.geometry.topology)
+            .with_depth_format(depth_format)
+            .with_features(features)
+            .with_render_state(render_state);
+        let pipeline = self
+            .pipelines
+            .get_or_create(key, || self.material.render_pipeline(device, &key));
+
+        // average of the instance centers
+        let center = (world * instances.mean).transform_point(geometry.center);
+
+        MeshGpuData {
+            geometry,
+            material,
+            pipeline,
+            instances,
+            topology: self.geometry.topology,
+            cast_shadow: self.cast_shadow,
+            receive_shadow: self.receive_shadow,
+            transparent: render_state.transparent,
+            oit: features.contains(ShaderFeatures::OIT),
+            render_order: self.render_order,
+            center,
+        }
     }
 }
 
-pub struct MeshID<M> {
+/// Handle of a mesh of type `M` in a [`Scene`](crate::Scene). Handles of removed meshes
+/// stay invalid when the slot is reused, see [`Scene::contains`](crate::Scene::contains).
+pub struct MeshID<M: ?Sized> {
     pub(crate) scene_id: SceneID,
     pub(crate) index: usize,
+    pub(crate) generation: u32,
     pub(crate) _phantom: PhantomData<M>,
 }
 
-impl<M> MeshID<M> {
-    pub(crate) fn new(scene_id: SceneID, index: usize) -> Self {
+impl<M: ?Sized> MeshID<M> {
+    pub(crate) fn new(scene_id: SceneID, index: usize, generation: u32) -> Self {
         Self {
             scene_id,
             index,
+            generation,
             _phantom: Default::default(),
         }
     }
+
+    /// Forgets the type of the mesh.
+    pub fn untyped(self) -> AnyMeshID {
+        MeshID::new(self.scene_id, self.index, self.generation)
+    }
+}
+
+/// ID of a mesh of any type, as returned by the iterators of [`Scene`](crate::Scene).
+pub type AnyMeshID = MeshID<dyn MeshBase>;
+
+impl AnyMeshID {
+    /// The ID as the ID of a mesh of type `M`, the scene returns
+    /// [`SceneError::TypeMismatch`](crate::SceneError::TypeMismatch) if it is not.
+    pub fn typed<M>(self) -> MeshID<M>
+    where
+        M: MeshBase,
+    {
+        MeshID::new(self.scene_id, self.index, self.generation)
+    }
+}
+
+// not derived, which would require the bounds of `M`
+impl<M: ?Sized> Clone for MeshID<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: ?Sized> Copy for MeshID<M> {}
+
+impl<M: ?Sized> PartialEq for MeshID<M> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.scene_id, self.index, self.generation)
+            == (other.scene_id, other.index, other.generation)
+    }
+}
+
+impl<M: ?Sized> Eq for MeshID<M> {}
+
+impl<M: ?Sized> Hash for MeshID<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.scene_id, self.index, self.generation).hash(state);
+    }
+}
+
+impl<M: ?Sized> fmt::Debug for MeshID<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeshID")
+            .field("scene_id", &self.scene_id)
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
 }
 
 pub trait DrawMesh<'b> {
-    fn draw_mesh(&mut self, geometry: &'b GeometryGpuData, material: &'b MaterialGpuData);
+    fn draw_mesh(&mut self, mesh: &'b MeshGpuData);
 }
 
 impl<'a, 'b> DrawMesh<'b> for wgpu::RenderPass<'a>
 where
     'b: 'a,
 {
-    fn draw_mesh(&mut self, geometry: &'b GeometryGpuData, material: &'b MaterialGpuData) {
-        self.set_pipeline(&material.pipeline);
+    fn draw_mesh(&mut self, mesh: &'b MeshGpuData) {
+        let geometry = &mesh.geometry;
+        let material = &mesh.material;
+
+        self.set_pipeline(&mesh.pipeline);
 
         self.set_vertex_buffer(0, geometry.vertices.slice(..));
+        self.set_vertex_buffer(1, mesh.instances.buffer.slice(..));
         if let Some(ref indices) = geometry.indices {
             self.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
         }
 
-        self.set_bind_group(0, &material.bind_group, &[]);
+        self.set_bind_group(1, &material.bind_group, &[]);
 
+        let instances = 0..mesh.instances.len;
         if geometry.indices.is_some() {
-            self.draw_indexed(0..geometry.indices_len, 0, 0..1);
+            self.draw_indexed(0..geometry.indices_len, 0, instances);
         } else {
-            self.draw(0..geometry.vertices_len, 0..1);
+            self.draw(0..geometry.vertices_len, instances);
         }
     }
 }
@@ -0,0 +1,135 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use once_cell::sync::{Lazy, OnceCell};
+
+use crate::{pipeline::PipelineCache, shader::ShaderFeatures, texture};
+
+static MIPMAP_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
+static MIPMAP_PIPELINES: Lazy<PipelineCache<wgpu::TextureFormat>> = Lazy::new(PipelineCache::new);
+
+/// Number of levels of a full mip chain down to 1×1.
+pub(crate) fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Fills the levels below the base of `texture` by repeatedly rendering each level into
+/// the next with a linear filter. Sampling and rendering through the texture's own format
+/// keeps the averaging in linear space for sRGB textures.
+pub(crate) fn generate_mipmaps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    mip_level_count: u32,
+) {
+    if mip_level_count < 2 {
+        return;
+    }
+
+    let pipeline = pipeline(device, format);
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Mipmap Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let views: Vec<_> = (0..mip_level_count)
+        .map(|level| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Mipmap View"),
+                base_mip_level: level,
+                mip_level_count: NonZeroU32::new(1),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mipmap Encoder"),
+    });
+
+    for pair in views.windows(2) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mipmap Bind Group"),
+            layout: layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&pair[0]),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Mipmap Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &pair[1],
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+}
+
+fn layout(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+    MIPMAP_LAYOUT.get_or_init(|| {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mipmap Bind Group Layout"),
+            entries: &[
+                texture::sampler_layout_entry(0),
+                texture::texture_layout_entry(1),
+            ],
+        })
+    })
+}
+
+fn pipeline(device: &wgpu::Device, format: wgpu::TextureFormat) -> Arc<wgpu::RenderPipeline> {
+    MIPMAP_PIPELINES.get_or_create(format, || {
+        let label = "san::mipmap::Mipmap";
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: crate::shader::builtin("mipmap.wgsl", ShaderFeatures::empty()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+        })
+    })
+}
@@ -0,0 +1,49 @@
+use std::cell::Cell;
+
+use cgmath::{Matrix4, SquareMatrix};
+
+use crate::{scene::SceneID, Instance};
+
+/// Handle of a node of a [`Scene`](crate::Scene), a transform relative to its parent that
+/// meshes, lights, the camera and other nodes are attached to. A node without anything
+/// attached groups its children. Handles of removed nodes stay invalid when the slot is
+/// reused, see [`Scene::contains_node`](crate::Scene::contains_node).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeID {
+    pub(crate) scene_id: SceneID,
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+}
+
+impl NodeID {
+    pub(crate) fn new(scene_id: SceneID, index: usize, generation: u32) -> Self {
+        Self {
+            scene_id,
+            index,
+            generation,
+        }
+    }
+}
+
+pub(crate) struct Node {
+    pub(crate) transform: Instance,
+    // the parent's world matrix times the transform, as of the last time the dirty nodes
+    // were resolved
+    pub(crate) world: Cell<Matrix4<f32>>,
+    pub(crate) parent: Option<usize>,
+    pub(crate) children: Vec<usize>,
+    // indices of the attached meshes, which are detached with the node
+    pub(crate) meshes: Vec<usize>,
+}
+
+impl Node {
+    pub(crate) fn new(transform: Instance) -> Self {
+        Self {
+            transform,
+            world: Cell::new(Matrix4::identity()),
+            parent: None,
+            children: Vec::new(),
+            meshes: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex};
+
+use crate::effect::{EffectContext, FullscreenPass, TargetTexture};
+
+pub(crate) const ACCUM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+pub(crate) const REVEALAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+// sizes whose targets are kept, enough for a window and a few render targets
+const MAX_CACHED_SIZES: usize = 4;
+
+/// How a scene draws the meshes of [`transparent`](crate::material::RenderState::transparent)
+/// materials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Transparency {
+    /// Blended back to front by the depth of each mesh in front of the camera, wrong where
+    /// transparent meshes intersect.
+    #[default]
+    Sorted,
+    /// Weighted blended order-independent transparency: the meshes are accumulated in any
+    /// order and composited over the opaque ones, approximating the order by depth and
+    /// alpha. Materials without support, such as a
+    /// [`ShaderMaterial`](crate::material::ShaderMaterial), are sorted after the composite.
+    WeightedBlended,
+}
+
+/// Color targets of the `OIT` variant of a transparent material.
+pub(crate) fn color_targets(write_mask: wgpu::ColorWrites) -> [Option<wgpu::ColorTargetState>; 2] {
+    let add = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    // the product of one minus the alpha of every fragment
+    let reveal = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::OneMinusSrc,
+        operation: wgpu::BlendOperation::Add,
+    };
+
+    [
+        Some(wgpu::ColorTargetState {
+            format: ACCUM_FORMAT,
+            blend: Some(wgpu::BlendState {
+                color: add,
+                alpha: add,
+            }),
+            write_mask,
+        }),
+        Some(wgpu::ColorTargetState {
+            format: REVEALAGE_FORMAT,
+            blend: Some(wgpu::BlendState {
+                color: reveal,
+                alpha: reveal,
+            }),
+            write_mask: if write_mask.is_empty() {
+                wgpu::ColorWrites::empty()
+            } else {
+                wgpu::ColorWrites::ALL
+            },
+        }),
+    ]
+}
+
+/// Accumulation and revealage targets of a frame.
+pub(crate) struct OitTargets {
+    size: (u32, u32),
+    pub(crate) accum: TargetTexture,
+    pub(crate) revealage: TargetTexture,
+}
+
+/// Targets the transparent meshes of a scene are accumulated into, and the pass blending
+/// them over the color attachment.
+pub(crate) struct OitPass {
+    // one per size, most recently used first
+    targets: Mutex<Vec<Arc<OitTargets>>>,
+    composite: FullscreenPass,
+}
+
+impl OitPass {
+    pub(crate) fn new() -> Self {
+        Self {
+            targets: Mutex::new(Vec::new()),
+            composite: FullscreenPass::builtin(
+                "san::oit::Composite",
+                "oit_composite.wgsl",
+                "fs_main",
+                1,
+                Some(wgpu::BlendState::ALPHA_BLENDING),
+            ),
+        }
+    }
+
+    /// Targets of `size`, shared by the frames of that size. The least recently used size
+    /// is dropped when more are rendered, such as while a window is resized.
+    pub(crate) fn targets(&self, device: &wgpu::Device, size: (u32, u32)) -> Arc<OitTargets> {
+        let mut targets = self.targets.lock().unwrap();
+        let used = match targets.iter().position(|targets| targets.size == size) {
+            Some(index) => targets.remove(index),
+            None => {
+                targets.truncate(MAX_CACHED_SIZES - 1);
+                Arc::new(OitTargets {
+                    size,
+                    accum: create_target(device, size, ACCUM_FORMAT),
+                    revealage: create_target(device, size, REVEALAGE_FORMAT),
+                })
+            }
+        };
+        targets.insert(0, Arc::clone(&used));
+        used
+    }
+
+    /// Blends the accumulated meshes onto `output`.
+    pub(crate) fn composite(
+        &self,
+        context: &EffectContext,
+        targets: &OitTargets,
+        output: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        self.composite.draw(
+            context,
+            &[],
+            &targets.accum.1,
+            &[&targets.revealage.1],
+            output,
+            format,
+            encoder,
+        );
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device,
+    (width, height): (u32, u32),
+    format: wgpu::TextureFormat,
+) -> TargetTexture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("OIT Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    (texture, view)
+}
@@ -4,12 +4,38 @@ use cgmath::{Matrix4, SquareMatrix};
 use once_cell::sync::OnceCell;
 use wgpu::util::DeviceExt;
 
-static GLOBAL_PARAMS_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+// indexed by `uniform_lights`, as the light bindings differ between the two
+static GLOBAL_PARAMS_LAYOUTS: [OnceCell<wgpu::BindGroupLayout>; 2] =
+    [OnceCell::new(), OnceCell::new()];
+
+/// Length of the light array of the `UNIFORM_LIGHTS` shader variant.
+pub(crate) const UNIFORM_MAX_LIGHTS: usize = 64;
+
+/// Whether the device lacks the storage buffers lights and shadows are read from, as with
+/// the WebGL2 limits. They are bound as fixed size uniform arrays instead.
+pub(crate) fn uniform_lights(device: &wgpu::Device) -> bool {
+    device.limits().max_storage_buffers_per_shader_stage < 2
+}
+
+pub(crate) fn lights_buffer_usage(device: &wgpu::Device) -> wgpu::BufferUsages {
+    if uniform_lights(device) {
+        wgpu::BufferUsages::UNIFORM
+    } else {
+        wgpu::BufferUsages::STORAGE
+    }
+}
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct GlobalParams {
-    view_proj: [[f32; 4]; 4],
+    pub(crate) view_proj: [[f32; 4]; 4],
+    // width, height in physical pixels
+    pub(crate) viewport: [f32; 2],
+    // number of valid entries in the light buffer
+    pub(crate) light_count: u32,
+    // whether shadow maps are sampled, toggled per mesh
+    pub(crate) receive_shadow: u32,
+    pub(crate) camera_position: [f32; 4],
 }
 
 impl GlobalParams {
@@ -18,19 +44,64 @@ impl GlobalParams {
     }
 
     pub(crate) fn desc(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
-        GLOBAL_PARAMS_LAYOUT.get_or_init(|| {
+        let uniform_lights = uniform_lights(device);
+        GLOBAL_PARAMS_LAYOUTS[uniform_lights as usize].get_or_init(|| {
+            let lights_ty = if uniform_lights {
+                wgpu::BufferBindingType::Uniform
+            } else {
+                wgpu::BufferBindingType::Storage { read_only: true }
+            };
+
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Global Params Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: lights_ty,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: lights_ty,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                ],
             })
         })
     }
@@ -38,6 +109,7 @@ impl GlobalParams {
     pub(crate) fn buffer_bind_group(
         &self,
         device: &wgpu::Device,
+        bindings: &GlobalBindings,
     ) -> (wgpu::Buffer, wgpu::BindGroup) {
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Global Params Buffer"),
@@ -46,22 +118,56 @@ impl GlobalParams {
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Local Params Bind Group"),
+            label: Some("Global Params Bind Group"),
             layout: Self::desc(device),
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: bindings.lights.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: bindings.shadows.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(bindings.shadow_atlas),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(bindings.shadow_sampler),
+                },
+            ],
         });
 
         (buffer, bind_group)
     }
 }
 
+/// Scene wide resources bound next to [`GlobalParams`].
+pub(crate) struct GlobalBindings<'a> {
+    /// Storage buffer of [`LightRaw`](crate::light::LightRaw), uniform buffer if
+    /// [`uniform_lights`].
+    pub(crate) lights: &'a wgpu::Buffer,
+    /// Storage buffer of [`ShadowRaw`](crate::light::ShadowRaw), uniform buffer if
+    /// [`uniform_lights`].
+    pub(crate) shadows: &'a wgpu::Buffer,
+    pub(crate) shadow_atlas: &'a wgpu::TextureView,
+    pub(crate) shadow_sampler: &'a wgpu::Sampler,
+}
+
 impl Default for GlobalParams {
     fn default() -> Self {
         Self {
             view_proj: Matrix4::identity().into(),
+            viewport: [1., 1.],
+            light_count: 0,
+            receive_shadow: 1,
+            camera_position: [0., 0., 0., 1.],
         }
     }
 }
@@ -75,7 +181,7 @@ pub trait LocalParams: Debug + Clone + Copy + bytemuck::Pod {
                 label: Some("Local Params Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
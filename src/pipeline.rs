@@ -1,14 +1,162 @@
-use crate::{params::LocalParams, Vertex};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    material::RenderState,
+    oit,
+    params::{GlobalParams, LocalParams},
+    shader::{self, ShaderFeatures},
+    InstanceRaw, Vertex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub format: wgpu::TextureFormat,
+    pub topology: wgpu::PrimitiveTopology,
+    /// Depth attachment of the render pass, meshes are depth tested and written if present.
+    pub depth_format: Option<wgpu::TextureFormat>,
+    /// Shader variant of the material.
+    pub features: ShaderFeatures,
+    pub render_state: RenderState,
+}
+
+impl PipelineKey {
+    pub fn new(format: wgpu::TextureFormat, topology: wgpu::PrimitiveTopology) -> Self {
+        Self {
+            format,
+            topology,
+            depth_format: None,
+            features: ShaderFeatures::empty(),
+            render_state: RenderState::default(),
+        }
+    }
+
+    pub fn with_depth_format(self, depth_format: Option<wgpu::TextureFormat>) -> Self {
+        Self {
+            depth_format,
+            ..self
+        }
+    }
+
+    pub fn with_features(self, features: ShaderFeatures) -> Self {
+        Self { features, ..self }
+    }
+
+    pub fn with_render_state(self, render_state: RenderState) -> Self {
+        Self {
+            render_state,
+            ..self
+        }
+    }
+}
+
+/// Pipelines created on first use, created again after [`shader::reload`] replaced the
+/// shaders they were created from.
+#[derive(Debug)]
+pub(crate) struct PipelineCache<K, V = wgpu::RenderPipeline> {
+    // shader generation the pipelines were created in
+    pipelines: RwLock<(u64, HashMap<K, Arc<V>>)>,
+}
+
+impl<K, V> PipelineCache<K, V>
+where
+    K: Eq + Hash,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            pipelines: RwLock::new((shader::generation(), HashMap::new())),
+        }
+    }
+
+    pub(crate) fn get_or_create<F>(&self, key: K, create: F) -> Arc<V>
+    where
+        F: FnOnce() -> V,
+    {
+        let generation = shader::generation();
+        {
+            let pipelines = self.pipelines.read().unwrap();
+            if pipelines.0 == generation {
+                if let Some(v) = pipelines.1.get(&key) {
+                    return Arc::clone(v);
+                }
+            }
+        }
+
+        let mut pipelines_mut = self.pipelines.write().unwrap();
+        if pipelines_mut.0 != generation {
+            *pipelines_mut = (generation, HashMap::new());
+        }
+        Arc::clone(
+            pipelines_mut
+                .1
+                .entry(key)
+                .or_insert_with(|| Arc::new(create())),
+        )
+    }
+}
+
+impl<K, V> Default for PipelineCache<K, V>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub fn create_render_pipeline_common<T>(
     device: &wgpu::Device,
-    format: wgpu::TextureFormat,
+    key: &PipelineKey,
+    common_label: &str,
+    source: wgpu::ShaderSource,
+) -> wgpu::RenderPipeline
+where
+    T: LocalParams,
+{
+    create_render_pipeline::<T>(
+        device,
+        key,
+        common_label,
+        source,
+        &[Vertex::desc(), InstanceRaw::desc()],
+    )
+}
+
+pub fn create_render_pipeline<T>(
+    device: &wgpu::Device,
+    key: &PipelineKey,
     common_label: &str,
     source: wgpu::ShaderSource,
+    buffers: &[wgpu::VertexBufferLayout],
 ) -> wgpu::RenderPipeline
 where
     T: LocalParams,
 {
+    create_render_pipeline_with_layout(device, key, common_label, source, buffers, T::desc(device))
+}
+
+/// Like [`create_render_pipeline`] for materials whose bind group layout is not static.
+pub fn create_render_pipeline_with_layout(
+    device: &wgpu::Device,
+    key: &PipelineKey,
+    common_label: &str,
+    source: wgpu::ShaderSource,
+    buffers: &[wgpu::VertexBufferLayout],
+    local_layout: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let state = &key.render_state;
+    let targets = if key.features.contains(ShaderFeatures::OIT) {
+        oit::color_targets(state.write_mask).to_vec()
+    } else {
+        vec![Some(wgpu::ColorTargetState {
+            format: key.format,
+            blend: state.blending.blend_state(),
+            write_mask: state.write_mask,
+        })]
+    };
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some(common_label),
         source,
@@ -16,7 +164,7 @@ where
 
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some(common_label),
-        bind_group_layouts: &[T::desc(device)],
+        bind_group_layouts: &[GlobalParams::desc(device), local_layout],
         push_constant_ranges: &[],
     });
 
@@ -26,24 +174,27 @@ where
         vertex: wgpu::VertexState {
             module: &shader,
             entry_point: "vs_main",
-            buffers: &[Vertex::desc()],
+            buffers,
         },
         primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            cull_mode: Some(wgpu::Face::Back),
-            polygon_mode: wgpu::PolygonMode::Fill,
+            topology: key.topology,
+            strip_index_format: key.topology.is_strip().then_some(wgpu::IndexFormat::Uint32),
+            cull_mode: state.side.cull_mode(),
+            polygon_mode: state.supported_polygon_mode(device),
             ..Default::default()
         },
-        depth_stencil: None,
+        depth_stencil: key.depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: !state.transparent,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
         multisample: wgpu::MultisampleState::default(),
         fragment: Some(wgpu::FragmentState {
             module: &shader,
             entry_point: "fs_main",
-            targets: &[Some(wgpu::ColorTargetState {
-                format,
-                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
+            targets: &targets,
         }),
         multiview: None,
     })
@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use crate::{
+    camera::{Camera, CameraView},
+    texture::{Texture, TextureGpuData},
+    Transparency,
+};
+
+const DEFAULT_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Offscreen color and depth attachments a [`Scene`](crate::Scene) is rendered into with
+/// [`Scene::render_to_target`](crate::Scene::render_to_target), for mirrors, minimaps or
+/// picture-in-picture. The color attachment is read by materials through
+/// [`texture`](Self::texture).
+pub struct RenderTarget {
+    device: Arc<wgpu::Device>,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    texture: Texture,
+    color: Arc<TextureGpuData>,
+    depth: Option<(wgpu::Texture, wgpu::TextureView)>,
+    camera: Option<CameraView>,
+    transparency: Transparency,
+}
+
+impl RenderTarget {
+    /// Materials can only sample filterable formats, such as `Rgba8UnormSrgb` or
+    /// `Rgba16Float`.
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        assert!(width > 0 && height > 0);
+
+        let (texture, color) = Texture::attachment(&device, width, height, format);
+        let depth = create_depth(&device, width, height, Some(DEFAULT_DEPTH_FORMAT));
+
+        Self {
+            device,
+            width,
+            height,
+            format,
+            depth_format: Some(DEFAULT_DEPTH_FORMAT),
+            texture,
+            color,
+            depth,
+            camera: None,
+            transparency: Transparency::default(),
+        }
+    }
+
+    /// Depth attachment the meshes are tested against, `Depth32Float` by default. Without
+    /// it opaque meshes are not sorted by depth but drawn in slot order, like on the
+    /// window, within their [render order](crate::Mesh::set_render_order).
+    pub fn with_depth_format(self, depth_format: Option<wgpu::TextureFormat>) -> Self {
+        let depth = create_depth(&self.device, self.width, self.height, depth_format);
+
+        Self {
+            depth_format,
+            depth,
+            ..self
+        }
+    }
+
+    pub fn with_transparency(self, transparency: Transparency) -> Self {
+        Self {
+            transparency,
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    pub fn transparency(&self) -> Transparency {
+        self.transparency
+    }
+
+    /// Color attachment as a texture without mipmaps. A material reading it must not be
+    /// rendered into the same target.
+    pub fn texture(&self) -> Texture {
+        self.texture.clone()
+    }
+
+    /// Color attachment for custom passes or copies, such as reading the pixels back.
+    pub fn color_texture(&self) -> &wgpu::Texture {
+        &self.color.texture
+    }
+
+    /// Depth attachment for custom passes, it is not filterable and cannot be bound as a
+    /// [`Texture`].
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth.as_ref().map(|(_, view)| view)
+    }
+
+    /// Camera used instead of the one of the scene.
+    pub fn set_camera<C>(&mut self, camera: &C)
+    where
+        C: Camera,
+    {
+        self.camera = Some(CameraView::new(camera));
+    }
+
+    /// Renders with the camera of the scene again.
+    pub fn clear_camera(&mut self) {
+        self.camera = None;
+    }
+
+    /// Recreates the attachments. Textures returned by [`texture`](Self::texture) before
+    /// keep the old contents, materials have to be given the new one.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        let (texture, color) = Texture::attachment(&self.device, width, height, self.format);
+        self.width = width;
+        self.height = height;
+        self.texture = texture;
+        self.color = color;
+        self.depth = create_depth(&self.device, width, height, self.depth_format);
+    }
+
+    pub(crate) fn attachments(&self) -> Attachments<'_> {
+        Attachments {
+            color: &self.color.view,
+            format: self.format,
+            depth: self
+                .depth
+                .as_ref()
+                .zip(self.depth_format)
+                .map(|((_, view), format)| (view, format)),
+            size: (self.width, self.height),
+            camera: self.camera,
+            transparency: self.transparency,
+        }
+    }
+}
+
+/// Views a scene is drawn into by one render pass.
+pub(crate) struct Attachments<'a> {
+    pub(crate) color: &'a wgpu::TextureView,
+    pub(crate) format: wgpu::TextureFormat,
+    pub(crate) depth: Option<(&'a wgpu::TextureView, wgpu::TextureFormat)>,
+    // width, height in physical pixels
+    pub(crate) size: (u32, u32),
+    // overrides the scene camera
+    pub(crate) camera: Option<CameraView>,
+    pub(crate) transparency: Transparency,
+}
+
+fn create_depth(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: Option<wgpu::TextureFormat>,
+) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Render Target Depth Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: format?,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    Some((texture, view))
+}
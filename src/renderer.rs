@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{fmt, path::PathBuf, sync::Arc};
 
 use winit::{
     event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
@@ -6,13 +6,30 @@ use winit::{
     window::Window,
 };
 
-use crate::Scene;
+use crate::{
+    effect::{Effect, PostProcess, ToneMappingOperator},
+    shader, RenderTarget, Scene, Transparency,
+};
 
 #[derive(Debug, Clone)]
 pub struct WGPURendererOption {
     pub power_preference: wgpu::PowerPreference,
     pub device_limits: wgpu::Limits,
+    /// Features requested when the adapter supports them, by default texture compression
+    /// formats and wireframes. Compressed textures are decompressed on the CPU without the
+    /// feature, wireframe materials are filled.
+    pub features: wgpu::Features,
+    /// Curve mapping the [`HDR_FORMAT`](crate::effect::HDR_FORMAT) frame to the surface,
+    /// none by default.
+    pub tone_mapping: ToneMappingOperator,
+    /// Scale of the frame before tone mapping, 1 by default.
+    pub exposure: f32,
+    /// How transparent materials are drawn, sorted by default.
+    pub transparency: Transparency,
     pub trace: Option<PathBuf>,
+    /// Copy of `src/shaders` the built-in shaders are loaded from and reloaded before a
+    /// frame when changed, looked for at most four times a second, see [`shader::watch`].
+    pub hot_reload: Option<PathBuf>,
 }
 
 impl Default for WGPURendererOption {
@@ -24,7 +41,15 @@ impl Default for WGPURendererOption {
             } else {
                 wgpu::Limits::default()
             },
+            features: wgpu::Features::TEXTURE_COMPRESSION_BC
+                | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                | wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR
+                | wgpu::Features::POLYGON_MODE_LINE,
+            tone_mapping: ToneMappingOperator::None,
+            exposure: 1.,
+            transparency: Transparency::default(),
             trace: None,
+            hot_reload: None,
         }
     }
 }
@@ -44,21 +69,50 @@ impl WGPURendererOption {
         }
     }
 
+    pub fn features(self, features: wgpu::Features) -> Self {
+        Self { features, ..self }
+    }
+
+    pub fn tone_mapping(self, tone_mapping: ToneMappingOperator) -> Self {
+        Self {
+            tone_mapping,
+            ..self
+        }
+    }
+
+    pub fn exposure(self, exposure: f32) -> Self {
+        Self { exposure, ..self }
+    }
+
+    pub fn transparency(self, transparency: Transparency) -> Self {
+        Self {
+            transparency,
+            ..self
+        }
+    }
+
     pub fn with_trace(self, trace: PathBuf) -> Self {
         Self {
             trace: Some(trace),
             ..self
         }
     }
+
+    pub fn with_hot_reload(self, dir: PathBuf) -> Self {
+        Self {
+            hot_reload: Some(dir),
+            ..self
+        }
+    }
 }
 
-#[derive(Debug)]
 pub struct WGPURenderer {
     window: Window,
     device: Arc<wgpu::Device>,
     queue: wgpu::Queue,
     surface: wgpu::Surface,
     surface_desc: wgpu::SurfaceConfiguration,
+    post_process: PostProcess,
 }
 
 impl WGPURenderer {
@@ -81,7 +135,7 @@ impl WGPURenderer {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    features: wgpu::Features::empty(),
+                    features: option.features & adapter.features(),
                     limits: option.device_limits,
                 },
                 option.trace.as_deref(),
@@ -108,17 +162,88 @@ impl WGPURenderer {
         };
         surface.configure(&device, &surface_desc);
 
+        if let Some(dir) = &option.hot_reload {
+            shader::watch(dir);
+        }
+
         Self {
             window,
             device: Arc::new(device),
             queue,
             surface,
             surface_desc,
+            post_process: PostProcess::new(
+                option.tone_mapping,
+                option.exposure,
+                option.transparency,
+            ),
         }
     }
 
     pub fn create_scene(&self) -> Scene {
-        Scene::new(Arc::clone(&self.device), self.surface_desc.format)
+        Scene::new(Arc::clone(&self.device))
+    }
+
+    /// Offscreen target in the surface format with the transparency of the renderer, see
+    /// [`RenderTarget::new`].
+    pub fn create_render_target(&self, width: u32, height: u32) -> RenderTarget {
+        RenderTarget::new(
+            Arc::clone(&self.device),
+            width,
+            height,
+            self.surface_desc.format,
+        )
+        .with_transparency(self.transparency())
+    }
+
+    pub fn render_to_target(&self, scene: &Scene, target: &RenderTarget) {
+        scene.render_to_target(&self.queue, target);
+    }
+
+    /// Appends an effect to the post-processing stack. The scene is rendered into an
+    /// [`HDR_FORMAT`](crate::effect::HDR_FORMAT) texture, which the effects process in
+    /// order before it is tone mapped to the surface.
+    pub fn add_effect<E>(&mut self, effect: E)
+    where
+        E: Effect + 'static,
+    {
+        self.post_process.effects.push(Box::new(effect));
+    }
+
+    pub fn effects(&self) -> &[Box<dyn Effect>] {
+        &self.post_process.effects
+    }
+
+    /// The stack for reordering or removing effects, or changing their settings through
+    /// [`AsAny`](crate::AsAny).
+    pub fn effects_mut(&mut self) -> &mut Vec<Box<dyn Effect>> {
+        &mut self.post_process.effects
+    }
+
+    pub fn tone_mapping(&self) -> ToneMappingOperator {
+        self.post_process.tone_mapping
+    }
+
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMappingOperator) {
+        self.post_process.tone_mapping = tone_mapping;
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.post_process.exposure
+    }
+
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.post_process.exposure = exposure;
+    }
+
+    pub fn transparency(&self) -> Transparency {
+        self.post_process.transparency
+    }
+
+    /// Applies to the window, render targets have their own
+    /// [`RenderTarget::with_transparency`].
+    pub fn set_transparency(&mut self, transparency: Transparency) {
+        self.post_process.transparency = transparency;
     }
 
     pub fn window(&self) -> &Window {
@@ -134,6 +259,11 @@ impl WGPURenderer {
     }
 
     pub fn render(&mut self, scene: &Scene) -> Result<(), wgpu::SurfaceError> {
+        // an invalid change keeps the previous shaders
+        if let Err(e) = shader::poll() {
+            log::error!("{e}");
+        }
+
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
@@ -145,7 +275,15 @@ impl WGPURenderer {
                 label: Some("Render Encoder"),
             });
 
-        scene.render(&view, &mut encoder);
+        self.post_process.render(
+            &self.device,
+            &self.queue,
+            scene,
+            &view,
+            self.surface_desc.format,
+            (self.surface_desc.width, self.surface_desc.height),
+            &mut encoder,
+        );
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
@@ -196,3 +334,17 @@ impl WGPURenderer {
         ControlFlow::Poll
     }
 }
+
+impl fmt::Debug for WGPURenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WGPURenderer")
+            .field("window", &self.window)
+            .field("device", &self.device)
+            .field("surface_desc", &self.surface_desc)
+            .field("effects", &self.post_process.effects.len())
+            .field("tone_mapping", &self.post_process.tone_mapping)
+            .field("exposure", &self.post_process.exposure)
+            .field("transparency", &self.post_process.transparency)
+            .finish_non_exhaustive()
+    }
+}
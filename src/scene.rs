@@ -1,129 +1,1136 @@
-use std::sync::{
-    atomic::{AtomicU16, Ordering},
-    Arc,
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashSet,
+    error, fmt,
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc,
+    },
 };
 
-use crate::mesh::{DrawMesh, MeshBase, MeshID};
+use bytemuck::Zeroable;
+use cgmath::{Matrix4, SquareMatrix};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    background::{Background, BackgroundPass},
+    camera::{Camera, CameraView},
+    effect::EffectContext,
+    light::{self, Light, LightID, LightRaw},
+    mesh::{AnyMeshID, DrawMesh, MeshBase, MeshGpuData, MeshID},
+    node::{Node, NodeID},
+    oit::OitPass,
+    params::{
+        lights_buffer_usage, uniform_lights, GlobalBindings, GlobalParams, UNIFORM_MAX_LIGHTS,
+    },
+    render_target::{Attachments, RenderTarget},
+    shadow::ShadowAtlas,
+    slots::Slots,
+    Instance,
+};
 
 pub(crate) type SceneID = u16;
 
 static SCENE_COUNTER: AtomicU16 = AtomicU16::new(0);
 
+const DEFAULT_MAX_LIGHTS: usize = 16;
+
+const DEFAULT_SHADOW_MAP_SIZE: u32 = 512;
+
+const DEFAULT_MAX_POINT_SHADOWS: usize = 2;
+
 pub struct Scene {
     id: SceneID,
     device: Arc<wgpu::Device>,
-    format: wgpu::TextureFormat,
-    background: wgpu::Color,
-    meshes: Vec<Option<Box<dyn MeshBase>>>,
-    mesh_recycle_ids: Vec<usize>,
+    background: BackgroundPass,
+    globals: GlobalParams,
+    // indexed by whether the mesh receives shadows
+    globals_bind_groups: [(wgpu::Buffer, wgpu::BindGroup); 2],
+    lights_buffer: wgpu::Buffer,
+    max_lights: usize,
+    shadow_atlas: ShadowAtlas,
+    oit: OitPass,
+    max_point_shadows: usize,
+    meshes: Slots<Entry<dyn MeshBase, MeshInfo>>,
+    lights: Slots<Entry<dyn Light>>,
+    nodes: Slots<Node>,
+    // nodes whose transform or parent changed since the world matrices were resolved
+    dirty_nodes: RefCell<HashSet<usize>>,
+    camera: CameraView,
+    // node the camera is placed relative to
+    camera_node: Option<usize>,
+}
+
+/// A mesh or light of the scene, the node it is attached to and what the application
+/// attached to it.
+struct Entry<T: ?Sized, I = ()> {
+    item: Box<T>,
+    node: Option<usize>,
+    info: I,
+}
+
+impl<T: ?Sized, I: Default> Entry<T, I> {
+    fn new(item: Box<T>) -> Self {
+        Self {
+            item,
+            node: None,
+            info: I::default(),
+        }
+    }
+}
+
+/// Error of looking up a mesh, light or node of a [`Scene`] by its ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneError {
+    /// The ID was returned by another scene.
+    WrongScene,
+    /// The item has been removed, possibly with another one added in its place.
+    Removed,
+    /// The item is not of the type of the ID.
+    TypeMismatch,
+    /// The node would become its own ancestor.
+    Cycle,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::WrongScene => "the ID belongs to another scene",
+            Self::Removed => "the item has been removed from the scene",
+            Self::TypeMismatch => "the item is not of the requested type",
+            Self::Cycle => "a node cannot be its own ancestor",
+        })
+    }
+}
+
+impl error::Error for SceneError {}
+
+/// What the application attached to a mesh, dropped with the mesh.
+#[derive(Default)]
+struct MeshInfo {
+    name: Option<String>,
+    tags: Vec<String>,
+    user_data: Option<Box<dyn Any>>,
+}
+
+/// Indices of the prepared meshes grouped by render pass, in draw order.
+struct RenderQueue {
+    opaque: Vec<usize>,
+    oit: Vec<usize>,
+    transparent: Vec<usize>,
+}
+
+impl RenderQueue {
+    // opaque meshes front to back so hidden fragments fail the depth test early, or in
+    // slot order without a depth test where the depth would be wrong either way,
+    // transparent ones back to front so they blend over what is behind them, unless
+    // they are accumulated in any order
+    fn new(meshes: &[MeshGpuData], camera: &CameraView, depth_test: bool) -> Self {
+        let depths: Vec<_> = meshes
+            .iter()
+            .map(|mesh| camera.depth(mesh.center))
+            .collect();
+        let (mut opaque, transparent): (Vec<_>, Vec<_>) =
+            (0..meshes.len()).partition(|&i| !meshes[i].transparent);
+        let (oit, mut transparent): (Vec<_>, Vec<_>) =
+            transparent.into_iter().partition(|&i| meshes[i].oit);
+        opaque.sort_by(|&a, &b| {
+            let order = meshes[a].render_order.cmp(&meshes[b].render_order);
+            if depth_test {
+                order.then(depths[a].total_cmp(&depths[b]))
+            } else {
+                order
+            }
+        });
+        transparent.sort_by(|&a, &b| {
+            meshes[a]
+                .render_order
+                .cmp(&meshes[b].render_order)
+                .then(depths[b].total_cmp(&depths[a]))
+        });
+
+        Self {
+            opaque,
+            oit,
+            transparent,
+        }
+    }
 }
 
 impl Scene {
-    pub fn new(device: Arc<wgpu::Device>, format: wgpu::TextureFormat) -> Self {
+    pub fn new(device: Arc<wgpu::Device>) -> Self {
+        let globals = GlobalParams::new();
+        let lights_buffer = create_lights_buffer(&device, DEFAULT_MAX_LIGHTS);
+        let shadow_atlas = ShadowAtlas::new(&device, DEFAULT_SHADOW_MAP_SIZE);
+        let background = BackgroundPass::new(&device);
+        let globals_bind_groups =
+            create_globals_bind_groups(&device, &globals, &lights_buffer, &shadow_atlas);
+
         Self {
             id: SCENE_COUNTER.fetch_add(1, Ordering::Relaxed),
             device,
-            format,
-            background: wgpu::Color::WHITE,
-            meshes: Vec::new(),
-            mesh_recycle_ids: Vec::new(),
+            background,
+            globals,
+            globals_bind_groups,
+            lights_buffer,
+            max_lights: DEFAULT_MAX_LIGHTS,
+            shadow_atlas,
+            oit: OitPass::new(),
+            max_point_shadows: DEFAULT_MAX_POINT_SHADOWS,
+            meshes: Slots::new(),
+            lights: Slots::new(),
+            nodes: Slots::new(),
+            dirty_nodes: RefCell::new(HashSet::new()),
+            camera: CameraView::default(),
+            camera_node: None,
         }
     }
 
-    pub(crate) fn render(&self, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) {
-        let gpu_data: Vec<_> = self
-            .meshes
-            .iter()
-            .flatten()
-            .map(|mesh| (mesh.gpu_data(&self.device, self.format)))
-            .collect();
+    /// Renders into `target` and submits the commands right away, so the scene can be
+    /// rendered into several targets with different cameras before it is presented.
+    pub fn render_to_target(&self, queue: &wgpu::Queue, target: &RenderTarget) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Target Encoder"),
+            });
+
+        self.render(queue, &target.attachments(), &mut encoder);
+
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Records the frame into `encoder`. The parameters are written to the queue, so
+    /// each call has to be submitted before the next one.
+    pub(crate) fn render(
+        &self,
+        queue: &wgpu::Queue,
+        attachments: &Attachments,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        self.resolve_worlds();
+        let camera = self.camera_view(attachments);
+        let (view_proj, eye) = (camera.view_proj, camera.eye);
+        let (format, depth_format) = (
+            attachments.format,
+            attachments.depth.map(|(_, format)| format),
+        );
+
+        let mut lights = Vec::new();
+        let mut shadows = Vec::new();
+        let mut point_shadows = 0;
+        for (_, _, entry) in self.lights.iter().take(self.max_lights) {
+            let light = entry.item.as_ref();
+            let (mut raw, mut light_shadows) = match entry.node {
+                Some(index) => {
+                    light::world_raws(light, &self.nodes[index].world.get(), &view_proj, eye)
+                }
+                None => (light.to_raw(), light.to_shadow_raws(&view_proj, eye)),
+            };
+
+            // lights whose shadow maps don't fit into the atlas are left unshadowed
+            if raw.is_point() && !light_shadows.is_empty() {
+                if point_shadows < self.max_point_shadows {
+                    point_shadows += 1;
+                } else {
+                    light_shadows.clear();
+                }
+            }
+            if shadows.len() + light_shadows.len() <= ShadowAtlas::capacity() {
+                raw.shadow_index = shadows.len() as u32;
+                raw.shadow_count = light_shadows.len() as u32;
+                for mut shadow in light_shadows {
+                    shadow.atlas_rect = ShadowAtlas::tile_rect(shadows.len());
+                    shadows.push(shadow);
+                }
+            }
+
+            lights.push(raw);
+        }
+        if !lights.is_empty() {
+            queue.write_buffer(&self.lights_buffer, 0, bytemuck::cast_slice(&lights));
+        }
+
+        for (receive_shadow, (buffer, _)) in self.globals_bind_groups.iter().enumerate() {
+            let mut globals = self.globals;
+            globals.view_proj = view_proj.into();
+            globals.camera_position = eye.to_homogeneous().into();
+            globals.viewport = [attachments.size.0 as f32, attachments.size.1 as f32];
+            globals.light_count = lights.len() as u32;
+            globals.receive_shadow = receive_shadow as u32;
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[globals]));
+        }
+
+        let gpu_data = self.gpu_data(queue, attachments);
+
+        self.shadow_atlas
+            .render(&self.device, queue, &shadows, &gpu_data, encoder);
+
+        let order = RenderQueue::new(&gpu_data, &camera, attachments.depth.is_some());
+        let meshes =
+            |indices: &[usize]| -> Vec<_> { indices.iter().map(|&i| &gpu_data[i]).collect() };
+        let (opaque, oit, transparent) = (
+            meshes(&order.opaque),
+            meshes(&order.oit),
+            meshes(&order.transparent),
+        );
+
+        let background_pipelines =
+            self.background
+                .prepare(&self.device, queue, view_proj, (format, depth_format));
 
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
+                view: attachments.color,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.background),
+                    load: wgpu::LoadOp::Clear(self.background.clear_color()),
                     store: true,
                 },
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: depth_attachment(attachments, wgpu::LoadOp::Clear(1.)),
+        });
+
+        // without a depth buffer the background has to come first
+        if let Some(pipelines) = &background_pipelines {
+            self.background.draw(pipelines, &mut render_pass);
+        }
+        self.draw_meshes(&mut render_pass, &opaque);
+        if oit.is_empty() {
+            self.draw_meshes(&mut render_pass, &transparent);
+            return;
+        }
+        drop(render_pass);
+
+        let targets = self.oit.targets(&self.device, attachments.size);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("OIT Render Pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.accum.1,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.revealage.1,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: true,
+                    },
+                }),
+            ],
+            depth_stencil_attachment: depth_attachment(attachments, wgpu::LoadOp::Load),
         });
+        self.draw_meshes(&mut render_pass, &oit);
+        drop(render_pass);
+
+        let context = EffectContext {
+            device: &self.device,
+            queue,
+            size: attachments.size,
+        };
+        self.oit
+            .composite(&context, &targets, attachments.color, format, encoder);
+
+        // materials without support are blended over the composite
+        if !transparent.is_empty() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Transparent Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: attachments.color,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: depth_attachment(attachments, wgpu::LoadOp::Load),
+            });
+            self.draw_meshes(&mut render_pass, &transparent);
+        }
+    }
+
+    /// IDs of the meshes in the order [`render_to_target`](Self::render_to_target) draws
+    /// them into `target`: opaque meshes front to back, or in slot order if `target` has
+    /// no depth attachment, then transparent meshes
+    /// accumulated by [`WeightedBlended`](crate::Transparency::WeightedBlended) in any
+    /// order, then the other transparent ones back to front. Meshes of a lower
+    /// [render order](crate::Mesh::set_render_order) come first in each group.
+    pub fn draw_order(&self, queue: &wgpu::Queue, target: &RenderTarget) -> Vec<AnyMeshID> {
+        self.resolve_worlds();
+        let attachments = target.attachments();
+        let ids: Vec<_> = self
+            .meshes
+            .iter()
+            .map(|(index, generation, _)| MeshID::new(self.id, index, generation))
+            .collect();
+        let gpu_data = self.gpu_data(queue, &attachments);
+        let order = RenderQueue::new(
+            &gpu_data,
+            &self.camera_view(&attachments),
+            attachments.depth.is_some(),
+        );
+
+        [order.opaque, order.oit, order.transparent]
+            .into_iter()
+            .flatten()
+            .map(|i| ids[i])
+            .collect()
+    }
+
+    fn camera_view(&self, attachments: &Attachments) -> CameraView {
+        attachments.camera.unwrap_or_else(|| {
+            match self.camera_node.map(|index| self.nodes[index].world.get()) {
+                Some(world) => self.camera.placed(&world),
+                None => self.camera,
+            }
+        })
+    }
 
-        for (geo, mat) in gpu_data.iter() {
-            render_pass.draw_mesh(geo, mat);
+    /// The meshes prepared for `attachments`, in slot order. The world matrices have to be
+    /// resolved.
+    fn gpu_data(&self, queue: &wgpu::Queue, attachments: &Attachments) -> Vec<MeshGpuData> {
+        self.meshes
+            .iter()
+            .map(|(_, _, entry)| {
+                let world = entry
+                    .node
+                    .map_or_else(Matrix4::identity, |index| self.nodes[index].world.get());
+                entry.item.gpu_data(
+                    &self.device,
+                    queue,
+                    world,
+                    attachments.format,
+                    attachments.depth.map(|(_, format)| format),
+                    attachments.transparency,
+                )
+            })
+            .collect()
+    }
+
+    fn draw_meshes<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        meshes: &[&'a MeshGpuData],
+    ) {
+        for mesh in meshes {
+            let (_, bind_group) = &self.globals_bind_groups[mesh.receive_shadow as usize];
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw_mesh(mesh);
         }
     }
 
+    pub fn background(&self) -> &Background {
+        self.background.background()
+    }
+
+    /// Accepts a flat color, a [`Background::gradient`] or a [`CubeTexture`](crate::CubeTexture)
+    /// drawn as a skybox.
     pub fn set_background<T>(&mut self, background: T)
     where
-        T: Into<wgpu::Color>,
+        T: Into<Background>,
+    {
+        self.background.set_background(background.into());
+    }
+
+    pub fn set_camera<C>(&mut self, camera: &C)
+    where
+        C: Camera,
     {
-        self.background = background.into();
+        self.camera = CameraView::new(camera);
+    }
+
+    pub fn max_lights(&self) -> usize {
+        self.max_lights
+    }
+
+    /// Limits how many lights are uploaded per frame. Lights beyond the limit are ignored
+    /// in slot order, where a light takes the slot of the last removed one. Devices without
+    /// storage buffers, such as WebGL2, support at most 64 lights. Zero disables lighting.
+    pub fn set_max_lights(&mut self, max_lights: usize) {
+        let max_lights = if uniform_lights(&self.device) && max_lights > UNIFORM_MAX_LIGHTS {
+            log::warn!("at most {UNIFORM_MAX_LIGHTS} lights are supported by the device");
+            UNIFORM_MAX_LIGHTS
+        } else {
+            max_lights
+        };
+
+        self.max_lights = max_lights;
+        self.lights_buffer = create_lights_buffer(&self.device, max_lights);
+        self.globals_bind_groups = create_globals_bind_groups(
+            &self.device,
+            &self.globals,
+            &self.lights_buffer,
+            &self.shadow_atlas,
+        );
+    }
+
+    pub fn max_point_shadows(&self) -> usize {
+        self.max_point_shadows
+    }
+
+    /// Limits how many point lights cast shadows, each of them takes six shadow maps.
+    pub fn set_max_point_shadows(&mut self, max_point_shadows: usize) {
+        self.max_point_shadows = max_point_shadows;
+    }
+
+    pub fn shadow_map_size(&self) -> u32 {
+        self.shadow_atlas.tile_size()
+    }
+
+    /// Resolution of each shadow map in the atlas shared by all shadow casting lights,
+    /// limited so that the atlas fits the maximum texture size of the device.
+    pub fn set_shadow_map_size(&mut self, size: u32) {
+        self.shadow_atlas = ShadowAtlas::new(&self.device, size);
+        self.globals_bind_groups = create_globals_bind_groups(
+            &self.device,
+            &self.globals,
+            &self.lights_buffer,
+            &self.shadow_atlas,
+        );
     }
 
     pub fn meshes_len(&self) -> usize {
-        self.meshes.len() - self.mesh_recycle_ids.len()
+        self.meshes.len()
     }
 
     pub fn add_mesh<M>(&mut self, mesh: M) -> MeshID<M>
     where
         M: MeshBase + 'static,
     {
-        let mesh = Some(Box::new(mesh) as Box<dyn MeshBase>);
+        let (index, generation) = self
+            .meshes
+            .insert(Entry::new(Box::new(mesh) as Box<dyn MeshBase>));
 
-        let index = match self.mesh_recycle_ids.pop() {
-            Some(index) => {
-                debug_assert!(self.meshes[index].is_none());
-                self.meshes[index] = mesh;
-                index
-            }
-            None => {
-                self.meshes.push(mesh);
-                self.meshes.len() - 1
-            }
-        };
+        MeshID::new(self.id, index, generation)
+    }
 
-        MeshID::new(self.id, index)
+    /// Whether the mesh is in this scene and has not been removed.
+    pub fn contains<M: ?Sized>(&self, mesh: &MeshID<M>) -> bool {
+        self.check_mesh(mesh).is_ok()
     }
 
-    pub fn remove_mesh<M>(&mut self, mesh_id: MeshID<M>) {
-        assert_eq!(self.id, mesh_id.scene_id);
+    pub fn try_remove_mesh<M>(&mut self, mesh_id: MeshID<M>) -> Result<(), SceneError>
+    where
+        M: ?Sized + 'static,
+    {
+        self.check_mesh(&mesh_id)?;
+        check_type::<M, dyn MeshBase>(self.meshes[mesh_id.index].item.as_any())?;
 
-        self.meshes.get_mut(mesh_id.index).unwrap().take().unwrap();
-        self.mesh_recycle_ids.push(mesh_id.index);
+        self.take_mesh(mesh_id.index);
+        Ok(())
     }
 
-    pub fn get_mesh_ref<M>(&self, mesh: &MeshID<M>) -> &M
+    pub fn try_get_mesh<M>(&self, mesh: &MeshID<M>) -> Result<&M, SceneError>
     where
         M: MeshBase + 'static,
     {
-        assert_eq!(self.id, mesh.scene_id);
+        self.check_mesh(mesh)?;
 
-        self.meshes
-            .get(mesh.index)
-            .unwrap()
-            .as_ref()
-            .unwrap()
+        self.meshes[mesh.index]
+            .item
             .as_any()
             .downcast_ref()
-            .unwrap()
+            .ok_or(SceneError::TypeMismatch)
+    }
+
+    pub fn try_get_mesh_mut<M>(&mut self, mesh: &MeshID<M>) -> Result<&mut M, SceneError>
+    where
+        M: MeshBase + 'static,
+    {
+        self.check_mesh(mesh)?;
+
+        self.meshes[mesh.index]
+            .item
+            .as_any_mut()
+            .downcast_mut()
+            .ok_or(SceneError::TypeMismatch)
+    }
+
+    /// Panics where [`try_remove_mesh`](Self::try_remove_mesh) returns an error.
+    pub fn remove_mesh<M>(&mut self, mesh_id: MeshID<M>)
+    where
+        M: ?Sized + 'static,
+    {
+        self.try_remove_mesh(mesh_id)
+            .unwrap_or_else(|e| panic!("{e}"))
     }
 
+    /// Panics where [`try_get_mesh`](Self::try_get_mesh) returns an error.
+    pub fn get_mesh_ref<M>(&self, mesh: &MeshID<M>) -> &M
+    where
+        M: MeshBase + 'static,
+    {
+        self.try_get_mesh(mesh).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Panics where [`try_get_mesh_mut`](Self::try_get_mesh_mut) returns an error.
     pub fn get_mesh_mut<M>(&mut self, mesh: &MeshID<M>) -> &mut M
     where
         M: MeshBase + 'static,
     {
-        assert_eq!(self.id, mesh.scene_id);
+        self.try_get_mesh_mut(mesh)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
 
+    /// The meshes of the scene with their IDs, in no particular order.
+    pub fn meshes(&self) -> impl Iterator<Item = (AnyMeshID, &dyn MeshBase)> {
+        let scene_id = self.id;
+        self.meshes.iter().map(move |(index, generation, entry)| {
+            (
+                MeshID::new(scene_id, index, generation),
+                entry.item.as_ref(),
+            )
+        })
+    }
+
+    pub fn meshes_mut(&mut self) -> impl Iterator<Item = (AnyMeshID, &mut dyn MeshBase)> {
+        let scene_id = self.id;
         self.meshes
-            .get_mut(mesh.index)
-            .unwrap()
-            .as_mut()
-            .unwrap()
+            .iter_mut()
+            .map(move |(index, generation, entry)| {
+                (
+                    MeshID::new(scene_id, index, generation),
+                    entry.item.as_mut() as &mut dyn MeshBase,
+                )
+            })
+    }
+
+    /// The meshes of type `M`.
+    pub fn meshes_of<M>(&self) -> impl Iterator<Item = (MeshID<M>, &M)>
+    where
+        M: MeshBase + 'static,
+    {
+        self.meshes()
+            .filter_map(|(id, mesh)| Some((id.typed(), mesh.as_any().downcast_ref()?)))
+    }
+
+    pub fn meshes_of_mut<M>(&mut self) -> impl Iterator<Item = (MeshID<M>, &mut M)>
+    where
+        M: MeshBase + 'static,
+    {
+        self.meshes_mut()
+            .filter_map(|(id, mesh)| Some((id.typed(), mesh.as_any_mut().downcast_mut()?)))
+    }
+
+    /// Removes the meshes for which `f` returns `false`.
+    pub fn retain_meshes<F>(&mut self, mut f: F)
+    where
+        F: FnMut(AnyMeshID, &mut dyn MeshBase) -> bool,
+    {
+        for index in 0..self.meshes.end() {
+            let Some((generation, entry)) = self.meshes.get_mut(index) else {
+                continue;
+            };
+            if !f(MeshID::new(self.id, index, generation), entry.item.as_mut()) {
+                self.take_mesh(index);
+            }
+        }
+    }
+
+    pub fn clear_meshes(&mut self) {
+        self.retain_meshes(|_, _| false);
+    }
+
+    /// Removes all meshes, returning them with the IDs they had.
+    pub fn drain_meshes(&mut self) -> std::vec::IntoIter<(AnyMeshID, Box<dyn MeshBase>)> {
+        let ids: Vec<_> = self.meshes().map(|(id, _)| id).collect();
+        let meshes: Vec<_> = ids
+            .into_iter()
+            .map(|id| (id, self.take_mesh(id.index)))
+            .collect();
+        meshes.into_iter()
+    }
+
+    /// `None` if the mesh has no name or is not in the scene.
+    pub fn mesh_name<M: ?Sized>(&self, mesh: &MeshID<M>) -> Option<&str> {
+        self.mesh_info(mesh).ok()?.name.as_deref()
+    }
+
+    pub fn set_mesh_name<M: ?Sized>(
+        &mut self,
+        mesh: &MeshID<M>,
+        name: Option<String>,
+    ) -> Result<(), SceneError> {
+        self.mesh_info_mut(mesh)?.name = name;
+        Ok(())
+    }
+
+    /// A mesh named `name`, any of them if there are several.
+    pub fn find_mesh(&self, name: &str) -> Option<AnyMeshID> {
+        self.meshes()
+            .map(|(id, _)| id)
+            .find(|id| self.meshes[id.index].info.name.as_deref() == Some(name))
+    }
+
+    pub fn mesh_tags<M: ?Sized>(&self, mesh: &MeshID<M>) -> Result<&[String], SceneError> {
+        Ok(&self.mesh_info(mesh)?.tags)
+    }
+
+    pub fn add_mesh_tag<M: ?Sized>(
+        &mut self,
+        mesh: &MeshID<M>,
+        tag: impl Into<String>,
+    ) -> Result<(), SceneError> {
+        let tag = tag.into();
+        let tags = &mut self.mesh_info_mut(mesh)?.tags;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        Ok(())
+    }
+
+    pub fn remove_mesh_tag<M: ?Sized>(
+        &mut self,
+        mesh: &MeshID<M>,
+        tag: &str,
+    ) -> Result<(), SceneError> {
+        self.mesh_info_mut(mesh)?.tags.retain(|t| t != tag);
+        Ok(())
+    }
+
+    pub fn meshes_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = AnyMeshID> + 'a {
+        self.meshes()
+            .map(|(id, _)| id)
+            .filter(move |id| self.meshes[id.index].info.tags.iter().any(|t| t == tag))
+    }
+
+    /// The user data of the mesh if it is of type `T`, `None` if the mesh is not in the
+    /// scene.
+    pub fn mesh_user_data<M: ?Sized, T: Any>(&self, mesh: &MeshID<M>) -> Option<&T> {
+        self.mesh_info(mesh)
+            .ok()?
+            .user_data
+            .as_ref()?
+            .downcast_ref()
+    }
+
+    pub fn mesh_user_data_mut<M: ?Sized, T: Any>(&mut self, mesh: &MeshID<M>) -> Option<&mut T> {
+        self.mesh_info_mut(mesh)
+            .ok()?
+            .user_data
+            .as_mut()?
+            .downcast_mut()
+    }
+
+    /// Replaces the user data of the mesh.
+    pub fn set_mesh_user_data<M: ?Sized, T: Any>(
+        &mut self,
+        mesh: &MeshID<M>,
+        data: T,
+    ) -> Result<(), SceneError> {
+        self.mesh_info_mut(mesh)?.user_data = Some(Box::new(data));
+        Ok(())
+    }
+
+    pub fn lights_len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn add_light<L>(&mut self, light: L) -> LightID<L>
+    where
+        L: Light + 'static,
+    {
+        let (index, generation) = self
+            .lights
+            .insert(Entry::new(Box::new(light) as Box<dyn Light>));
+
+        LightID::new(self.id, index, generation)
+    }
+
+    /// Whether the light is in this scene and has not been removed.
+    pub fn contains_light<L>(&self, light: &LightID<L>) -> bool {
+        self.check_light(light).is_ok()
+    }
+
+    pub fn try_remove_light<L>(&mut self, light_id: LightID<L>) -> Result<(), SceneError>
+    where
+        L: Light + 'static,
+    {
+        self.try_get_light(&light_id)?;
+
+        self.lights.remove(light_id.index);
+        Ok(())
+    }
+
+    pub fn try_get_light<L>(&self, light: &LightID<L>) -> Result<&L, SceneError>
+    where
+        L: Light + 'static,
+    {
+        self.check_light(light)?;
+
+        self.lights[light.index]
+            .item
+            .as_any()
+            .downcast_ref()
+            .ok_or(SceneError::TypeMismatch)
+    }
+
+    pub fn try_get_light_mut<L>(&mut self, light: &LightID<L>) -> Result<&mut L, SceneError>
+    where
+        L: Light + 'static,
+    {
+        self.check_light(light)?;
+
+        self.lights[light.index]
+            .item
             .as_any_mut()
             .downcast_mut()
-            .unwrap()
+            .ok_or(SceneError::TypeMismatch)
+    }
+
+    /// Panics where [`try_remove_light`](Self::try_remove_light) returns an error.
+    pub fn remove_light<L>(&mut self, light_id: LightID<L>)
+    where
+        L: Light + 'static,
+    {
+        self.try_remove_light(light_id)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Panics where [`try_get_light`](Self::try_get_light) returns an error.
+    pub fn get_light_ref<L>(&self, light: &LightID<L>) -> &L
+    where
+        L: Light + 'static,
+    {
+        self.try_get_light(light).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Panics where [`try_get_light_mut`](Self::try_get_light_mut) returns an error.
+    pub fn get_light_mut<L>(&mut self, light: &LightID<L>) -> &mut L
+    where
+        L: Light + 'static,
+    {
+        self.try_get_light_mut(light)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn nodes_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Adds a node without a parent, placed by `transform` in world space.
+    pub fn add_node(&mut self, transform: Instance) -> NodeID {
+        let (index, generation) = self.nodes.insert(Node::new(transform));
+        self.mark_dirty(index);
+
+        NodeID::new(self.id, index, generation)
+    }
+
+    /// Whether the node is in this scene and has not been removed.
+    pub fn contains_node(&self, node: &NodeID) -> bool {
+        self.check_node(node).is_ok()
+    }
+
+    /// Children of the node become roots keeping their transform, meshes, lights and the
+    /// camera attached to it are detached.
+    pub fn remove_node(&mut self, node_id: NodeID) -> Result<(), SceneError> {
+        self.check_node(&node_id)?;
+
+        let index = node_id.index;
+        let node = self.nodes.remove(index).unwrap();
+        if let Some(parent) = node.parent {
+            self.nodes[parent].children.retain(|&i| i != index);
+        }
+        for child in node.children {
+            self.nodes[child].parent = None;
+            self.mark_dirty(child);
+        }
+        for mesh in node.meshes {
+            self.meshes[mesh].node = None;
+        }
+        for (_, _, entry) in self.lights.iter_mut() {
+            if entry.node == Some(index) {
+                entry.node = None;
+            }
+        }
+        if self.camera_node == Some(index) {
+            self.camera_node = None;
+        }
+        Ok(())
     }
+
+    pub fn node_transform(&self, node: &NodeID) -> Result<Instance, SceneError> {
+        self.check_node(node)?;
+
+        Ok(self.nodes[node.index].transform)
+    }
+
+    /// The world matrices of the node and its descendants are updated when they are next
+    /// read or rendered.
+    pub fn set_node_transform(
+        &mut self,
+        node: &NodeID,
+        transform: Instance,
+    ) -> Result<(), SceneError> {
+        self.check_node(node)?;
+
+        self.nodes[node.index].transform = transform;
+        self.mark_dirty(node.index);
+        Ok(())
+    }
+
+    pub fn node_world_matrix(&self, node: &NodeID) -> Result<Matrix4<f32>, SceneError> {
+        self.check_node(node)?;
+
+        self.resolve_worlds();
+        Ok(self.nodes[node.index].world.get())
+    }
+
+    pub fn node_parent(&self, node: &NodeID) -> Result<Option<NodeID>, SceneError> {
+        self.check_node(node)?;
+
+        Ok(self.nodes[node.index]
+            .parent
+            .map(|index| self.node_id(index)))
+    }
+
+    pub fn node_children(
+        &self,
+        node: &NodeID,
+    ) -> Result<impl Iterator<Item = NodeID> + '_, SceneError> {
+        self.check_node(node)?;
+
+        Ok(self.nodes[node.index]
+            .children
+            .iter()
+            .map(|&index| self.node_id(index)))
+    }
+
+    /// The transform of the node becomes relative to `parent`, or to the world for `None`.
+    /// Returns [`SceneError::Cycle`] if `parent` is the node or one of its descendants.
+    pub fn set_node_parent(
+        &mut self,
+        node: &NodeID,
+        parent: Option<&NodeID>,
+    ) -> Result<(), SceneError> {
+        self.check_node(node)?;
+        let parent = self.node_index(parent)?;
+
+        let index = node.index;
+        let mut ancestor = parent;
+        while let Some(i) = ancestor {
+            if i == index {
+                return Err(SceneError::Cycle);
+            }
+            ancestor = self.nodes[i].parent;
+        }
+
+        if let Some(old) = self.nodes[index].parent {
+            self.nodes[old].children.retain(|&i| i != index);
+        }
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(index);
+        }
+        self.nodes[index].parent = parent;
+        self.mark_dirty(index);
+        Ok(())
+    }
+
+    /// Places the mesh relative to `node`, or to the world for `None`.
+    pub fn attach_mesh<M: ?Sized>(
+        &mut self,
+        mesh: &MeshID<M>,
+        node: Option<&NodeID>,
+    ) -> Result<(), SceneError> {
+        self.check_mesh(mesh)?;
+        let node = self.node_index(node)?;
+
+        if let Some(old) = self.meshes[mesh.index].node {
+            self.nodes[old].meshes.retain(|&i| i != mesh.index);
+        }
+        if let Some(index) = node {
+            self.nodes[index].meshes.push(mesh.index);
+        }
+        self.meshes[mesh.index].node = node;
+        Ok(())
+    }
+
+    /// Places the light relative to `node`, or to the world for `None`.
+    pub fn attach_light<L>(
+        &mut self,
+        light: &LightID<L>,
+        node: Option<&NodeID>,
+    ) -> Result<(), SceneError> {
+        self.check_light(light)?;
+        let node = self.node_index(node)?;
+
+        self.lights[light.index].node = node;
+        Ok(())
+    }
+
+    /// Places the camera of [`set_camera`](Self::set_camera) relative to `node`, or to the
+    /// world for `None`. Cameras of render targets are not affected.
+    pub fn set_camera_node(&mut self, node: Option<&NodeID>) -> Result<(), SceneError> {
+        self.camera_node = self.node_index(node)?;
+        Ok(())
+    }
+
+    fn check_mesh<M: ?Sized>(&self, mesh: &MeshID<M>) -> Result<(), SceneError> {
+        if mesh.scene_id != self.id {
+            Err(SceneError::WrongScene)
+        } else if !self.meshes.contains(mesh.index, mesh.generation) {
+            Err(SceneError::Removed)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_light<L>(&self, light: &LightID<L>) -> Result<(), SceneError> {
+        if light.scene_id != self.id {
+            Err(SceneError::WrongScene)
+        } else if !self.lights.contains(light.index, light.generation) {
+            Err(SceneError::Removed)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_node(&self, node: &NodeID) -> Result<(), SceneError> {
+        if node.scene_id != self.id {
+            Err(SceneError::WrongScene)
+        } else if !self.nodes.contains(node.index, node.generation) {
+            Err(SceneError::Removed)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn node_index(&self, node: Option<&NodeID>) -> Result<Option<usize>, SceneError> {
+        node.map(|node| self.check_node(node).map(|_| node.index))
+            .transpose()
+    }
+
+    fn node_id(&self, index: usize) -> NodeID {
+        let (generation, _) = self.nodes.get(index).unwrap();
+        NodeID::new(self.id, index, generation)
+    }
+
+    fn take_mesh(&mut self, index: usize) -> Box<dyn MeshBase> {
+        let entry = self.meshes.remove(index).unwrap();
+        if let Some(node) = entry.node {
+            self.nodes[node].meshes.retain(|&i| i != index);
+        }
+        entry.item
+    }
+
+    fn mesh_info<M: ?Sized>(&self, mesh: &MeshID<M>) -> Result<&MeshInfo, SceneError> {
+        self.check_mesh(mesh)?;
+        Ok(&self.meshes[mesh.index].info)
+    }
+
+    fn mesh_info_mut<M: ?Sized>(&mut self, mesh: &MeshID<M>) -> Result<&mut MeshInfo, SceneError> {
+        self.check_mesh(mesh)?;
+        Ok(&mut self.meshes[mesh.index].info)
+    }
+
+    // the world matrices of the node and its descendants are recomputed by the next
+    // `resolve_worlds`
+    fn mark_dirty(&mut self, index: usize) {
+        self.dirty_nodes.get_mut().insert(index);
+    }
+
+    // recomputes the world matrices of the dirty nodes and their descendants, once for
+    // any number of changes since the last call
+    fn resolve_worlds(&self) {
+        let mut dirty = self.dirty_nodes.borrow_mut();
+        if dirty.is_empty() {
+            return;
+        }
+
+        // nodes below another dirty node are resolved with it, removed ones are skipped
+        let mut stack: Vec<_> = dirty
+            .iter()
+            .copied()
+            .filter(|&index| {
+                let Some((_, node)) = self.nodes.get(index) else {
+                    return false;
+                };
+                let mut ancestor = node.parent;
+                while let Some(i) = ancestor {
+                    if dirty.contains(&i) {
+                        return false;
+                    }
+                    ancestor = self.nodes[i].parent;
+                }
+                true
+            })
+            .collect();
+        dirty.clear();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let parent = node
+                .parent
+                .map_or_else(Matrix4::identity, |parent| self.nodes[parent].world.get());
+            node.world.set(parent * node.transform.matrix());
+            stack.extend_from_slice(&node.children);
+        }
+    }
+}
+
+// whether `item` is an `M`, any type matches the trait object `Dyn`
+fn check_type<M, Dyn>(item: &dyn Any) -> Result<(), SceneError>
+where
+    M: ?Sized + 'static,
+    Dyn: ?Sized + 'static,
+{
+    if TypeId::of::<M>() == TypeId::of::<Dyn>() || Any::type_id(item) == TypeId::of::<M>() {
+        Ok(())
+    } else {
+        Err(SceneError::TypeMismatch)
+    }
+}
+
+fn depth_attachment<'a>(
+    attachments: &Attachments<'a>,
+    load: wgpu::LoadOp<f32>,
+) -> Option<wgpu::RenderPassDepthStencilAttachment<'a>> {
+    attachments
+        .depth
+        .map(|(view, _)| wgpu::RenderPassDepthStencilAttachment {
+            view,
+            depth_ops: Some(wgpu::Operations { load, store: true }),
+            stencil_ops: None,
+        })
+}
+
+fn create_lights_buffer(device: &wgpu::Device, max_lights: usize) -> wgpu::Buffer {
+    // the uniform array has a fixed length, a binding can't be empty
+    let len = if uniform_lights(device) {
+        UNIFORM_MAX_LIGHTS
+    } else {
+        max_lights.max(1)
+    };
+
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Lights Buffer"),
+        contents: bytemuck::cast_slice(&vec![LightRaw::zeroed(); len]),
+        usage: lights_buffer_usage(device) | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+fn create_globals_bind_groups(
+    device: &wgpu::Device,
+    globals: &GlobalParams,
+    lights_buffer: &wgpu::Buffer,
+    shadow_atlas: &ShadowAtlas,
+) -> [(wgpu::Buffer, wgpu::BindGroup); 2] {
+    let bindings = GlobalBindings {
+        lights: lights_buffer,
+        shadows: &shadow_atlas.shadows_buffer,
+        shadow_atlas: &shadow_atlas.view,
+        shadow_sampler: &shadow_atlas.sampler,
+    };
+
+    [
+        globals.buffer_bind_group(device, &bindings),
+        globals.buffer_bind_group(device, &bindings),
+    ]
 }
@@ -0,0 +1,708 @@
+//! WGSL composition shared by the built-in materials and [`ShaderMaterial`](crate::material::ShaderMaterial).
+//!
+//! Sources are run through [`preprocess`], which understands these directives on lines of
+//! their own:
+//!
+//! - `#include "name"` inserts a chunk once per shader: `camera` declares `globals`,
+//!   `lights` the light and shadow bindings of group 0, `lighting` the `incident_light`
+//!   and `facing_normal` functions, `vertex` the `VertexInput` and `instance` the `InstanceInput` of meshes,
+//!   with the `model_matrix` and `normal_matrix` helpers, `output` the `FragmentOutput` of
+//!   meshes returned by `fragment_output`, `effect` the input and fullscreen vertex shader
+//!   of effects.
+//! - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` keep lines depending on whether
+//!   `NAME` is defined, by the [`ShaderFeatures`] of the material or by `#define NAME`.
+//!
+//! [`compile`] also validates the result with naga, so that errors are reported as a
+//! [`ShaderError`] in the file they were written in instead of by the device.
+//!
+//! During development the built-in shaders and chunks can be loaded from a copy of
+//! `src/shaders` with [`watch`] instead of the sources compiled into the crate, and are
+//! replaced in the pipeline caches by [`reload`] after they change on disk.
+
+use std::{
+    collections::{HashMap, HashSet},
+    error, fmt, fs, ops,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+
+const SHADERS: [(&str, &str); 17] = [
+    ("background.wgsl", include_str!("shaders/background.wgsl")),
+    ("basic.wgsl", include_str!("shaders/basic.wgsl")),
+    ("basic_mesh.wgsl", include_str!("shaders/basic_mesh.wgsl")),
+    ("bloom.wgsl", include_str!("shaders/bloom.wgsl")),
+    (
+        "color_grading.wgsl",
+        include_str!("shaders/color_grading.wgsl"),
+    ),
+    ("equirect.wgsl", include_str!("shaders/equirect.wgsl")),
+    ("error.wgsl", include_str!("shaders/error.wgsl")),
+    ("fxaa.wgsl", include_str!("shaders/fxaa.wgsl")),
+    ("lambert.wgsl", include_str!("shaders/lambert.wgsl")),
+    ("line2.wgsl", include_str!("shaders/line2.wgsl")),
+    ("mipmap.wgsl", include_str!("shaders/mipmap.wgsl")),
+    (
+        "oit_composite.wgsl",
+        include_str!("shaders/oit_composite.wgsl"),
+    ),
+    ("phong.wgsl", include_str!("shaders/phong.wgsl")),
+    ("shadow.wgsl", include_str!("shaders/shadow.wgsl")),
+    ("standard.wgsl", include_str!("shaders/standard.wgsl")),
+    (
+        "tone_mapping.wgsl",
+        include_str!("shaders/tone_mapping.wgsl"),
+    ),
+    ("vignette.wgsl", include_str!("shaders/vignette.wgsl")),
+];
+
+const CHUNKS: [(&str, &str); 7] = [
+    ("camera", include_str!("shaders/chunks/camera.wgsl")),
+    ("effect", include_str!("shaders/chunks/effect.wgsl")),
+    ("instance", include_str!("shaders/chunks/instance.wgsl")),
+    ("lighting", include_str!("shaders/chunks/lighting.wgsl")),
+    ("lights", include_str!("shaders/chunks/lights.wgsl")),
+    ("output", include_str!("shaders/chunks/output.wgsl")),
+    ("vertex", include_str!("shaders/chunks/vertex.wgsl")),
+];
+
+/// Optional parts of a material, each compiled into its own shader variant and part of the
+/// [`PipelineKey`](crate::PipelineKey).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+    /// A color map is sampled, defines `MAP`.
+    pub const MAP: Self = Self(1);
+    /// A tangent space normal map is sampled, defines `NORMAL_MAP`.
+    pub const NORMAL_MAP: Self = Self(1 << 1);
+    /// An environment map lights the surface, defines `ENV_MAP`.
+    pub const ENV_MAP: Self = Self(1 << 2);
+    /// Written into the weighted blended transparency targets, defines `OIT`. Set by the
+    /// scene for transparent meshes, see [`Transparency`](crate::Transparency).
+    pub const OIT: Self = Self(1 << 3);
+    /// Lights and shadows are read from uniform arrays, defines `UNIFORM_LIGHTS`. Set on
+    /// devices without storage buffers such as WebGL2.
+    pub const UNIFORM_LIGHTS: Self = Self(1 << 4);
+
+    const NAMES: [(Self, &'static str); 5] = [
+        (Self::MAP, "MAP"),
+        (Self::NORMAL_MAP, "NORMAL_MAP"),
+        (Self::ENV_MAP, "ENV_MAP"),
+        (Self::OIT, "OIT"),
+        (Self::UNIFORM_LIGHTS, "UNIFORM_LIGHTS"),
+    ];
+
+    /// The features every mesh drawn on `device` needs.
+    pub(crate) fn device(device: &wgpu::Device) -> Self {
+        let mut features = Self::empty();
+        features.set(Self::UNIFORM_LIGHTS, crate::params::uniform_lights(device));
+        features
+    }
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn set(&mut self, other: Self, enabled: bool) {
+        if enabled {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+
+    /// Names defined for the preprocessor.
+    pub fn defines(self) -> impl Iterator<Item = &'static str> {
+        Self::NAMES
+            .into_iter()
+            .filter(move |(feature, _)| self.contains(*feature))
+            .map(|(_, name)| name)
+    }
+
+    /// Every combination of the features.
+    pub fn variants() -> impl Iterator<Item = Self> {
+        (0..1 << Self::NAMES.len()).map(Self)
+    }
+}
+
+impl ops::BitOr for ShaderFeatures {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitOrAssign for ShaderFeatures {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Error of [`preprocess`], at a 1-based line of the source or of the chunk it occurred in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreprocessError {
+    /// `#include` of a name that is not a chunk.
+    UnknownChunk { line: usize, name: String },
+    /// An unknown or malformed directive, or `#else` and `#endif` outside of a conditional.
+    InvalidDirective { line: usize, directive: String },
+    /// `#ifdef` or `#ifndef` without `#endif`.
+    Unterminated { line: usize },
+}
+
+impl PreprocessError {
+    fn line(&self) -> usize {
+        match self {
+            Self::UnknownChunk { line, .. }
+            | Self::InvalidDirective { line, .. }
+            | Self::Unterminated { line } => *line,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::UnknownChunk { name, .. } => format!("unknown shader chunk \"{name}\""),
+            Self::InvalidDirective { directive, .. } => format!("invalid directive `{directive}`"),
+            Self::Unterminated { .. } => "conditional without #endif".to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line(), self.message())
+    }
+}
+
+impl error::Error for PreprocessError {}
+
+/// Error of [`compile`], located in the file or chunk it was written in rather than in the
+/// preprocessed source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderError {
+    /// Label or path the shader was compiled as, or `name.wgsl` for a chunk.
+    pub file: String,
+    /// 1-based, 0 when the error has no position.
+    pub line: usize,
+    /// 1-based, 0 when the error has no position.
+    pub column: usize,
+    pub message: String,
+    /// The line of the error with a caret under the column.
+    pub excerpt: String,
+}
+
+impl ShaderError {
+    fn new(file: &str, source: &str, line: usize, column: usize, message: String) -> Self {
+        let excerpt = match source.lines().nth(line.wrapping_sub(1)) {
+            Some(text) => {
+                let number = line.to_string();
+                format!(
+                    "{number} | {text}\n{:width$} | {:column$}^",
+                    "",
+                    "",
+                    width = number.len(),
+                    column = column.saturating_sub(1),
+                )
+            }
+            None => String::new(),
+        };
+
+        Self {
+            file: file.to_owned(),
+            line,
+            column,
+            message,
+            excerpt,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )?;
+        if !self.excerpt.is_empty() {
+            write!(f, "\n{}", self.excerpt)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ShaderError {}
+
+/// Expands the directives of `source` with `defines` defined, see the [module](self)
+/// documentation.
+pub fn preprocess<'a, I>(source: &str, defines: I) -> Result<String, PreprocessError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut composer = Composer::new(defines);
+    composer.files.push((String::new(), source.into()));
+    composer.expand(0).map_err(|(_, error)| error)?;
+
+    Ok(composer.out)
+}
+
+/// Preprocesses `source` like [`preprocess`] and validates the result with naga, returning
+/// the WGSL to create a shader module from. `file` names the source in errors.
+pub fn compile<'a, I>(file: &str, source: &str, defines: I) -> Result<String, ShaderError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut composer = Composer::new(defines);
+    composer.append(file, source)?;
+    composer.finish()
+}
+
+/// Source of a variant of the built-in shader `file` of `src/shaders`, which compiles
+/// without errors.
+pub(crate) fn builtin(file: &str, features: ShaderFeatures) -> wgpu::ShaderSource<'static> {
+    match compile(file, &builtin_source(file), features.defines()) {
+        Ok(source) => wgpu::ShaderSource::Wgsl(source.into()),
+        Err(e) => panic!("invalid built-in shader {e}"),
+    }
+}
+
+/// The built-in shader `file` before preprocessing, reloaded if it is watched.
+pub(crate) fn builtin_source(file: &str) -> Arc<str> {
+    if let Some(reloaded) = RELOADED.read().unwrap().get(file) {
+        return Arc::clone(reloaded);
+    }
+    let (_, source) = SHADERS
+        .iter()
+        .find(|(name, _)| *name == file)
+        .expect("unknown built-in shader");
+    (*source).into()
+}
+
+// valid sources read by `reload`, by path relative to the watched directory
+type Sources = Arc<HashMap<String, Arc<str>>>;
+
+static RELOADED: Lazy<RwLock<Sources>> = Lazy::new(Default::default);
+
+// incremented whenever `RELOADED` changes
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static WATCHER: Mutex<Option<Watcher>> = Mutex::new(None);
+
+/// How often the [`WGPURenderer`](crate::WGPURenderer) looks for changed shaders.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+struct Watcher {
+    dir: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    // the files as last read, valid or not
+    sources: HashMap<String, Arc<str>>,
+    // files changed since the sources were last replaced
+    pending: HashSet<String>,
+    last_poll: Option<Instant>,
+}
+
+/// Loads the built-in shaders and chunks from `dir` from the next [`reload`] on, a
+/// directory laid out like `src/shaders` with the chunks in `chunks`. Files missing from
+/// it keep the sources compiled into the crate.
+///
+/// Meant for development, a [`WGPURenderer`](crate::WGPURenderer) created with
+/// [`with_hot_reload`](crate::WGPURendererOption::with_hot_reload) watches and reloads
+/// on its own.
+pub fn watch<P>(dir: P)
+where
+    P: AsRef<Path>,
+{
+    *WATCHER.lock().unwrap() = Some(Watcher {
+        dir: dir.as_ref().to_owned(),
+        modified: HashMap::new(),
+        sources: HashMap::new(),
+        pending: HashSet::new(),
+        last_poll: None,
+    });
+}
+
+/// [`reload`] unless it was polled less than [`POLL_INTERVAL`] ago, called every frame.
+pub(crate) fn poll() -> Result<bool, ShaderError> {
+    {
+        let mut watcher = WATCHER.lock().unwrap();
+        let Some(watcher) = watcher.as_mut() else {
+            return Ok(false);
+        };
+        if watcher
+            .last_poll
+            .is_some_and(|last| last.elapsed() < POLL_INTERVAL)
+        {
+            return Ok(false);
+        }
+        watcher.last_poll = Some(Instant::now());
+    }
+
+    reload()
+}
+
+/// Reads the files of the watched directory changed since the last call and validates the
+/// built-in shaders using them, for every [`ShaderFeatures`] variant they test and against
+/// the entry points of the compiled in source. Removed files revert to the compiled in
+/// source. Pipelines created from the built-in shaders are recreated with the new sources
+/// on their next use if all are valid, otherwise the previous sources and pipelines are
+/// kept and the first error is returned.
+///
+/// Returns whether the sources were replaced, false when nothing is watched or changed.
+pub fn reload() -> Result<bool, ShaderError> {
+    let mut watcher = WATCHER.lock().unwrap();
+    let Some(watcher) = watcher.as_mut() else {
+        return Ok(false);
+    };
+
+    let mut changed = false;
+    let mut present = HashSet::new();
+    for prefix in ["", "chunks/"] {
+        let Ok(entries) = fs::read_dir(watcher.dir.join(prefix)) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "wgsl")
+            {
+                continue;
+            }
+            let Ok(modified) = fs::metadata(&path).and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            let file = format!("{prefix}{}", path.file_name().unwrap().to_string_lossy());
+            present.insert(file.clone());
+            if watcher.modified.get(&path) == Some(&modified) {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path)
+                .map_err(|e| ShaderError::new(&file, "", 0, 0, e.to_string()))?;
+            watcher.modified.insert(path, modified);
+            watcher.sources.insert(file.clone(), source.into());
+            watcher.pending.insert(file);
+            changed = true;
+        }
+    }
+    let removed: Vec<_> = watcher
+        .sources
+        .keys()
+        .filter(|file| !present.contains(*file))
+        .cloned()
+        .collect();
+    for file in removed {
+        watcher.modified.remove(&watcher.dir.join(&file));
+        watcher.sources.remove(&file);
+        watcher.pending.insert(file);
+        changed = true;
+    }
+    if !changed {
+        return Ok(false);
+    }
+
+    let sources = Arc::new(watcher.sources.clone());
+    for (file, builtin) in SHADERS {
+        let source = sources.get(file).map_or(builtin, |source| source);
+        let (files, names) = dependencies(file, source, &sources);
+        if files.is_disjoint(&watcher.pending) {
+            continue;
+        }
+
+        // only the variants of the features the shader tests
+        let tested = ShaderFeatures::NAMES
+            .into_iter()
+            .filter(|(_, name)| names.contains(*name))
+            .fold(ShaderFeatures::empty(), |tested, (feature, _)| {
+                tested | feature
+            });
+        for features in ShaderFeatures::variants().filter(|v| tested.contains(*v)) {
+            let mut composer = Composer::with_sources(features.defines(), Arc::clone(&sources));
+            composer.append(file, source)?;
+            let module = composer.module()?;
+
+            let mut original = Composer::with_sources(features.defines(), Sources::default());
+            original.append(file, builtin)?;
+            for entry_point in original.module()?.entry_points {
+                let stage = entry_point.stage;
+                if !module
+                    .entry_points
+                    .iter()
+                    .any(|e| e.name == entry_point.name && e.stage == stage)
+                {
+                    let message = format!("missing {stage:?} entry point `{}`", entry_point.name);
+                    return Err(ShaderError::new(file, "", 0, 0, message));
+                }
+            }
+        }
+    }
+
+    watcher.pending.clear();
+    *RELOADED.write().unwrap() = sources;
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+    Ok(true)
+}
+
+// the files the shader `file` is composed of, with the chunks included in any variant,
+// and the names tested by its conditionals
+fn dependencies(file: &str, source: &str, sources: &Sources) -> (HashSet<String>, HashSet<String>) {
+    let mut files = HashSet::from([file.to_owned()]);
+    let mut names = HashSet::new();
+    let mut stack = vec![Arc::<str>::from(source)];
+    while let Some(source) = stack.pop() {
+        for text in source.lines() {
+            let Some(directive) = text.trim().strip_prefix('#') else {
+                continue;
+            };
+            let (name, argument) = directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""));
+            let argument = argument.trim();
+            match name {
+                "ifdef" | "ifndef" => {
+                    names.insert(argument.to_owned());
+                }
+                "include" => {
+                    let chunk = argument.trim_matches('"');
+                    let chunk_file = format!("chunks/{chunk}.wgsl");
+                    if files.contains(&chunk_file) {
+                        continue;
+                    }
+                    let source = match sources.get(&chunk_file) {
+                        Some(reloaded) => Arc::clone(reloaded),
+                        None => match CHUNKS.iter().find(|(name, _)| *name == chunk) {
+                            Some((_, source)) => (*source).into(),
+                            None => continue,
+                        },
+                    };
+                    files.insert(chunk_file);
+                    stack.push(source);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    (files, names)
+}
+
+/// Changes whenever reloaded shaders replace the previous ones.
+pub(crate) fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Preprocessed WGSL assembled from sources and chunks, remembering where each line came from.
+pub(crate) struct Composer {
+    defines: HashSet<String>,
+    included: HashSet<&'static str>,
+    // reloaded chunks, replacing the ones compiled in
+    sources: Sources,
+    // names and sources of the files lines are taken from
+    files: Vec<(String, Arc<str>)>,
+    // file errors without a position are reported in
+    main: usize,
+    // file index and 1-based line of each line of `out`
+    origins: Vec<(usize, usize)>,
+    out: String,
+}
+
+impl Composer {
+    pub(crate) fn new<'a, I>(defines: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        Self::with_sources(defines, Arc::clone(&RELOADED.read().unwrap()))
+    }
+
+    fn with_sources<'a, I>(defines: I, sources: Sources) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        Self {
+            defines: defines.into_iter().map(str::to_owned).collect(),
+            included: HashSet::new(),
+            sources,
+            files: Vec::new(),
+            main: 0,
+            origins: Vec::new(),
+            out: String::new(),
+        }
+    }
+
+    /// Appends the chunk `name` unless it already is.
+    pub(crate) fn include(&mut self, name: &str) {
+        let included = self.include_chunk(name).expect("unknown shader chunk");
+        included.expect("invalid shader chunk");
+    }
+
+    pub(crate) fn append(&mut self, file: &str, source: &str) -> Result<(), ShaderError> {
+        self.files.push((file.to_owned(), source.into()));
+        let index = self.files.len() - 1;
+        self.main = index;
+
+        self.expand(index).map_err(|(index, error)| {
+            let (file, source) = &self.files[index];
+            let column = match &error {
+                PreprocessError::Unterminated { .. } => 1,
+                _ => source
+                    .lines()
+                    .nth(error.line() - 1)
+                    .map_or(1, |text| text.len() - text.trim_start().len() + 1),
+            };
+            ShaderError::new(file, source, error.line(), column, error.message())
+        })
+    }
+
+    /// Validates the composed source.
+    pub(crate) fn finish(self) -> Result<String, ShaderError> {
+        self.module()?;
+        Ok(self.out)
+    }
+
+    fn module(&self) -> Result<naga::Module, ShaderError> {
+        let module = naga::front::wgsl::parse_str(&self.out).map_err(|e| {
+            let location = e.location(&self.out);
+            let message = match e.labels().next() {
+                Some((_, label)) if !label.is_empty() => format!("{}: {}", e.message(), label),
+                _ => e.message().to_owned(),
+            };
+            self.error(location, message)
+        })?;
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .map_err(|e| {
+            let mut message = e.as_inner().to_string();
+            let mut source: &dyn error::Error = e.as_inner();
+            while let Some(next) = source.source() {
+                message = format!("{message}: {next}");
+                source = next;
+            }
+            self.error(e.location(&self.out), message)
+        })?;
+
+        Ok(module)
+    }
+
+    fn error(&self, location: Option<naga::SourceLocation>, message: String) -> ShaderError {
+        let origin = location.and_then(|location| {
+            let line = location.line_number as usize;
+            match self.origins.get(line - 1) {
+                Some(&origin) => Some((origin, location.line_position as usize)),
+                // the end of the source, after its last line
+                None => self.origins.last().map(|&(index, line)| {
+                    let text = self.files[index].1.lines().nth(line - 1).unwrap_or("");
+                    ((index, line), text.len() + 1)
+                }),
+            }
+        });
+
+        match origin {
+            Some(((index, line), column)) => {
+                let (file, source) = &self.files[index];
+                ShaderError::new(file, source, line, column, message)
+            }
+            None => ShaderError::new(&self.files[self.main].0, "", 0, 0, message),
+        }
+    }
+
+    // None if `name` is not a chunk
+    fn include_chunk(&mut self, name: &str) -> Option<Result<(), (usize, PreprocessError)>> {
+        let &(name, source) = CHUNKS.iter().find(|(chunk, _)| *chunk == name)?;
+        if !self.included.insert(name) {
+            return Some(Ok(()));
+        }
+
+        let source = match self.sources.get(&format!("chunks/{name}.wgsl")) {
+            Some(reloaded) => Arc::clone(reloaded),
+            None => source.into(),
+        };
+        self.files.push((format!("{name}.wgsl"), source));
+        Some(self.expand(self.files.len() - 1))
+    }
+
+    fn expand(&mut self, file: usize) -> Result<(), (usize, PreprocessError)> {
+        let source = Arc::clone(&self.files[file].1);
+        // open conditionals: line, whether the enclosing lines are kept, whether its own are
+        let mut conditionals: Vec<(usize, bool, bool)> = Vec::new();
+
+        for (i, text) in source.lines().enumerate() {
+            let line = i + 1;
+            let active = conditionals.last().map_or(true, |&(_, _, active)| active);
+            let Some(directive) = text.trim().strip_prefix('#') else {
+                if active {
+                    self.out.push_str(text);
+                    self.out.push('\n');
+                    self.origins.push((file, line));
+                }
+                continue;
+            };
+
+            let invalid = || {
+                let directive = text.trim().to_owned();
+                (file, PreprocessError::InvalidDirective { line, directive })
+            };
+            let (name, argument) = match directive.split_once(char::is_whitespace) {
+                Some((name, argument)) => (name, argument.trim()),
+                None => (directive, ""),
+            };
+            let is_name = !argument.is_empty() && !argument.contains(char::is_whitespace);
+
+            match name {
+                "ifdef" | "ifndef" if is_name => {
+                    let defined = self.defines.contains(argument) == (name == "ifdef");
+                    conditionals.push((line, active, active && defined));
+                }
+                "else" if argument.is_empty() => {
+                    let (start, parent, kept) = conditionals.pop().ok_or_else(invalid)?;
+                    conditionals.push((start, parent, parent && !kept));
+                }
+                "endif" if argument.is_empty() => {
+                    conditionals.pop().ok_or_else(invalid)?;
+                }
+                "define" if is_name => {
+                    if active {
+                        self.defines.insert(argument.to_owned());
+                    }
+                }
+                "include" => {
+                    let chunk = argument
+                        .strip_prefix('"')
+                        .and_then(|a| a.strip_suffix('"'))
+                        .ok_or_else(invalid)?;
+                    if !active {
+                        continue;
+                    }
+
+                    let Some(included) = self.include_chunk(chunk) else {
+                        let name = chunk.to_owned();
+                        return Err((file, PreprocessError::UnknownChunk { line, name }));
+                    };
+                    included?;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        match conditionals.last() {
+            Some(&(line, _, _)) => Err((file, PreprocessError::Unterminated { line })),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+struct BackgroundParams {
+    // maps clip space back to world space, translation included
+    inv_view_proj: mat4x4<f32>,
+    top: vec4<f32>,
+    bottom: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: BackgroundParams;
+@group(0) @binding(1)
+var skybox_sampler: sampler;
+@group(0) @binding(2)
+var skybox: texture_cube<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+// Vertex shader
+
+// one triangle covering the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let ndc = vec2<f32>(uv.x * 2. - 1., 1. - uv.y * 2.);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0., 1.);
+    out.ndc = ndc;
+    out.uv = uv;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_gradient(in: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(params.top, params.bottom, in.uv.y);
+}
+
+@fragment
+fn fs_skybox(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the view ray through the pixel, from the near to the far plane
+    let near = params.inv_view_proj * vec4<f32>(in.ndc, 0., 1.);
+    let far = params.inv_view_proj * vec4<f32>(in.ndc, 1., 1.);
+    let direction = far.xyz / far.w - near.xyz / near.w;
+
+    return textureSampleLevel(skybox, skybox_sampler, direction, 0.);
+}
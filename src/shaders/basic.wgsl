@@ -0,0 +1,42 @@
+#include "camera"
+#include "vertex"
+#include "instance"
+#include "output"
+
+struct LocalParams {
+    color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> locals: LocalParams;
+@group(1) @binding(1)
+var map_sampler: sampler;
+@group(1) @binding(2)
+var map: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Vertex shader
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * model_matrix(instance) * vec4<f32>(model.position, 1.0);
+    out.uv = model.uv;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+#ifdef MAP
+    let color = locals.color * textureSample(map, map_sampler, in.uv);
+#else
+    let color = locals.color;
+#endif
+    return fragment_output(color, in.clip_position);
+}
@@ -1,15 +1,15 @@
+#include "camera"
+#include "vertex"
+#include "instance"
+#include "output"
+
 struct LocalParams {
     color: vec4<f32>,
 }
 
-@group(0) @binding(0)
+@group(1) @binding(0)
 var<uniform> locals: LocalParams;
 
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) _normal: vec3<f32>,
-}
-
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
@@ -18,9 +18,9 @@ struct VertexOutput {
 // Vertex shader
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.clip_position = globals.view_proj * model_matrix(instance) * vec4<f32>(model.position, 1.0);
     out.color = locals.color;
     return out;
 }
@@ -28,6 +28,6 @@ fn vs_main(model: VertexInput) -> VertexOutput {
 // Fragment shader
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color);
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    return fragment_output(in.color, in.clip_position);
 }
@@ -0,0 +1,78 @@
+#include "effect"
+
+struct BloomParams {
+    threshold: f32,
+    // width of the soft transition below the threshold
+    knee: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(2)
+var<uniform> params: BloomParams;
+// the blurred bright parts, read by the composite pass
+@group(0) @binding(3)
+var bloom_texture: texture_2d<f32>;
+
+fn sample_rgb(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(input_texture, input_sampler, uv, 0.).rgb;
+}
+
+// average of a 4×4 texel box around uv, from four bilinear taps
+fn box_filter(uv: vec2<f32>) -> vec3<f32> {
+    let d = input_texel_size();
+    return 0.25 * (
+        sample_rgb(uv + vec2<f32>(-d.x, -d.y)) +
+        sample_rgb(uv + vec2<f32>(d.x, -d.y)) +
+        sample_rgb(uv + vec2<f32>(-d.x, d.y)) +
+        sample_rgb(uv + vec2<f32>(d.x, d.y))
+    );
+}
+
+// Fragment shader
+
+// downsamples the frame keeping only what is brighter than the threshold
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = box_filter(in.uv);
+    let brightness = max(color.r, max(color.g, color.b));
+
+    var soft = clamp(brightness - params.threshold + params.knee, 0., 2. * params.knee);
+    soft = soft * soft / (4. * params.knee + 0.00001);
+    let contribution = max(soft, brightness - params.threshold) / max(brightness, 0.00001);
+
+    return vec4<f32>(color * contribution, 1.);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(box_filter(in.uv), 1.);
+}
+
+// 3×3 tent filter of the smaller level, added onto the larger one
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = input_texel_size();
+
+    var color = sample_rgb(in.uv) * 4.;
+    color += (
+        sample_rgb(in.uv + vec2<f32>(-d.x, 0.)) +
+        sample_rgb(in.uv + vec2<f32>(d.x, 0.)) +
+        sample_rgb(in.uv + vec2<f32>(0., -d.y)) +
+        sample_rgb(in.uv + vec2<f32>(0., d.y))
+    ) * 2.;
+    color += (
+        sample_rgb(in.uv + vec2<f32>(-d.x, -d.y)) +
+        sample_rgb(in.uv + vec2<f32>(d.x, -d.y)) +
+        sample_rgb(in.uv + vec2<f32>(-d.x, d.y)) +
+        sample_rgb(in.uv + vec2<f32>(d.x, d.y))
+    );
+
+    return vec4<f32>(color / 16., 1.);
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+    let bloom = textureSampleLevel(bloom_texture, input_sampler, in.uv, 0.).rgb;
+    return vec4<f32>(color.rgb + bloom * params.intensity, color.a);
+}
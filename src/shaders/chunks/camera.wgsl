@@ -0,0 +1,10 @@
+struct GlobalParams {
+    view_proj: mat4x4<f32>,
+    viewport: vec2<f32>,
+    light_count: u32,
+    receive_shadow: u32,
+    camera_position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: GlobalParams;
@@ -0,0 +1,28 @@
+// Shared by all post-processing effects, included before the source of each.
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Vertex shader
+
+// one triangle covering the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2. - 1., 1. - uv.y * 2., 0., 1.);
+    out.uv = uv;
+    return out;
+}
+
+fn input_texel_size() -> vec2<f32> {
+    return 1. / vec2<f32>(textureDimensions(input_texture));
+}
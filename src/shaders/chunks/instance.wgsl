@@ -0,0 +1,17 @@
+struct InstanceInput {
+    @location(5) model_0: vec4<f32>,
+    @location(6) model_1: vec4<f32>,
+    @location(7) model_2: vec4<f32>,
+    @location(8) model_3: vec4<f32>,
+    @location(9) normal_0: vec3<f32>,
+    @location(10) normal_1: vec3<f32>,
+    @location(11) normal_2: vec3<f32>,
+}
+
+fn model_matrix(instance: InstanceInput) -> mat4x4<f32> {
+    return mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+}
+
+fn normal_matrix(instance: InstanceInput) -> mat3x3<f32> {
+    return mat3x3<f32>(instance.normal_0, instance.normal_1, instance.normal_2);
+}
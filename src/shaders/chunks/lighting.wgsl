@@ -0,0 +1,121 @@
+#include "camera"
+#include "lights"
+
+struct IncidentLight {
+    // direction towards the light
+    direction: vec3<f32>,
+    // radiance arriving from `direction`
+    radiance: vec3<f32>,
+    // irradiance of ambient and hemisphere lights, applied to the diffuse term only
+    ambient: vec3<f32>,
+}
+
+// Back faces are only drawn by double or back sided materials, and are lit from their side.
+fn facing_normal(world_normal: vec3<f32>, front_facing: bool) -> vec3<f32> {
+    return select(-world_normal, world_normal, front_facing);
+}
+
+fn distance_attenuation(distance: f32, range: f32, decay: f32) -> f32 {
+    var attenuation = 1.0 / max(pow(distance, decay), 0.01);
+    if range > 0.0 {
+        let window = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+        attenuation *= window * window;
+    }
+    return attenuation;
+}
+
+// 3x3 PCF around `uv` of a shadow map, with the kernel kept inside its tile of the atlas.
+fn filter_shadow(shadow: Shadow, uv: vec2<f32>, depth: f32) -> f32 {
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_atlas));
+    let tile_min = shadow.atlas_rect.xy + texel * 0.5;
+    let tile_max = shadow.atlas_rect.xy + shadow.atlas_rect.zw - texel * 0.5;
+    let center = shadow.atlas_rect.xy + uv * shadow.atlas_rect.zw;
+
+    var visibility = 0.0;
+    for (var y = -1; y <= 1; y += 1) {
+        for (var x = -1; x <= 1; x += 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            let atlas_uv = clamp(center + offset, tile_min, tile_max);
+            visibility += textureSampleCompareLevel(shadow_atlas, shadow_sampler, atlas_uv, depth);
+        }
+    }
+    return visibility / 9.0;
+}
+
+// Point light shadow face along the major axis of `d`, ordered +X, -X, +Y, -Y, +Z, -Z.
+fn cube_face(d: vec3<f32>) -> u32 {
+    let a = abs(d);
+    if a.x >= a.y && a.x >= a.z {
+        return select(1u, 0u, d.x > 0.0);
+    }
+    if a.y >= a.z {
+        return select(3u, 2u, d.y > 0.0);
+    }
+    return select(5u, 4u, d.z > 0.0);
+}
+
+// Fraction of the light reaching `position`. Cascades are ordered from near to far and the
+// first one covering `position` is used.
+fn light_visibility(light: Light, position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if globals.receive_shadow == 0u || light.shadow_count == 0u {
+        return 1.0;
+    }
+
+    // point lights store the linear distance in six cube faces
+    if light.kind == 2u {
+        let offset_position = position + normal * shadows[light.shadow_index].normal_bias;
+        let to_surface = offset_position - light.position;
+        let shadow = shadows[light.shadow_index + cube_face(to_surface)];
+        let clip = shadow.view_proj * vec4<f32>(offset_position, 1.0);
+        let uv = clip.xy / clip.w * vec2<f32>(0.5, -0.5) + 0.5;
+        return filter_shadow(shadow, uv, (length(to_surface) - shadow.bias) / shadow.far);
+    }
+
+    for (var i = 0u; i < light.shadow_count; i += 1u) {
+        let shadow = shadows[light.shadow_index + i];
+        let clip = shadow.view_proj * vec4<f32>(position + normal * shadow.normal_bias, 1.0);
+        let ndc = clip.xyz / clip.w;
+        let uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+        if clip.w <= 0.0 || any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+            continue;
+        }
+
+        return filter_shadow(shadow, uv, ndc.z - shadow.bias);
+    }
+    return 1.0;
+}
+
+fn incident_light(light: Light, position: vec3<f32>, normal: vec3<f32>) -> IncidentLight {
+    var out: IncidentLight;
+    out.direction = normal;
+    out.radiance = vec3<f32>(0.0);
+    out.ambient = vec3<f32>(0.0);
+
+    let color = light.color * light.intensity;
+    switch light.kind {
+        case 0u: {
+            out.ambient = color;
+        }
+        case 1u: {
+            out.direction = -light.direction;
+            out.radiance = color * light_visibility(light, position, normal);
+        }
+        case 2u, 3u: {
+            let to_light = light.position - position;
+            let distance = length(to_light);
+            out.direction = to_light / max(distance, 1e-5);
+            var attenuation = distance_attenuation(distance, light.range, light.decay);
+            if light.kind == 3u {
+                let cos_angle = dot(-out.direction, light.direction);
+                attenuation *= smoothstep(light.cone_cos.y, light.cone_cos.x, cos_angle);
+            }
+            out.radiance = color * attenuation * light_visibility(light, position, normal);
+        }
+        case 4u: {
+            let weight = 0.5 * dot(normal, light.direction) + 0.5;
+            out.ambient = mix(light.ground_color, light.color, weight) * light.intensity;
+        }
+        default: {}
+    }
+    return out;
+}
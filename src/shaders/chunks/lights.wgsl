@@ -0,0 +1,49 @@
+struct Light {
+    color: vec3<f32>,
+    intensity: f32,
+    position: vec3<f32>,
+    range: f32,
+    direction: vec3<f32>,
+    decay: f32,
+    ground_color: vec3<f32>,
+    // 0: ambient, 1: directional, 2: point, 3: spot, 4: hemisphere
+    kind: u32,
+    // cosines of the inner and outer cone angles
+    cone_cos: vec2<f32>,
+    // range of the light's entries in `shadows`
+    shadow_index: u32,
+    shadow_count: u32,
+}
+
+#ifdef UNIFORM_LIGHTS
+// params::UNIFORM_MAX_LIGHTS, `light_count` of them are valid
+@group(0) @binding(1)
+var<uniform> lights: array<Light, 64>;
+#else
+@group(0) @binding(1)
+var<storage, read> lights: array<Light>;
+#endif
+
+struct Shadow {
+    view_proj: mat4x4<f32>,
+    // offset and scale of the shadow map tile in the atlas
+    atlas_rect: vec4<f32>,
+    // the tile stores distance to the light divided by `far`, unless `far` is 0
+    light_position: vec3<f32>,
+    far: f32,
+    bias: f32,
+    normal_bias: f32,
+}
+
+#ifdef UNIFORM_LIGHTS
+// ShadowAtlas::capacity
+@group(0) @binding(2)
+var<uniform> shadows: array<Shadow, 36>;
+#else
+@group(0) @binding(2)
+var<storage, read> shadows: array<Shadow>;
+#endif
+@group(0) @binding(3)
+var shadow_atlas: texture_depth_2d;
+@group(0) @binding(4)
+var shadow_sampler: sampler_comparison;
@@ -0,0 +1,27 @@
+// Fragment output of meshes. With OIT defined the color is written into the accumulation
+// and revealage targets of weighted blended order-independent transparency instead.
+
+struct FragmentOutput {
+#ifdef OIT
+    @location(0) accum: vec4<f32>,
+    @location(1) revealage: vec4<f32>,
+#else
+    @location(0) color: vec4<f32>,
+#endif
+}
+
+// `frag_coord` is the @builtin(position) of the fragment
+fn fragment_output(color: vec4<f32>, frag_coord: vec4<f32>) -> FragmentOutput {
+    var out: FragmentOutput;
+#ifdef OIT
+    // equation 10 of McGuire and Bavoil, favoring opaque and near fragments
+    let a = color.a;
+    let depth = 1.0 - frag_coord.z * 0.9;
+    let weight = clamp(pow(min(1.0, a * 10.0) + 0.01, 3.0) * 1e8 * depth * depth * depth, 1e-2, 3e3);
+    out.accum = vec4<f32>(color.rgb * a, a) * weight;
+    out.revealage = vec4<f32>(a);
+#else
+    out.color = color;
+#endif
+    return out;
+}
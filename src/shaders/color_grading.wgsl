@@ -0,0 +1,39 @@
+#include "effect"
+
+struct ColorGradingParams {
+    intensity: f32,
+}
+
+@group(0) @binding(2)
+var<uniform> params: ColorGradingParams;
+// size² × size strip of size slices along blue, red and green along x and y of a slice
+@group(0) @binding(3)
+var lut: texture_2d<f32>;
+
+fn lut_sample(color: vec3<f32>, slice: f32, size: f32) -> vec3<f32> {
+    let uv = vec2<f32>(
+        (slice * size + color.r * (size - 1.) + 0.5) / (size * size),
+        (color.g * (size - 1.) + 0.5) / size,
+    );
+    return textureSampleLevel(lut, input_sampler, uv, 0.).rgb;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+    let c = clamp(color.rgb, vec3<f32>(0.), vec3<f32>(1.));
+
+    // blend the two slices around blue
+    let size = f32(textureDimensions(lut).y);
+    let blue = c.b * (size - 1.);
+    let slice = floor(blue);
+    let graded = mix(
+        lut_sample(c, slice, size),
+        lut_sample(c, min(slice + 1., size - 1.), size),
+        blue - slice,
+    );
+
+    return vec4<f32>(mix(color.rgb, graded, params.intensity), color.a);
+}
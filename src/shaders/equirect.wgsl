@@ -0,0 +1,69 @@
+struct EquirectParams {
+    // cube face in the order +X, -X, +Y, -Y, +Z, -Z
+    face: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: EquirectParams;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+const PI: f32 = 3.14159265359;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Vertex shader
+
+// one triangle covering the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2. - 1., 1. - uv.y * 2., 0., 1.);
+    out.uv = uv;
+    return out;
+}
+
+// Fragment shader
+
+// direction through a texel of a cube face, following the cube map sampling convention
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2. - 1.;
+    switch face {
+        case 0u: {
+            return vec3<f32>(1., -st.y, -st.x);
+        }
+        case 1u: {
+            return vec3<f32>(-1., -st.y, st.x);
+        }
+        case 2u: {
+            return vec3<f32>(st.x, 1., st.y);
+        }
+        case 3u: {
+            return vec3<f32>(st.x, -1., -st.y);
+        }
+        case 4u: {
+            return vec3<f32>(st.x, -st.y, 1.);
+        }
+        default: {
+            return vec3<f32>(-st.x, -st.y, -1.);
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = normalize(face_direction(params.face, in.uv));
+    // the center of the panorama faces -Z
+    let uv = vec2<f32>(
+        atan2(direction.x, -direction.z) / (2. * PI) + 0.5,
+        acos(clamp(direction.y, -1., 1.)) / PI,
+    );
+    return textureSampleLevel(source, source_sampler, uv, 0.);
+}
@@ -0,0 +1,15 @@
+// Drawn in place of a ShaderMaterial whose source fails to compile.
+
+#include "camera"
+#include "vertex"
+#include "instance"
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    return globals.view_proj * model_matrix(instance) * vec4<f32>(model.position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+}
@@ -0,0 +1,67 @@
+#include "effect"
+
+struct FxaaParams {
+    // minimum and relative contrast for a pixel to be treated as an edge
+    edge_threshold_min: f32,
+    edge_threshold: f32,
+    // longest blur along an edge, in pixels
+    span_max: f32,
+}
+
+@group(0) @binding(2)
+var<uniform> params: FxaaParams;
+
+fn luma(color: vec3<f32>) -> f32 {
+    // perceptual weights of colors mapped to 0..1 first, so HDR highlights don't dominate
+    let c = color / (1. + color);
+    return dot(c, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn sample_rgb(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(input_texture, input_sampler, uv, 0.).rgb;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = input_texel_size();
+    let center = textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+
+    let luma_nw = luma(sample_rgb(in.uv + vec2<f32>(-1., -1.) * texel));
+    let luma_ne = luma(sample_rgb(in.uv + vec2<f32>(1., -1.) * texel));
+    let luma_sw = luma(sample_rgb(in.uv + vec2<f32>(-1., 1.) * texel));
+    let luma_se = luma(sample_rgb(in.uv + vec2<f32>(1., 1.) * texel));
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    if luma_max - luma_min < max(params.edge_threshold_min, luma_max * params.edge_threshold) {
+        return center;
+    }
+
+    // blur along the edge, perpendicular to the luma gradient
+    var direction = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let direction_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.03125, 1. / 128.);
+    let scale = 1. / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * scale, vec2<f32>(-params.span_max), vec2<f32>(params.span_max)) * texel;
+
+    let near = 0.5 * (
+        sample_rgb(in.uv + direction * (1. / 3. - 0.5)) +
+        sample_rgb(in.uv + direction * (2. / 3. - 0.5))
+    );
+    let far = near * 0.5 + 0.25 * (
+        sample_rgb(in.uv - direction * 0.5) +
+        sample_rgb(in.uv + direction * 0.5)
+    );
+
+    // the wider blur crossed another edge
+    let luma_far = luma(far);
+    if luma_far < luma_min || luma_far > luma_max {
+        return vec4<f32>(near, center.a);
+    }
+    return vec4<f32>(far, center.a);
+}
@@ -0,0 +1,50 @@
+#include "lighting"
+#include "vertex"
+#include "instance"
+#include "output"
+
+struct LocalParams {
+    color: vec4<f32>,
+    emissive: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> locals: LocalParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+// Vertex shader
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let world_position = model_matrix(instance) * vec4<f32>(model.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.world_normal = normal_matrix(instance) * model.normal;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(
+    in: VertexOutput,
+    @builtin(front_facing) front_facing: bool,
+) -> FragmentOutput {
+    let normal = normalize(facing_normal(in.world_normal, front_facing));
+
+    var diffuse = vec3<f32>(0.0);
+    for (var i = 0u; i < globals.light_count; i += 1u) {
+        let light = incident_light(lights[i], in.world_position, normal);
+        diffuse += light.ambient + max(dot(normal, light.direction), 0.0) * light.radiance;
+    }
+    let color = locals.color.rgb * diffuse + locals.emissive.rgb;
+
+    return fragment_output(vec4<f32>(color, locals.color.a), in.clip_position);
+}
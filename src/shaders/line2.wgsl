@@ -0,0 +1,150 @@
+#include "camera"
+#include "output"
+
+struct LocalParams {
+    color: vec4<f32>,
+    width: f32,
+    dash_size: f32,
+    gap_size: f32,
+    dash_offset: f32,
+    // 0: butt, 1: square, 2: round
+    cap: u32,
+    // 0: round, 1: miter, 2: bevel
+    join: u32,
+    miter_limit: f32,
+    dashed: u32,
+}
+
+@group(1) @binding(0)
+var<uniform> locals: LocalParams;
+
+// x: side of the line (-1 or 1)
+// y: 2 end extension, 1 end, 0 start, -1 start extension
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) _normal: vec3<f32>,
+}
+
+struct SegmentInput {
+    @location(3) prev: vec3<f32>,
+    @location(4) start: vec3<f32>,
+    @location(5) end: vec3<f32>,
+    @location(6) next: vec3<f32>,
+    @location(7) color_start: vec4<f32>,
+    @location(8) color_end: vec4<f32>,
+    @location(9) distances: vec2<f32>,
+    // bit 0: has prev, bit 1: has next
+    @location(10) flags: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // offset from the line center in units of half width
+    @location(1) uv: vec2<f32>,
+    @location(2) distance: f32,
+}
+
+fn to_clip(position: vec3<f32>) -> vec4<f32> {
+    return globals.view_proj * vec4<f32>(position, 1.0);
+}
+
+fn to_screen(clip: vec4<f32>) -> vec2<f32> {
+    return clip.xy / clip.w * globals.viewport * 0.5;
+}
+
+// Vertex shader
+
+@vertex
+fn vs_main(vertex: VertexInput, segment: SegmentInput) -> VertexOutput {
+    let is_end = vertex.position.y > 0.5;
+    let is_extension = vertex.position.y > 1.5 || vertex.position.y < -0.5;
+    let side = vertex.position.x;
+    let half_width = locals.width * 0.5;
+
+    let clip_start = to_clip(segment.start);
+    let clip_end = to_clip(segment.end);
+    let screen_start = to_screen(clip_start);
+    let screen_end = to_screen(clip_end);
+
+    let dir = normalize(screen_end - screen_start);
+    let normal = vec2<f32>(-dir.y, dir.x);
+
+    var clip = clip_start;
+    var along = -dir;
+    var has_neighbor = (segment.flags & 1u) != 0u;
+    var neighbor_dir = dir;
+    if is_end {
+        clip = clip_end;
+        along = dir;
+        has_neighbor = (segment.flags & 2u) != 0u;
+        if has_neighbor {
+            neighbor_dir = normalize(to_screen(to_clip(segment.next)) - screen_end);
+        }
+    } else if has_neighbor {
+        neighbor_dir = normalize(screen_start - to_screen(to_clip(segment.prev)));
+    }
+    let neighbor_normal = vec2<f32>(-neighbor_dir.y, neighbor_dir.x);
+
+    var offset = normal * side * half_width;
+    var round = false;
+
+    if !has_neighbor {
+        if is_extension && locals.cap != 0u {
+            offset += along * half_width;
+            round = locals.cap == 2u;
+        }
+    } else {
+        let miter_sum = normal + neighbor_normal;
+        var miter_scale = locals.miter_limit + 1.0;
+        if length(miter_sum) > 1e-4 {
+            miter_scale = 1.0 / max(dot(normalize(miter_sum), normal), 1e-4);
+        }
+
+        if locals.join == 1u && miter_scale <= locals.miter_limit {
+            offset = normalize(miter_sum) * side * half_width * miter_scale;
+        } else if is_end && is_extension {
+            if locals.join == 0u {
+                offset += along * half_width;
+                round = true;
+            } else {
+                // bevel: fill the gap on the outer side of the turn only
+                let turn = dir.x * neighbor_dir.y - dir.y * neighbor_dir.x;
+                if side * turn < 0.0 {
+                    offset = neighbor_normal * side * half_width;
+                } else {
+                    offset = vec2<f32>(0.0, 0.0);
+                }
+            }
+        }
+    }
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(clip.xy + offset / (globals.viewport * 0.5) * clip.w, clip.zw);
+    out.color = select(segment.color_start, segment.color_end, is_end) * locals.color;
+    out.uv = vec2<f32>(dot(offset, normal), select(0.0, dot(offset, along), round)) / half_width;
+    out.distance = select(segment.distances.x, segment.distances.y, is_end);
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    let r = length(in.uv);
+    let aa = max(fwidth(r), 1e-4);
+    let coverage = 1.0 - smoothstep(1.0 - aa, 1.0, r);
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    if locals.dashed != 0u {
+        let period = locals.dash_size + locals.gap_size;
+        let phase = fract((in.distance + locals.dash_offset) / period) * period;
+        if phase > locals.dash_size {
+            discard;
+        }
+    }
+
+    return fragment_output(vec4<f32>(in.color.rgb, in.color.a * coverage), in.clip_position);
+}
@@ -0,0 +1,20 @@
+#include "effect"
+
+// the accumulation target is the input texture
+@group(0) @binding(3)
+var revealage_texture: texture_2d<f32>;
+
+// Fragment shader
+
+// blended over the opaque meshes by the alpha
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(in.clip_position.xy);
+    let accum = textureLoad(input_texture, coords, 0);
+    let revealage = textureLoad(revealage_texture, coords, 0).r;
+    if revealage >= 1.0 {
+        discard;
+    }
+
+    return vec4<f32>(accum.rgb / max(accum.a, 1e-5), 1.0 - revealage);
+}
@@ -0,0 +1,35 @@
+#include "vertex"
+#include "instance"
+
+struct ShadowPassParams {
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    far: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> shadow_pass: ShadowPassParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+}
+
+// Vertex shader
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let world_position = model_matrix(instance) * vec4<f32>(model.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = shadow_pass.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    return out;
+}
+
+// Fragment shader, only used for point lights
+
+@fragment
+fn fs_linear(in: VertexOutput) -> @builtin(frag_depth) f32 {
+    return length(in.world_position - shadow_pass.light_position) / shadow_pass.far;
+}
@@ -0,0 +1,200 @@
+#include "lighting"
+#include "vertex"
+#include "instance"
+#include "output"
+
+struct LocalParams {
+    base_color: vec4<f32>,
+    emissive: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    env_intensity: f32,
+    sh: array<vec4<f32>, 9>,
+    env_max_lod: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> locals: LocalParams;
+@group(1) @binding(1)
+var map_sampler: sampler;
+@group(1) @binding(2)
+var base_color_map: texture_2d<f32>;
+@group(1) @binding(3)
+var metallic_roughness_map: texture_2d<f32>;
+@group(1) @binding(4)
+var normal_map: texture_2d<f32>;
+@group(1) @binding(5)
+var occlusion_map: texture_2d<f32>;
+@group(1) @binding(6)
+var emissive_map: texture_2d<f32>;
+@group(1) @binding(7)
+var env_map: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+// Vertex shader
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let world_position = model_matrix(instance) * vec4<f32>(model.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.world_normal = normal_matrix(instance) * model.normal;
+    out.uv = model.uv;
+    return out;
+}
+
+// Fragment shader
+
+const PI: f32 = 3.14159265359;
+
+#ifdef NORMAL_MAP
+// Normal mapping without precomputed tangents, using screen space derivatives of the
+// position and uv. The bitangent points towards decreasing v, matching glTF normal maps.
+fn perturb_normal(
+    normal: vec3<f32>,
+    dp1: vec3<f32>,
+    dp2: vec3<f32>,
+    duv1: vec2<f32>,
+    duv2: vec2<f32>,
+    map_normal: vec3<f32>,
+) -> vec3<f32> {
+    let dp2_perp = cross(dp2, normal);
+    let dp1_perp = cross(normal, dp1);
+    let tangent = dp2_perp * duv1.x + dp1_perp * duv2.x;
+    let bitangent = -(dp2_perp * duv1.y + dp1_perp * duv2.y);
+
+    let inv_max = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-12));
+    return normalize(mat3x3<f32>(tangent * inv_max, bitangent * inv_max, normal) * map_normal);
+}
+#endif
+
+fn distribution_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+fn visibility_smith_ggx(n_dot_l: f32, n_dot_v: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let gv = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - a2) + a2);
+    let gl = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - a2) + a2);
+    return 0.5 / max(gv + gl, 1e-5);
+}
+
+fn fresnel_schlick(f0: vec3<f32>, v_dot_h: f32) -> vec3<f32> {
+    return f0 + (vec3<f32>(1.0) - f0) * pow(1.0 - v_dot_h, 5.0);
+}
+
+#ifdef ENV_MAP
+// Analytic approximation of the split sum BRDF lookup (Karis 2014).
+fn env_brdf_approx(f0: vec3<f32>, roughness: f32, n_dot_v: f32) -> vec3<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+    return f0 * ab.x + ab.y;
+}
+
+fn sh_irradiance(n: vec3<f32>) -> vec3<f32> {
+    let sh = locals.sh;
+    var result = sh[0].rgb;
+    result += sh[1].rgb * n.y + sh[2].rgb * n.z + sh[3].rgb * n.x;
+    result += sh[4].rgb * n.x * n.y + sh[5].rgb * n.y * n.z;
+    result += sh[6].rgb * (3.0 * n.z * n.z - 1.0);
+    result += sh[7].rgb * n.x * n.z + sh[8].rgb * (n.x * n.x - n.y * n.y);
+    return max(result, vec3<f32>(0.0));
+}
+
+fn equirect_uv(dir: vec3<f32>) -> vec2<f32> {
+    let u = atan2(dir.z, dir.x) / (2.0 * PI) + 0.5;
+    let v = acos(clamp(dir.y, -1.0, 1.0)) / PI;
+    return vec2<f32>(u, v);
+}
+#endif
+
+@fragment
+fn fs_main(
+    in: VertexOutput,
+    @builtin(front_facing) front_facing: bool,
+) -> FragmentOutput {
+#ifdef MAP
+    let base_color = locals.base_color * textureSample(base_color_map, map_sampler, in.uv);
+#else
+    let base_color = locals.base_color;
+#endif
+    let metallic_roughness = textureSample(metallic_roughness_map, map_sampler, in.uv);
+    let metallic = clamp(locals.metallic * metallic_roughness.b, 0.0, 1.0);
+    let roughness = clamp(locals.roughness * metallic_roughness.g, 0.04, 1.0);
+    let ao = textureSample(occlusion_map, map_sampler, in.uv).r;
+    let occlusion = 1.0 + locals.occlusion_strength * (ao - 1.0);
+    let emissive = locals.emissive * textureSample(emissive_map, map_sampler, in.uv).rgb;
+    let world_normal = facing_normal(in.world_normal, front_facing);
+
+#ifdef NORMAL_MAP
+    var map_normal = textureSample(normal_map, map_sampler, in.uv).xyz * 2.0 - 1.0;
+    map_normal = vec3<f32>(map_normal.xy * locals.normal_scale, map_normal.z);
+
+    // derivatives are taken here, since helper functions are also emitted for the vertex stage
+    let normal = perturb_normal(
+        normalize(world_normal),
+        dpdx(in.world_position),
+        dpdy(in.world_position),
+        dpdx(in.uv),
+        dpdy(in.uv),
+        map_normal,
+    );
+#else
+    let normal = normalize(world_normal);
+#endif
+
+    let view_dir = normalize(globals.camera_position.xyz - in.world_position);
+    let n_dot_v = max(dot(normal, view_dir), 1e-4);
+
+    let f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+    let diffuse_color = base_color.rgb * (1.0 - metallic);
+    let alpha = roughness * roughness;
+
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < globals.light_count; i += 1u) {
+        let light = incident_light(lights[i], in.world_position, normal);
+        let half_dir = normalize(light.direction + view_dir);
+        let n_dot_l = max(dot(normal, light.direction), 0.0);
+        let n_dot_h = max(dot(normal, half_dir), 0.0);
+        let v_dot_h = max(dot(view_dir, half_dir), 0.0);
+
+        let fresnel = fresnel_schlick(f0, v_dot_h);
+        let specular = fresnel * distribution_ggx(n_dot_h, alpha) * visibility_smith_ggx(n_dot_l, n_dot_v, alpha);
+        let diffuse = (vec3<f32>(1.0) - fresnel) * diffuse_color / PI;
+        let irradiance = light.radiance * n_dot_l * PI;
+        color += (diffuse + specular) * irradiance;
+        color += light.ambient * diffuse_color * occlusion;
+    }
+
+#ifdef ENV_MAP
+    // image based lighting
+    let reflected = reflect(-view_dir, normal);
+    let prefiltered = textureSampleLevel(
+        env_map,
+        map_sampler,
+        equirect_uv(reflected),
+        roughness * locals.env_max_lod,
+    ).rgb;
+    let ibl = sh_irradiance(normal) * diffuse_color + prefiltered * env_brdf_approx(f0, roughness, n_dot_v);
+    color += ibl * locals.env_intensity * occlusion;
+#endif
+
+    color += emissive;
+
+    return fragment_output(vec4<f32>(color, base_color.a), in.clip_position);
+}
@@ -0,0 +1,86 @@
+#include "effect"
+
+struct ToneMappingParams {
+    exposure: f32,
+    // see ToneMappingOperator
+    tone_operator: u32,
+    encode_srgb: u32,
+}
+
+@group(0) @binding(2)
+var<uniform> params: ToneMappingParams;
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1. + color);
+}
+
+// Narkowicz's fit of the ACES filmic curve
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let mapped = (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14);
+    return clamp(mapped, vec3<f32>(0.), vec3<f32>(1.));
+}
+
+// polynomial fit of the AgX base contrast curve by Benjamin Wrensch
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2
+        + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    let encoded = clamp(log2(inset * max(color, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    let display = outset * agx_contrast((encoded - min_ev) / (max_ev - min_ev));
+    // the curve ends in display encoding, back to linear for the output
+    return pow(max(display, vec3<f32>(0.)), vec3<f32>(2.2));
+}
+
+fn tone_map(color: vec3<f32>) -> vec3<f32> {
+    let exposed = color * params.exposure;
+    switch params.tone_operator {
+        case 1u: {
+            return reinhard(exposed);
+        }
+        case 2u: {
+            return aces(exposed);
+        }
+        case 3u: {
+            return agx(exposed);
+        }
+        default: {
+            return exposed;
+        }
+    }
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let clamped = clamp(color, vec3<f32>(0.), vec3<f32>(1.));
+    let low = clamped * 12.92;
+    let high = 1.055 * pow(clamped, vec3<f32>(1. / 2.4)) - 0.055;
+    return select(high, low, clamped <= vec3<f32>(0.0031308));
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+    var mapped = tone_map(max(color.rgb, vec3<f32>(0.)));
+    if params.encode_srgb != 0u {
+        mapped = linear_to_srgb(mapped);
+    }
+    return vec4<f32>(mapped, color.a);
+}
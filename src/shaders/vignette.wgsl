@@ -0,0 +1,24 @@
+#include "effect"
+
+struct VignetteParams {
+    color: vec4<f32>,
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+}
+
+@group(0) @binding(2)
+var<uniform> params: VignetteParams;
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.);
+
+    // 0 in the center, 1 in the corners
+    let edge_distance = length(in.uv - 0.5) * sqrt(2.);
+    let falloff = smoothstep(params.radius - params.smoothness, params.radius, edge_distance);
+
+    return vec4<f32>(mix(color.rgb, params.color.rgb, falloff * params.intensity), color.a);
+}
@@ -0,0 +1,289 @@
+use std::{collections::HashMap, num::NonZeroU64};
+
+use bytemuck::Zeroable;
+use once_cell::sync::OnceCell;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    light::ShadowRaw, mesh::MeshGpuData, params::lights_buffer_usage, pipeline::PipelineCache,
+    shader::ShaderFeatures, InstanceRaw, Vertex,
+};
+
+/// Tiles per row of the shadow atlas, each holding one shadow map.
+const ATLAS_TILES: u32 = 6;
+
+pub(crate) const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+static SHADOW_PASS_LAYOUT: OnceCell<wgpu::BindGroupLayout> = OnceCell::new();
+
+/// Depth atlas shared by every shadow casting light of a scene.
+pub(crate) struct ShadowAtlas {
+    tile_size: u32,
+    _texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    pub(crate) sampler: wgpu::Sampler,
+    pub(crate) shadows_buffer: wgpu::Buffer,
+    // pass params per tile, one aligned slot each
+    pass_buffer: wgpu::Buffer,
+    pass_stride: u64,
+    pass_bind_group: wgpu::BindGroup,
+    // keyed by topology and whether linear depth is written
+    pipelines: PipelineCache<(wgpu::PrimitiveTopology, bool)>,
+}
+
+impl ShadowAtlas {
+    pub(crate) fn new(device: &wgpu::Device, tile_size: u32) -> Self {
+        let max_tile_size = device.limits().max_texture_dimension_2d / ATLAS_TILES;
+        if tile_size > max_tile_size {
+            log::warn!("shadow map size {tile_size} is limited to {max_tile_size} by the device");
+        }
+        let tile_size = tile_size.min(max_tile_size);
+        let size = tile_size * ATLAS_TILES;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Atlas"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let shadows_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadows Buffer"),
+            contents: bytemuck::cast_slice(&vec![ShadowRaw::zeroed(); Self::capacity()]),
+            usage: lights_buffer_usage(device) | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let params_size = std::mem::size_of::<ShadowPassParams>() as u64;
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let pass_stride = params_size.div_ceil(alignment) * alignment;
+        let pass_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Pass Buffer"),
+            size: pass_stride * Self::capacity() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let pass_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Pass Bind Group"),
+            layout: Self::pass_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &pass_buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(params_size),
+                }),
+            }],
+        });
+
+        Self {
+            tile_size,
+            _texture: texture,
+            view,
+            sampler,
+            shadows_buffer,
+            pass_buffer,
+            pass_stride,
+            pass_bind_group,
+            pipelines: PipelineCache::new(),
+        }
+    }
+
+    /// Number of shadow maps that fit into the atlas.
+    pub(crate) fn capacity() -> usize {
+        (ATLAS_TILES * ATLAS_TILES) as usize
+    }
+
+    pub(crate) fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// Offset and scale of a tile in atlas uv coordinates.
+    pub(crate) fn tile_rect(index: usize) -> [f32; 4] {
+        let scale = 1. / ATLAS_TILES as f32;
+        let (x, y) = (index as u32 % ATLAS_TILES, index as u32 / ATLAS_TILES);
+        [x as f32 * scale, y as f32 * scale, scale, scale]
+    }
+
+    /// Uploads `shadows` and renders the casting meshes into their tiles.
+    pub(crate) fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shadows: &[ShadowRaw],
+        meshes: &[MeshGpuData],
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        debug_assert!(shadows.len() <= Self::capacity());
+        if shadows.is_empty() {
+            return;
+        }
+
+        queue.write_buffer(&self.shadows_buffer, 0, bytemuck::cast_slice(shadows));
+        for (i, shadow) in shadows.iter().enumerate() {
+            let params = ShadowPassParams {
+                view_proj: shadow.view_proj,
+                light_position: shadow.light_position,
+                far: shadow.far,
+            };
+            queue.write_buffer(
+                &self.pass_buffer,
+                i as u64 * self.pass_stride,
+                bytemuck::cast_slice(&[params]),
+            );
+        }
+
+        let pipelines: HashMap<_, _> = [
+            wgpu::PrimitiveTopology::TriangleList,
+            wgpu::PrimitiveTopology::TriangleStrip,
+        ]
+        .into_iter()
+        .flat_map(|topology| [(topology, false), (topology, true)])
+        .map(|key| {
+            let pipeline = self
+                .pipelines
+                .get_or_create(key, || Self::pipeline(device, key.0, key.1));
+            (key, pipeline)
+        })
+        .collect();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        for (i, shadow) in shadows.iter().enumerate() {
+            let (x, y) = (i as u32 % ATLAS_TILES, i as u32 / ATLAS_TILES);
+            let size = self.tile_size as f32;
+            render_pass.set_viewport(x as f32 * size, y as f32 * size, size, size, 0., 1.);
+            render_pass.set_bind_group(
+                0,
+                &self.pass_bind_group,
+                &[(i as u64 * self.pass_stride) as u32],
+            );
+
+            for mesh in meshes.iter().filter(|mesh| mesh.cast_shadow) {
+                let Some(pipeline) = pipelines.get(&(mesh.topology, shadow.far > 0.)) else {
+                    continue;
+                };
+                render_pass.set_pipeline(pipeline);
+
+                let geometry = &mesh.geometry;
+                render_pass.set_vertex_buffer(0, geometry.vertices.slice(..));
+                render_pass.set_vertex_buffer(1, mesh.instances.buffer.slice(..));
+
+                let instances = 0..mesh.instances.len;
+                if let Some(ref indices) = geometry.indices {
+                    render_pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..geometry.indices_len, 0, instances);
+                } else {
+                    render_pass.draw(0..geometry.vertices_len, instances);
+                }
+            }
+        }
+    }
+
+    fn pass_layout(device: &wgpu::Device) -> &'static wgpu::BindGroupLayout {
+        SHADOW_PASS_LAYOUT.get_or_init(|| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Shadow Pass Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            })
+        })
+    }
+
+    /// `linear` pipelines write the distance to the light instead of the hardware depth.
+    fn pipeline(
+        device: &wgpu::Device,
+        topology: wgpu::PrimitiveTopology,
+        linear: bool,
+    ) -> wgpu::RenderPipeline {
+        let label = "san::shadow::ShadowPass";
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: crate::shader::builtin("shadow.wgsl", ShaderFeatures::empty()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[Self::pass_layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology,
+                strip_index_format: topology.is_strip().then_some(wgpu::IndexFormat::Uint32),
+                // thin geometry such as planes has to cast from both sides
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.,
+                    clamp: 0.,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: linear.then_some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_linear",
+                targets: &[],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowPassParams {
+    view_proj: [[f32; 4]; 4],
+    light_position: [f32; 3],
+    far: f32,
+}
@@ -0,0 +1,106 @@
+use std::ops::{Index, IndexMut};
+
+/// Values addressed by index and generation. The generation of a slot is bumped when its
+/// value is removed, so that the handles of a removed value don't match the value that
+/// takes its slot.
+pub(crate) struct Slots<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+impl<T> Slots<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Returns the index and generation of the value.
+    pub(crate) fn insert(&mut self, value: T) -> (usize, u32) {
+        let index = match self.free.pop() {
+            Some(index) => {
+                debug_assert!(self.slots[index].value.is_none());
+                self.slots[index].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                self.slots.len() - 1
+            }
+        };
+
+        (index, self.slots[index].generation)
+    }
+
+    pub(crate) fn contains(&self, index: usize, generation: u32) -> bool {
+        self.slots
+            .get(index)
+            .is_some_and(|slot| slot.generation == generation && slot.value.is_some())
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) -> Option<T> {
+        let slot = self.slots.get_mut(index)?;
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+        Some(value)
+    }
+
+    /// Live values with their index and generation, in slot order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, u32, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.generation, slot.value.as_ref()?)))
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (usize, u32, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.generation, slot.value.as_mut()?)))
+    }
+
+    /// The index one past the last slot.
+    pub(crate) fn end(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<(u32, &T)> {
+        let slot = self.slots.get(index)?;
+        Some((slot.generation, slot.value.as_ref()?))
+    }
+
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<(u32, &mut T)> {
+        let slot = self.slots.get_mut(index)?;
+        Some((slot.generation, slot.value.as_mut()?))
+    }
+}
+
+// indexing is for the links between values, which are removed with the value
+impl<T> Index<usize> for Slots<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.slots[index].value.as_ref().unwrap()
+    }
+}
+
+impl<T> IndexMut<usize> for Slots<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.slots[index].value.as_mut().unwrap()
+    }
+}
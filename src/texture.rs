@@ -0,0 +1,915 @@
+use std::{
+    error, fmt,
+    num::{NonZeroU32, NonZeroU8},
+    sync::Arc,
+};
+
+use half::f16;
+use image::{codecs::hdr::HdrDecoder, ImageFormat};
+use once_cell::sync::OnceCell;
+
+use crate::{
+    equirect,
+    gpu::{GpuCached, ToGpu},
+    mipmap,
+};
+
+mod astc;
+mod bc;
+mod compressed;
+mod etc;
+
+/// How the stored texel values are interpreted when sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Color data such as base color and emissive maps, decoded to linear when sampled.
+    Srgb,
+    /// Non-color data such as normal, roughness and occlusion maps.
+    Linear,
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Decode(image::ImageError),
+    Ktx2(ktx2::ParseError),
+    /// A valid KTX2 file using a feature that cannot be loaded, such as supercompression or
+    /// array layers.
+    UnsupportedKtx2(&'static str),
+    /// Cube map faces have to be square and share size, color space and texel format
+    /// with the first face. Render target textures cannot be faces.
+    CubeFace(usize),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "failed to decode image: {e}"),
+            Self::Ktx2(e) => write!(f, "failed to parse KTX2 file: {e}"),
+            Self::UnsupportedKtx2(what) => write!(f, "unsupported KTX2 file: {what}"),
+            Self::CubeFace(face) => write!(f, "cube map face {face} does not match face 0"),
+        }
+    }
+}
+
+impl error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Decode(e) => Some(e),
+            Self::Ktx2(e) => Some(e),
+            Self::UnsupportedKtx2(_) | Self::CubeFace(_) => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for TextureError {
+    fn from(e: image::ImageError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl From<ktx2::ParseError> for TextureError {
+    fn from(e: ktx2::ParseError) -> Self {
+        Self::Ktx2(e)
+    }
+}
+
+/// Image data uploaded to the GPU on first use, together with the sampler it is read with.
+/// Clones share the same upload.
+#[derive(Clone)]
+pub struct Texture {
+    data: Arc<GpuCached<TextureData>>,
+    sampler: Sampler,
+}
+
+impl Texture {
+    pub fn from_rgba8(width: u32, height: u32, data: Vec<u8>, color_space: ColorSpace) -> Self {
+        assert_eq!(data.len(), (width * height * 4) as usize);
+
+        Self::from_data(TextureData {
+            width,
+            height,
+            color_space,
+            mipmaps: true,
+            texels: Texels::Rgba8(data),
+        })
+    }
+
+    /// High dynamic range data in linear color space, stored as half floats on the GPU.
+    pub fn from_rgba32f(width: u32, height: u32, data: Vec<f32>) -> Self {
+        assert_eq!(data.len(), (width * height * 4) as usize);
+
+        Self::from_data(TextureData {
+            width,
+            height,
+            color_space: ColorSpace::Linear,
+            mipmaps: true,
+            texels: Texels::Rgba32Float(data),
+        })
+    }
+
+    /// Decodes a PNG, JPEG or Radiance HDR image, the format is detected from the data.
+    /// HDR images are always linear and `color_space` is ignored for them.
+    pub fn from_image_bytes(bytes: &[u8], color_space: ColorSpace) -> Result<Self, TextureError> {
+        // the generic decoder tone maps HDR images to 8 bit
+        if image::guess_format(bytes)? == ImageFormat::Hdr {
+            let decoder = HdrDecoder::new(bytes)?;
+            let metadata = decoder.metadata();
+            let data = decoder
+                .read_image_hdr()?
+                .into_iter()
+                .flat_map(|texel| [texel[0], texel[1], texel[2], 1.])
+                .collect();
+            return Ok(Self::from_rgba32f(metadata.width, metadata.height, data));
+        }
+
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        let (width, height) = image.dimensions();
+
+        Ok(Self::from_rgba8(
+            width,
+            height,
+            image.into_raw(),
+            color_space,
+        ))
+    }
+
+    /// Loads a 2D texture from a KTX2 container holding BC1–BC7, ETC2, EAC or LDR ASTC blocks,
+    /// or uncompressed RGBA8 or RGBA16F texels. The color space follows the texel format.
+    ///
+    /// Block compressed levels are uploaded as stored in the file when the device has the
+    /// matching [`wgpu::Features`] and the size is a multiple of the block size. Otherwise
+    /// the first level is decompressed on the CPU and the mip chain is generated from it.
+    pub fn from_ktx2(bytes: &[u8]) -> Result<Self, TextureError> {
+        let reader = ktx2::Reader::new(bytes)?;
+        let header = reader.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(TextureError::UnsupportedKtx2("supercompression"));
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            return Err(TextureError::UnsupportedKtx2("not a 2D texture"));
+        }
+        let (format, color_space) = header
+            .format
+            .and_then(compressed::wgpu_format)
+            .ok_or(TextureError::UnsupportedKtx2("texel format"))?;
+
+        let size = wgpu::Extent3d {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let mut levels = Vec::new();
+        for (level, data) in reader
+            .levels()
+            .take(mipmap::mip_level_count(size.width, size.height) as usize)
+            .enumerate()
+        {
+            let len = level_len(
+                size.mip_level_size(level as u32, wgpu::TextureDimension::D2),
+                format,
+            );
+            levels.push(
+                data.get(..len)
+                    .ok_or(ktx2::ParseError::UnexpectedEnd)?
+                    .to_vec(),
+            );
+        }
+        if levels.is_empty() {
+            return Err(ktx2::ParseError::UnexpectedEnd.into());
+        }
+
+        let texels = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                Texels::Rgba8(levels.swap_remove(0))
+            }
+            wgpu::TextureFormat::Rgba16Float => Texels::Rgba32Float(
+                levels[0]
+                    .chunks_exact(2)
+                    .map(|v| f16::from_le_bytes([v[0], v[1]]).to_f32())
+                    .collect(),
+            ),
+            _ => Texels::Compressed {
+                format,
+                levels,
+                decoded: OnceCell::new(),
+            },
+        };
+
+        Ok(Self::from_data(TextureData {
+            width: size.width,
+            height: size.height,
+            color_space,
+            mipmaps: true,
+            texels,
+        }))
+    }
+
+    /// Empty texture rendered into on the GPU, returned with its upload to draw into.
+    pub(crate) fn attachment(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> (Self, Arc<TextureGpuData>) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let gpu_data = Arc::new(TextureGpuData {
+            texture,
+            view,
+            mip_level_count: 1,
+        });
+
+        let data = TextureData {
+            width,
+            height,
+            color_space: if format.describe().srgb {
+                ColorSpace::Srgb
+            } else {
+                ColorSpace::Linear
+            },
+            mipmaps: false,
+            texels: Texels::Attachment(format),
+        };
+        let texture = Self {
+            data: Arc::new(GpuCached::with_gpu_data(data, Arc::clone(&gpu_data))),
+            sampler: Sampler::default(),
+        };
+
+        (texture, gpu_data)
+    }
+
+    pub(crate) fn solid(rgba: [u8; 4], color_space: ColorSpace) -> Self {
+        Self::from_rgba8(1, 1, rgba.to_vec(), color_space)
+    }
+
+    fn from_data(data: TextureData) -> Self {
+        Self {
+            data: Arc::new(GpuCached::new(data)),
+            sampler: Sampler::default(),
+        }
+    }
+
+    /// Shares the image data, sampled with `sampler`.
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    /// Whether a full mip chain is generated on upload, enabled by default.
+    /// Disabling it saves memory for textures that are never minified. Render target
+    /// textures always have a single level.
+    pub fn with_mipmaps(self, mipmaps: bool) -> Self {
+        if let Texels::Attachment(_) = self.data.texels {
+            return self;
+        }
+
+        let mut data = TextureData::clone(&self.data);
+        data.mipmaps = mipmaps;
+        Self {
+            data: Arc::new(GpuCached::new(data)),
+            ..self
+        }
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    pub fn width(&self) -> u32 {
+        self.data.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.data.height
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.data.color_space
+    }
+
+    /// Number of mip levels on the GPU. Block compressed textures keep the levels stored
+    /// in the file, unless they are decompressed on the CPU.
+    pub fn mip_level_count(&self) -> u32 {
+        self.data.mip_level_count()
+    }
+
+    /// Texels converted to linear RGBA, none for render target textures.
+    pub(crate) fn linear_texels(&self) -> Box<dyn Iterator<Item = [f32; 4]> + '_> {
+        let data = self.data.decoded();
+        let srgb = data.color_space == ColorSpace::Srgb;
+
+        match &data.texels {
+            Texels::Rgba8(data) => Box::new(data.chunks_exact(4).map(move |texel| {
+                let mut c = [texel[0], texel[1], texel[2], texel[3]].map(|v| v as f32 / 255.);
+                if srgb {
+                    for v in &mut c[..3] {
+                        *v = srgb_to_linear(*v);
+                    }
+                }
+                c
+            })),
+            Texels::Rgba32Float(data) => Box::new(
+                data.chunks_exact(4)
+                    .map(|texel| [texel[0], texel[1], texel[2], texel[3]]),
+            ),
+            Texels::Compressed { .. } => unreachable!("decoded texels are uncompressed"),
+            Texels::Attachment(_) => Box::new(std::iter::empty()),
+        }
+    }
+
+    pub(crate) fn gpu_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Arc<TextureGpuData> {
+        self.data.to_gpu(device, queue, format)
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("width", &self.data.width)
+            .field("height", &self.data.height)
+            .field("color_space", &self.data.color_space)
+            .field("mip_level_count", &self.data.mip_level_count())
+            .field("sampler", &self.sampler)
+            .finish_non_exhaustive()
+    }
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Bytes of a mip level of `size` texels in a block compressed or uncompressed `format`.
+fn level_len(size: wgpu::Extent3d, format: wgpu::TextureFormat) -> usize {
+    let info = format.describe();
+    let size = size.physical_size(format);
+    let blocks_x = size.width / info.block_dimensions.0 as u32;
+    let blocks_y = size.height / info.block_dimensions.1 as u32;
+    (blocks_x * blocks_y * info.block_size as u32) as usize
+}
+
+#[derive(Clone)]
+enum Texels {
+    Rgba8(Vec<u8>),
+    Rgba32Float(Vec<f32>),
+    /// Block compressed mip levels, largest first, with the CPU decompressed first level
+    /// for devices without support for `format`.
+    Compressed {
+        format: wgpu::TextureFormat,
+        levels: Vec<Vec<u8>>,
+        decoded: OnceCell<Box<TextureData>>,
+    },
+    /// Contents of a [`RenderTarget`](crate::RenderTarget), which only exist on the GPU.
+    Attachment(wgpu::TextureFormat),
+}
+
+#[derive(Clone)]
+pub struct TextureData {
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+    mipmaps: bool,
+    texels: Texels,
+}
+
+impl TextureData {
+    fn format(&self) -> wgpu::TextureFormat {
+        match (&self.texels, self.color_space) {
+            (Texels::Rgba8(_), ColorSpace::Srgb) => wgpu::TextureFormat::Rgba8UnormSrgb,
+            (Texels::Rgba8(_), ColorSpace::Linear) => wgpu::TextureFormat::Rgba8Unorm,
+            // 32 bit floats are not filterable without an extra feature
+            (Texels::Rgba32Float(_), _) => wgpu::TextureFormat::Rgba16Float,
+            (Texels::Compressed { .. }, _) => self.decoded().format(),
+            (Texels::Attachment(format), _) => *format,
+        }
+    }
+
+    /// Texels encoded in [`TextureData::format`].
+    fn bytes(&self) -> Vec<u8> {
+        match &self.texels {
+            Texels::Rgba8(data) => data.clone(),
+            Texels::Rgba32Float(data) => {
+                let half: Vec<u16> = data.iter().map(|&v| f16::from_f32(v).to_bits()).collect();
+                bytemuck::cast_slice(&half).to_vec()
+            }
+            Texels::Compressed { .. } => self.decoded().bytes(),
+            Texels::Attachment(_) => unreachable!("render target texels stay on the GPU"),
+        }
+    }
+
+    fn mip_level_count(&self) -> u32 {
+        match &self.texels {
+            _ if !self.mipmaps => 1,
+            Texels::Compressed { levels, .. } => levels.len() as u32,
+            _ => mipmap::mip_level_count(self.width, self.height),
+        }
+    }
+
+    /// Uncompressed texels, block compressed data is decoded on first use.
+    fn decoded(&self) -> &TextureData {
+        let Texels::Compressed {
+            format,
+            levels,
+            decoded,
+        } = &self.texels
+        else {
+            return self;
+        };
+
+        decoded.get_or_init(|| {
+            let texels = compressed::decode(*format, self.width, self.height, &levels[0]);
+            Box::new(TextureData {
+                width: self.width,
+                height: self.height,
+                color_space: self.color_space,
+                mipmaps: self.mipmaps,
+                texels,
+            })
+        })
+    }
+
+    /// Uploads block compressed levels without decoding them.
+    fn upload_compressed(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        levels: &[Vec<u8>],
+    ) -> TextureGpuData {
+        let info = format.describe();
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        let mip_level_count = self.mip_level_count();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture"),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        for (level, data) in levels.iter().take(mip_level_count as usize).enumerate() {
+            let level_size = size
+                .mip_level_size(level as u32, wgpu::TextureDimension::D2)
+                .physical_size(format);
+            let blocks_x = level_size.width / info.block_dimensions.0 as u32;
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(blocks_x * info.block_size as u32),
+                    rows_per_image: None,
+                },
+                level_size,
+            );
+        }
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        TextureGpuData {
+            texture,
+            view,
+            mip_level_count,
+        }
+    }
+}
+
+impl ToGpu for TextureData {
+    type Target = TextureGpuData;
+
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        if let Texels::Compressed { format, levels, .. } = &self.texels {
+            let info = format.describe();
+            let (block_width, block_height) = info.block_dimensions;
+            if device.features().contains(info.required_features)
+                && self.width % block_width as u32 == 0
+                && self.height % block_height as u32 == 0
+            {
+                return self.upload_compressed(device, queue, *format, levels);
+            }
+        }
+
+        let this = self.decoded();
+        let (format, data) = (this.format(), this.bytes());
+
+        let size = wgpu::Extent3d {
+            width: this.width,
+            height: this.height,
+            depth_or_array_layers: 1,
+        };
+        let mip_level_count = this.mip_level_count();
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        if mip_level_count > 1 {
+            // lower levels are rendered from the level above
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture"),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            &data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(data.len() as u32 / this.height),
+                rows_per_image: None,
+            },
+            size,
+        );
+        mipmap::generate_mipmaps(device, queue, &texture, format, mip_level_count);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self::Target {
+            texture,
+            view,
+            mip_level_count,
+        }
+    }
+}
+
+pub struct TextureGpuData {
+    pub(crate) texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    pub(crate) mip_level_count: u32,
+}
+
+/// Six square images forming the inside of a cube, sampled by direction. Clones share the
+/// same upload.
+#[derive(Clone)]
+pub struct CubeTexture {
+    data: Arc<GpuCached<CubeTextureData>>,
+    sampler: Sampler,
+}
+
+impl CubeTexture {
+    /// Faces in the order +X, -X, +Y, -Y, +Z, -Z, each seen from the inside of the cube.
+    pub fn from_faces(faces: [Texture; 6]) -> Result<Self, TextureError> {
+        let first = &faces[0].data;
+        if let Some(face) = faces.iter().position(|face| {
+            let face = &face.data;
+            face.width != first.width
+                || face.height != first.width
+                || face.format() != first.format()
+                || matches!(face.texels, Texels::Attachment(_))
+        }) {
+            return Err(TextureError::CubeFace(face));
+        }
+
+        Ok(Self::from_data(CubeTextureData::Faces(faces)))
+    }
+
+    /// Decodes six images, see [`Texture::from_image_bytes`] and [`CubeTexture::from_faces`].
+    pub fn from_images(faces: [&[u8]; 6], color_space: ColorSpace) -> Result<Self, TextureError> {
+        let mut textures = Vec::with_capacity(6);
+        for bytes in faces {
+            textures.push(Texture::from_image_bytes(bytes, color_space)?);
+        }
+
+        Self::from_faces(textures.try_into().unwrap())
+    }
+
+    /// Projects an equirectangular panorama, such as an HDR environment, onto faces of
+    /// `size` × `size` texels. The conversion runs on the GPU on first use.
+    pub fn from_equirect(texture: Texture, size: u32) -> Self {
+        Self::from_data(CubeTextureData::Equirect { texture, size })
+    }
+
+    fn from_data(data: CubeTextureData) -> Self {
+        Self {
+            data: Arc::new(GpuCached::new(data)),
+            sampler: Sampler::default(),
+        }
+    }
+
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        Self { sampler, ..self }
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// Width and height of each face.
+    pub fn size(&self) -> u32 {
+        match &**self.data {
+            CubeTextureData::Faces(faces) => faces[0].width(),
+            CubeTextureData::Equirect { size, .. } => *size,
+        }
+    }
+
+    pub(crate) fn gpu_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Arc<TextureGpuData> {
+        self.data.to_gpu(device, queue, format)
+    }
+}
+
+impl fmt::Debug for CubeTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CubeTexture")
+            .field("size", &self.size())
+            .field("sampler", &self.sampler)
+            .finish_non_exhaustive()
+    }
+}
+
+pub enum CubeTextureData {
+    Faces([Texture; 6]),
+    Equirect { texture: Texture, size: u32 },
+}
+
+impl ToGpu for CubeTextureData {
+    type Target = TextureGpuData;
+
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        let (format, size) = match self {
+            Self::Faces(faces) => (faces[0].data.format(), faces[0].width()),
+            Self::Equirect { texture, size } => (texture.data.format(), *size),
+        };
+        let extent = wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Cube Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        match self {
+            Self::Faces(faces) => {
+                for (layer, face) in faces.iter().enumerate() {
+                    let data = face.data.bytes();
+                    queue.write_texture(
+                        wgpu::ImageCopyTexture {
+                            texture: &texture,
+                            mip_level: 0,
+                            origin: wgpu::Origin3d {
+                                x: 0,
+                                y: 0,
+                                z: layer as u32,
+                            },
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        &data,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: NonZeroU32::new(data.len() as u32 / size),
+                            rows_per_image: None,
+                        },
+                        wgpu::Extent3d {
+                            depth_or_array_layers: 1,
+                            ..extent
+                        },
+                    );
+                }
+            }
+            Self::Equirect {
+                texture: source, ..
+            } => {
+                let source = source.gpu_data(device, queue, format);
+                equirect::project(device, queue, &source.view, &texture, format);
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        Self::Target {
+            texture,
+            view,
+            mip_level_count: 1,
+        }
+    }
+}
+
+/// Filtering and wrapping used to read a [`Texture`]. Clones share the same GPU sampler.
+#[derive(Clone)]
+pub struct Sampler(Arc<GpuCached<SamplerData>>);
+
+impl Sampler {
+    /// Trilinear filtering, blending between the two closest mip levels.
+    pub fn linear() -> Self {
+        Self::from_data(SamplerData {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            address_modes: [wgpu::AddressMode::Repeat; 3],
+            anisotropy: 1,
+        })
+    }
+
+    pub fn nearest() -> Self {
+        Self::linear().filter(wgpu::FilterMode::Nearest)
+    }
+
+    /// Sets magnification, minification and mipmap filters.
+    pub fn filter(self, filter: wgpu::FilterMode) -> Self {
+        self.with(|data| {
+            data.mag_filter = filter;
+            data.min_filter = filter;
+            data.mipmap_filter = filter;
+        })
+    }
+
+    pub fn mag_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.with(|data| data.mag_filter = filter)
+    }
+
+    pub fn min_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.with(|data| data.min_filter = filter)
+    }
+
+    pub fn mipmap_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.with(|data| data.mipmap_filter = filter)
+    }
+
+    /// Sets the wrap mode of all axes.
+    pub fn wrap(self, mode: wgpu::AddressMode) -> Self {
+        self.with(|data| data.address_modes = [mode; 3])
+    }
+
+    pub fn wrap_u(self, mode: wgpu::AddressMode) -> Self {
+        self.with(|data| data.address_modes[0] = mode)
+    }
+
+    pub fn wrap_v(self, mode: wgpu::AddressMode) -> Self {
+        self.with(|data| data.address_modes[1] = mode)
+    }
+
+    pub fn wrap_w(self, mode: wgpu::AddressMode) -> Self {
+        self.with(|data| data.address_modes[2] = mode)
+    }
+
+    /// Maximum anisotropy, rounded down to a power of two up to 16. It only takes effect
+    /// when all filters are linear and the device supports anisotropic filtering.
+    pub fn anisotropy(self, anisotropy: u8) -> Self {
+        let anisotropy = anisotropy.clamp(1, 16);
+        self.with(|data| data.anisotropy = 1 << (7 - anisotropy.leading_zeros()))
+    }
+
+    pub(crate) fn gpu_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Arc<wgpu::Sampler> {
+        self.0.to_gpu(device, queue, format)
+    }
+
+    fn from_data(data: SamplerData) -> Self {
+        Self(Arc::new(GpuCached::new(data)))
+    }
+
+    fn with<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut SamplerData),
+    {
+        let mut data = **self.0;
+        f(&mut data);
+        Self::from_data(data)
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::linear()
+    }
+}
+
+impl PartialEq for Sampler {
+    fn eq(&self, other: &Self) -> bool {
+        **self.0 == **other.0
+    }
+}
+
+impl fmt::Debug for Sampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self.0, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerData {
+    mag_filter: wgpu::FilterMode,
+    min_filter: wgpu::FilterMode,
+    mipmap_filter: wgpu::FilterMode,
+    address_modes: [wgpu::AddressMode; 3],
+    anisotropy: u8,
+}
+
+impl ToGpu for SamplerData {
+    type Target = wgpu::Sampler;
+
+    fn to_gpu(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _format: wgpu::TextureFormat,
+    ) -> Self::Target {
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|&filter| filter == wgpu::FilterMode::Linear);
+
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sampler"),
+            address_mode_u: self.address_modes[0],
+            address_mode_v: self.address_modes[1],
+            address_mode_w: self.address_modes[2],
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            anisotropy_clamp: NonZeroU8::new(self.anisotropy).filter(|&a| linear && a.get() > 1),
+            ..Default::default()
+        })
+    }
+}
+
+/// Layout entry of a filterable 2D texture read in the fragment stage.
+pub(crate) fn texture_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+/// Layout entry of a filtering sampler read in the fragment stage.
+pub(crate) fn sampler_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }
+}
@@ -0,0 +1,679 @@
+//! ASTC LDR block decoder for 2D blocks, following the Khronos Data Format Specification.
+//! Every block is 16 bytes, covering between 4×4 and 12×12 texels in row-major order.
+
+/// Result of blocks that are malformed or use HDR features.
+const ERROR_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// Quantization ranges of the integer sequence encoding: number of levels, whether the
+/// values carry a trit or a quint, and the number of plain bits.
+#[derive(Clone, Copy)]
+struct Range {
+    levels: u32,
+    trit: bool,
+    quint: bool,
+    bits: u32,
+}
+
+const fn range(levels: u32, trit: bool, quint: bool, bits: u32) -> Range {
+    Range {
+        levels,
+        trit,
+        quint,
+        bits,
+    }
+}
+
+#[rustfmt::skip]
+const RANGES: [Range; 21] = [
+    range(2, false, false, 1), range(3, true, false, 0), range(4, false, false, 2),
+    range(5, false, true, 0), range(6, true, false, 1), range(8, false, false, 3),
+    range(10, false, true, 1), range(12, true, false, 2), range(16, false, false, 4),
+    range(20, false, true, 2), range(24, true, false, 3), range(32, false, false, 5),
+    range(40, false, true, 3), range(48, true, false, 4), range(64, false, false, 6),
+    range(80, false, true, 4), range(96, true, false, 5), range(128, false, false, 7),
+    range(160, false, true, 5), range(192, true, false, 6), range(256, false, false, 8),
+];
+
+impl Range {
+    /// Bits taken by `count` values.
+    fn sequence_bits(&self, count: u32) -> u32 {
+        self.bits * count
+            + if self.trit {
+                (8 * count).div_ceil(5)
+            } else {
+                0
+            }
+            + if self.quint {
+                (7 * count).div_ceil(3)
+            } else {
+                0
+            }
+    }
+}
+
+/// Reads little endian bit fields, past the end of the block reads zeros.
+fn bits(block: u128, start: u32, count: u32) -> u32 {
+    if start >= 128 || count == 0 {
+        return 0;
+    }
+    ((block >> start) & ((1u128 << count) - 1)) as u32
+}
+
+struct BlockMode {
+    grid_width: usize,
+    grid_height: usize,
+    dual_plane: bool,
+    weight_range: Range,
+}
+
+fn block_mode(block: u128) -> Option<BlockMode> {
+    let mode = bits(block, 0, 11);
+    let bit = |i: u32| mode >> i & 1;
+    let a = mode >> 5 & 3;
+    let b = mode >> 7 & 3;
+
+    let (r, width, height, mut dual_plane, mut high_precision);
+    dual_plane = bit(10) == 1;
+    high_precision = bit(9) == 1;
+
+    if mode & 3 != 0 {
+        r = (mode & 3) << 1 | bit(4);
+        (width, height) = match mode >> 2 & 3 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if bit(8) == 0 => (a + 2, bit(7) + 6),
+            _ => (bit(7) + 2, a + 2),
+        };
+    } else {
+        r = (mode >> 2 & 3) << 1 | bit(4);
+        if r < 2 {
+            return None;
+        }
+        (width, height) = match mode >> 7 & 3 {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                dual_plane = false;
+                high_precision = false;
+                (a + 6, (mode >> 9 & 3) + 6)
+            }
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        };
+    }
+    if r < 2 {
+        return None;
+    }
+
+    let weight_range = RANGES[(r - 2 + if high_precision { 6 } else { 0 }) as usize];
+    Some(BlockMode {
+        grid_width: width as usize,
+        grid_height: height as usize,
+        dual_plane,
+        weight_range,
+    })
+}
+
+fn decode_trits(t: u32) -> [u32; 5] {
+    let bit = |i: u32| t >> i & 1;
+    let (c, t4, t3);
+    if t >> 2 & 7 == 7 {
+        c = (t >> 5 & 7) << 2 | (t & 3);
+        t4 = 2;
+        t3 = 2;
+    } else {
+        c = t & 31;
+        if t >> 5 & 3 == 3 {
+            t4 = 2;
+            t3 = bit(7);
+        } else {
+            t4 = bit(7);
+            t3 = t >> 5 & 3;
+        }
+    }
+
+    let cbit = |i: u32| c >> i & 1;
+    let (t2, t1, t0);
+    if c & 3 == 3 {
+        t2 = 2;
+        t1 = cbit(4);
+        t0 = cbit(3) << 1 | (cbit(2) & !cbit(3) & 1);
+    } else if c >> 2 & 3 == 3 {
+        t2 = 2;
+        t1 = 2;
+        t0 = c & 3;
+    } else {
+        t2 = cbit(4);
+        t1 = c >> 2 & 3;
+        t0 = cbit(1) << 1 | (cbit(0) & !cbit(1) & 1);
+    }
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(q: u32) -> [u32; 3] {
+    let bit = |i: u32| q >> i & 1;
+    if q >> 1 & 3 == 3 && q >> 5 & 3 == 0 {
+        let q2 = bit(0) << 2 | (bit(4) & !bit(0) & 1) << 1 | (bit(3) & !bit(0) & 1);
+        return [4, 4, q2];
+    }
+
+    let (q2, c);
+    if q >> 1 & 3 == 3 {
+        q2 = 4;
+        c = (q >> 3 & 3) << 3 | (!(q >> 5) & 3) << 1 | bit(0);
+    } else {
+        q2 = q >> 5 & 3;
+        c = q & 31;
+    }
+    let (q1, q0) = if c & 7 == 5 {
+        (4, c >> 3 & 3)
+    } else {
+        (c >> 3 & 3, c & 7)
+    };
+    [q0, q1, q2]
+}
+
+/// Decodes `count` integers of `range` stored from bit `start` upwards.
+fn decode_sequence(block: u128, start: u32, count: usize, range: Range) -> Vec<u32> {
+    let mut values = Vec::with_capacity(count + 4);
+    let mut position = start;
+    let n = range.bits;
+
+    while values.len() < count {
+        if range.trit {
+            let mut m = [0; 5];
+            let mut t = 0;
+            // trit bits are interleaved between the values: 2, 2, 1, 2, 1
+            for (i, t_bits) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)]
+                .into_iter()
+                .enumerate()
+            {
+                m[i] = bits(block, position, n);
+                position += n;
+                t |= bits(block, position, t_bits.1) << t_bits.0;
+                position += t_bits.1;
+            }
+            for (m, t) in m.iter().zip(decode_trits(t)) {
+                values.push(t << n | m);
+            }
+        } else if range.quint {
+            let mut m = [0; 3];
+            let mut q = 0;
+            for (i, q_bits) in [(0, 3), (3, 2), (5, 2)].into_iter().enumerate() {
+                m[i] = bits(block, position, n);
+                position += n;
+                q |= bits(block, position, q_bits.1) << q_bits.0;
+                position += q_bits.1;
+            }
+            for (m, q) in m.iter().zip(decode_quints(q)) {
+                values.push(q << n | m);
+            }
+        } else {
+            values.push(bits(block, position, n));
+            position += n;
+        }
+    }
+    values.truncate(count);
+    values
+}
+
+fn unquantize_color(value: u32, range: Range) -> u32 {
+    let n = range.bits;
+    if !range.trit && !range.quint {
+        // bit replication up to 8 bits
+        let mut v = value << (8 - n);
+        let mut shift = n;
+        while shift < 8 {
+            v |= v >> shift;
+            shift *= 2;
+        }
+        return v & 255;
+    }
+
+    let m = value & ((1 << n) - 1);
+    let d = value >> n;
+    let a = if m & 1 == 1 { 0x1ff } else { 0 };
+    let (b, c) = match (range.trit, n) {
+        (true, 1) => (0, 204),
+        (true, 2) => {
+            let b = m >> 1 & 1;
+            (b << 8 | b << 4 | b << 2 | b << 1, 93)
+        }
+        (true, 3) => {
+            let cb = m >> 1 & 3;
+            (cb << 7 | cb << 2 | cb, 44)
+        }
+        (true, 4) => {
+            let dcb = m >> 1 & 7;
+            (dcb << 6 | dcb, 22)
+        }
+        (true, 5) => {
+            let edcb = m >> 1 & 15;
+            (edcb << 5 | edcb >> 2, 11)
+        }
+        (true, _) => {
+            let fedcb = m >> 1 & 31;
+            (fedcb << 4 | fedcb >> 4, 5)
+        }
+        (false, 1) => (0, 113),
+        (false, 2) => {
+            let b = m >> 1 & 1;
+            (b << 8 | b << 3 | b << 2, 54)
+        }
+        (false, 3) => {
+            let cb = m >> 1 & 3;
+            (cb << 7 | cb << 1 | cb >> 1, 26)
+        }
+        (false, 4) => {
+            let dcb = m >> 1 & 7;
+            (dcb << 6 | dcb >> 1, 13)
+        }
+        (false, _) => {
+            let edcb = m >> 1 & 15;
+            (edcb << 5 | edcb >> 3, 6)
+        }
+    };
+
+    let t = (d * c + b) ^ a;
+    (a & 0x80) | t >> 2
+}
+
+fn unquantize_weight(value: u32, range: Range) -> u32 {
+    let n = range.bits;
+    let t = if !range.trit && !range.quint {
+        let mut v = value << (6 - n);
+        let mut shift = n;
+        while shift < 6 {
+            v |= v >> shift;
+            shift *= 2;
+        }
+        v & 63
+    } else if n == 0 {
+        if range.trit {
+            [0, 32, 63][value as usize]
+        } else {
+            [0, 16, 32, 47, 63][value as usize]
+        }
+    } else {
+        let m = value & ((1 << n) - 1);
+        let d = value >> n;
+        let a = if m & 1 == 1 { 0x7f } else { 0 };
+        let (b, c) = match (range.trit, n) {
+            (true, 1) => (0, 50),
+            (true, 2) => {
+                let b = m >> 1 & 1;
+                (b << 6 | b << 2 | b, 23)
+            }
+            (true, _) => {
+                let cb = m >> 1 & 3;
+                (cb << 5 | cb, 11)
+            }
+            (false, 1) => (0, 28),
+            (false, _) => {
+                let b = m >> 1 & 1;
+                (b << 6 | b << 1, 13)
+            }
+        };
+        let t = (d * c + b) ^ a;
+        (a & 0x20) | t >> 2
+    };
+
+    if t > 32 {
+        t + 1
+    } else {
+        t
+    }
+}
+
+fn hash52(mut p: u32) -> u32 {
+    p ^= p >> 15;
+    p = p.wrapping_mul(0xeede0891);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+fn select_partition(seed: u32, x: u32, y: u32, count: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (count - 1) * 1024;
+    let rnum = hash52(seed);
+
+    let mut s = [
+        rnum,
+        rnum >> 4,
+        rnum >> 8,
+        rnum >> 12,
+        rnum >> 16,
+        rnum >> 20,
+        rnum >> 24,
+        rnum >> 28,
+        rnum >> 18,
+        rnum >> 22,
+        rnum >> 26,
+        rnum.rotate_left(2),
+    ]
+    .map(|v| {
+        let v = v & 15;
+        v * v
+    });
+
+    let (sh1, sh2) = if seed & 1 == 1 {
+        (
+            if seed & 2 != 0 { 4 } else { 5 },
+            if count == 3 { 6 } else { 5 },
+        )
+    } else {
+        (
+            if count == 3 { 6 } else { 5 },
+            if seed & 2 != 0 { 4 } else { 5 },
+        )
+    };
+    let sh3 = if seed & 0x10 != 0 { sh1 } else { sh2 };
+    for (i, v) in s.iter_mut().enumerate() {
+        *v >>= match i {
+            0..=7 if i % 2 == 0 => sh1,
+            0..=7 => sh2,
+            _ => sh3,
+        };
+    }
+
+    // z is always zero for 2D blocks
+    let a = (s[0] * x + s[1] * y + (rnum >> 14)) & 0x3f;
+    let b = (s[2] * x + s[3] * y + (rnum >> 10)) & 0x3f;
+    let c = if count < 3 {
+        0
+    } else {
+        (s[4] * x + s[5] * y + (rnum >> 6)) & 0x3f
+    };
+    let d = if count < 4 {
+        0
+    } else {
+        (s[6] * x + s[7] * y + (rnum >> 2)) & 0x3f
+    };
+
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let b = (b >> 1) | (a & 0x80);
+    let mut a = (a >> 1) & 0x3f;
+    if a & 0x20 != 0 {
+        a -= 0x40;
+    }
+    (a, b)
+}
+
+fn blue_contract(r: i32, g: i32, b: i32, a: i32) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+/// Endpoint pair of an LDR color endpoint mode, `None` for HDR modes.
+fn endpoints(mode: u32, v: &[u32]) -> Option<[[u8; 4]; 2]> {
+    let v: Vec<i32> = v.iter().map(|&v| v as i32).collect();
+    let (e0, e1) = match mode {
+        0 => ([v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]),
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xc0);
+            let l1 = (l0 + (v[1] & 0x3f)).min(255);
+            ([l0, l0, l0, 255], [l1, l1, l1, 255])
+        }
+        4 => ([v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]),
+        5 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let l = v0 + v1;
+            ([v0, v0, v0, v2], [l, l, l, v2 + v3])
+        }
+        6 => (
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                255,
+            ],
+            [v[0], v[1], v[2], 255],
+        ),
+        8 | 12 => {
+            let (a0, a1) = if mode == 12 { (v[6], v[7]) } else { (255, 255) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                ([v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1])
+            } else {
+                (
+                    blue_contract(v[1], v[3], v[5], a1),
+                    blue_contract(v[0], v[2], v[4], a0),
+                )
+            }
+        }
+        9 | 13 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let (v5, v4) = bit_transfer_signed(v[5], v[4]);
+            let (v7, v6) = if mode == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            if v1 + v3 + v5 >= 0 {
+                ([v0, v2, v4, v6], [v0 + v1, v2 + v3, v4 + v5, v6 + v7])
+            } else {
+                (
+                    blue_contract(v0 + v1, v2 + v3, v4 + v5, v6 + v7),
+                    blue_contract(v0, v2, v4, v6),
+                )
+            }
+        }
+        10 => (
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                v[4],
+            ],
+            [v[0], v[1], v[2], v[5]],
+        ),
+        _ => return None,
+    };
+
+    let clamp = |e: [i32; 4]| e.map(|c| c.clamp(0, 255) as u8);
+    Some([clamp(e0), clamp(e1)])
+}
+
+/// Decodes one block of `block_width` × `block_height` texels. Writes the error color for
+/// malformed blocks and blocks using HDR features.
+pub(super) fn decode(data: &[u8], block_width: usize, block_height: usize, out: &mut [[u8; 4]]) {
+    let block = u128::from_le_bytes(data[..16].try_into().unwrap());
+    if !decode_block(block, block_width, block_height, out) {
+        out.fill(ERROR_COLOR);
+    }
+}
+
+fn decode_block(block: u128, block_width: usize, block_height: usize, out: &mut [[u8; 4]]) -> bool {
+    if bits(block, 0, 9) == 0x1fc {
+        return void_extent(block, out);
+    }
+
+    let Some(mode) = block_mode(block) else {
+        return false;
+    };
+    let planes = if mode.dual_plane { 2 } else { 1 };
+    let weight_count = mode.grid_width * mode.grid_height * planes;
+    let weight_bits = mode.weight_range.sequence_bits(weight_count as u32);
+    if weight_count > 64
+        || !(24..=96).contains(&weight_bits)
+        || mode.grid_width > block_width
+        || mode.grid_height > block_height
+    {
+        return false;
+    }
+
+    let partitions = bits(block, 11, 2) as usize + 1;
+    if mode.dual_plane && partitions == 4 {
+        return false;
+    }
+
+    // color endpoint modes per partition, and where the endpoint values end
+    let mut cems = [0; 4];
+    let mut end = 128 - weight_bits;
+    let color_start;
+    if partitions == 1 {
+        cems[0] = bits(block, 13, 4);
+        color_start = 17;
+    } else {
+        color_start = 29;
+        let selector = bits(block, 23, 2);
+        if selector == 0 {
+            cems[..partitions].fill(bits(block, 25, 4));
+        } else {
+            let extra = 3 * partitions as u32 - 4;
+            end -= extra;
+            let value = bits(block, 23, 6) | bits(block, end, extra) << 6;
+            let base = selector - 1;
+            for (i, cem) in cems[..partitions].iter_mut().enumerate() {
+                let c = value >> (2 + i) & 1;
+                let m = value >> (2 + partitions + 2 * i) & 3;
+                *cem = (base + c) << 2 | m;
+            }
+        }
+    }
+    let plane_component = if mode.dual_plane {
+        end -= 2;
+        Some(bits(block, end, 2) as usize)
+    } else {
+        None
+    };
+
+    let value_count: usize = cems[..partitions]
+        .iter()
+        .map(|&cem| 2 * ((cem >> 2) as usize + 1))
+        .sum();
+    if value_count > 18 || end < color_start {
+        return false;
+    }
+    let color_bits = end - color_start;
+    let Some(color_range) = RANGES
+        .iter()
+        .rev()
+        .find(|range| range.sequence_bits(value_count as u32) <= color_bits)
+    else {
+        return false;
+    };
+    if color_range.levels < 6 {
+        return false;
+    }
+
+    let values = decode_sequence(block, color_start, value_count, *color_range);
+    let values: Vec<u32> = values
+        .into_iter()
+        .map(|v| unquantize_color(v, *color_range))
+        .collect();
+    let mut endpoint_pairs = [[[0; 4]; 2]; 4];
+    let mut offset = 0;
+    for (pair, &cem) in endpoint_pairs.iter_mut().zip(&cems[..partitions]) {
+        let count = 2 * ((cem >> 2) as usize + 1);
+        let Some(e) = endpoints(cem, &values[offset..offset + count]) else {
+            return false;
+        };
+        *pair = e;
+        offset += count;
+    }
+
+    // weights are stored bit reversed from the top of the block
+    let weights = decode_sequence(block.reverse_bits(), 0, weight_count, mode.weight_range);
+    let weights: Vec<u32> = weights
+        .into_iter()
+        .map(|w| unquantize_weight(w, mode.weight_range))
+        .collect();
+
+    let seed = bits(block, 13, 10);
+    let small_block = block_width * block_height < 31;
+    let ds = (1024 + block_width / 2) / (block_width - 1);
+    let dt = (1024 + block_height / 2) / (block_height - 1);
+
+    for t in 0..block_height {
+        for s in 0..block_width {
+            let gs = (ds * s * (mode.grid_width - 1) + 32) >> 6;
+            let gt = (dt * t * (mode.grid_height - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, gs & 15);
+            let (jt, ft) = (gt >> 4, gt & 15);
+            let w11 = (fs * ft + 8) >> 4;
+            let w10 = ft - w11;
+            let w01 = fs - w11;
+            let w00 = 16 - fs - ft + w11;
+
+            let weight = |plane: usize| -> u32 {
+                let at = |x: usize, y: usize| -> u32 {
+                    if x < mode.grid_width && y < mode.grid_height {
+                        weights[(y * mode.grid_width + x) * planes + plane]
+                    } else {
+                        0
+                    }
+                };
+                (at(js, jt) * w00 as u32
+                    + at(js + 1, jt) * w01 as u32
+                    + at(js, jt + 1) * w10 as u32
+                    + at(js + 1, jt + 1) * w11 as u32
+                    + 8)
+                    >> 4
+            };
+            let w0 = weight(0);
+            let w1 = if mode.dual_plane { weight(1) } else { w0 };
+
+            let partition = if partitions > 1 {
+                select_partition(seed, s as u32, t as u32, partitions as u32, small_block)
+            } else {
+                0
+            };
+            let [e0, e1] = endpoint_pairs[partition];
+
+            let texel = &mut out[t * block_width + s];
+            for c in 0..4 {
+                let w = if plane_component == Some(c) { w1 } else { w0 };
+                let c0 = (e0[c] as u32) << 8 | e0[c] as u32;
+                let c1 = (e1[c] as u32) << 8 | e1[c] as u32;
+                let value = (c0 * (64 - w) + c1 * w + 32) / 64;
+                texel[c] = (value >> 8) as u8;
+            }
+        }
+    }
+
+    true
+}
+
+/// Blocks of a single color, the extent coordinates are only an optimization hint.
+fn void_extent(block: u128, out: &mut [[u8; 4]]) -> bool {
+    // HDR void extent
+    if bits(block, 9, 1) == 1 {
+        return false;
+    }
+
+    let s = (bits(block, 12, 13), bits(block, 25, 13));
+    let t = (bits(block, 38, 13), bits(block, 51, 13));
+    let all_ones = s.0 == 0x1fff && s.1 == 0x1fff && t.0 == 0x1fff && t.1 == 0x1fff;
+    if !all_ones && (s.0 >= s.1 || t.0 >= t.1) {
+        return false;
+    }
+
+    let color = [0, 1, 2, 3].map(|i| (bits(block, 64 + 16 * i, 16) >> 8) as u8);
+    out.fill(color);
+    true
+}
@@ -0,0 +1,579 @@
+//! BC1–BC7 block decoders, each block covers 4×4 texels in row-major order.
+
+use half::f16;
+
+/// Decodes the 8 byte color part shared by BC1, BC2 and BC3. `punch_through` enables the
+/// three color mode with a transparent black texel when the first endpoint is not greater.
+fn color_block(block: &[u8], punch_through: bool, out: &mut [[u8; 4]]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let e0 = rgb565(c0);
+    let e1 = rgb565(c1);
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+
+    let mut palette = [[0; 4]; 4];
+    palette[0] = [e0[0], e0[1], e0[2], 255];
+    palette[1] = [e1[0], e1[1], e1[2], 255];
+    if c0 > c1 || !punch_through {
+        for i in 0..3 {
+            palette[2][i] = mix(e0[i], e1[i], 2, 1);
+            palette[3][i] = mix(e0[i], e1[i], 1, 2);
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for i in 0..3 {
+            palette[2][i] = mix(e0[i], e1[i], 1, 1);
+        }
+        palette[2][3] = 255;
+        palette[3] = [0; 4];
+    }
+
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = palette[(indices >> (2 * i) & 3) as usize];
+    }
+}
+
+fn rgb565(c: u16) -> [u8; 3] {
+    let r = (c >> 11 & 31) as u8;
+    let g = (c >> 5 & 63) as u8;
+    let b = (c & 31) as u8;
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2]
+}
+
+/// Eight interpolated values from two endpoints, the BC3 alpha and BC4 channel block.
+/// Returns values in `[0, 1]`, or `[-1, 1]` if `signed`.
+fn channel_block(block: &[u8], signed: bool) -> [f32; 16] {
+    let (e0, e1) = if signed {
+        let e = |v: u8| (v as i8).max(-127) as f32 / 127.;
+        (e(block[0]), e(block[1]))
+    } else {
+        (block[0] as f32 / 255., block[1] as f32 / 255.)
+    };
+    let greater = if signed {
+        block[0] as i8 > block[1] as i8
+    } else {
+        block[0] > block[1]
+    };
+
+    let mut palette = [0.; 8];
+    palette[0] = e0;
+    palette[1] = e1;
+    if greater {
+        for i in 1..7 {
+            palette[i + 1] = (e0 * (7 - i) as f32 + e1 * i as f32) / 7.;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (e0 * (5 - i) as f32 + e1 * i as f32) / 5.;
+        }
+        palette[6] = if signed { -1. } else { 0. };
+        palette[7] = 1.;
+    }
+
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+
+    let mut out = [0.; 16];
+    for (i, value) in out.iter_mut().enumerate() {
+        *value = palette[(indices >> (3 * i) & 7) as usize];
+    }
+    out
+}
+
+fn unorm8(v: f32) -> u8 {
+    (v * 255. + 0.5) as u8
+}
+
+pub(super) fn bc1(block: &[u8], out: &mut [[u8; 4]]) {
+    color_block(block, true, out);
+}
+
+pub(super) fn bc2(block: &[u8], out: &mut [[u8; 4]]) {
+    color_block(&block[8..], false, out);
+
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    for (i, texel) in out.iter_mut().enumerate() {
+        texel[3] = (alpha >> (4 * i) & 15) as u8 * 17;
+    }
+}
+
+pub(super) fn bc3(block: &[u8], out: &mut [[u8; 4]]) {
+    color_block(&block[8..], false, out);
+
+    for (texel, alpha) in out.iter_mut().zip(channel_block(block, false)) {
+        texel[3] = unorm8(alpha);
+    }
+}
+
+pub(super) fn bc4(block: &[u8], out: &mut [[u8; 4]]) {
+    for (texel, r) in out.iter_mut().zip(channel_block(block, false)) {
+        *texel = [unorm8(r), 0, 0, 255];
+    }
+}
+
+pub(super) fn bc4_snorm(block: &[u8], out: &mut [[f32; 4]]) {
+    for (texel, r) in out.iter_mut().zip(channel_block(block, true)) {
+        *texel = [r, 0., 0., 1.];
+    }
+}
+
+pub(super) fn bc5(block: &[u8], out: &mut [[u8; 4]]) {
+    let r = channel_block(block, false);
+    let g = channel_block(&block[8..], false);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = [unorm8(r[i]), unorm8(g[i]), 0, 255];
+    }
+}
+
+pub(super) fn bc5_snorm(block: &[u8], out: &mut [[f32; 4]]) {
+    let r = channel_block(block, true);
+    let g = channel_block(&block[8..], true);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = [r[i], g[i], 0., 1.];
+    }
+}
+
+struct Bits(u128, u32);
+
+impl Bits {
+    fn new(block: &[u8]) -> Self {
+        Self(u128::from_le_bytes(block[..16].try_into().unwrap()), 0)
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.0 >> self.1) as u32 & ((1u64 << count) - 1) as u32;
+        self.1 += count;
+        value
+    }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+#[rustfmt::skip]
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+/// Subset of each texel for the two subset partitions, one bit per texel.
+#[rustfmt::skip]
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Subset of each texel for the three subset partitions, two bits per texel.
+#[rustfmt::skip]
+const PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// Texel index of the second subset's anchor in the two subset partitions.
+#[rustfmt::skip]
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Texel indices of the second and third subset's anchors in the three subset partitions.
+#[rustfmt::skip]
+const ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn bc7_interpolate(e0: u8, e1: u8, index: u32, index_bits: u32) -> u8 {
+    let weight = match index_bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    };
+    (((64 - weight) * e0 as u32 + weight * e1 as u32 + 32) >> 6) as u8
+}
+
+pub(super) fn bc7(block: &[u8], out: &mut [[u8; 4]]) {
+    let Some(mode_index) = (0..8).find(|&i| block[0] >> i & 1 == 1) else {
+        // reserved mode
+        out.fill([0; 4]);
+        return;
+    };
+    let mode = &BC7_MODES[mode_index];
+
+    let mut bits = Bits::new(block);
+    bits.read(mode_index as u32 + 1);
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    // endpoints[subset * 2 + end][channel]
+    let mut endpoints = [[0u8; 4]; 6];
+    let endpoint_count = mode.subsets * 2;
+    for channel in 0..3 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(mode.color_bits) as u8;
+        }
+    }
+    for endpoint in &mut endpoints[..endpoint_count] {
+        endpoint[3] = bits.read(mode.alpha_bits) as u8;
+    }
+
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let p_bits: Vec<u32> = if mode.endpoint_p_bits {
+            (0..endpoint_count).map(|_| bits.read(1)).collect()
+        } else {
+            (0..mode.subsets)
+                .flat_map(|_| {
+                    let p = bits.read(1);
+                    [p, p]
+                })
+                .collect()
+        };
+        for (endpoint, p) in endpoints.iter_mut().zip(p_bits) {
+            for (i, value) in endpoint.iter_mut().enumerate() {
+                if i < 3 || mode.alpha_bits > 0 {
+                    *value = *value << 1 | p as u8;
+                }
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    let expand = |v: u8, n: u32| -> u8 {
+        if n == 0 {
+            255
+        } else {
+            let v = (v as u32) << (8 - n);
+            (v | v >> n) as u8
+        }
+    };
+    for endpoint in &mut endpoints[..endpoint_count] {
+        for (i, value) in endpoint.iter_mut().enumerate() {
+            *value = expand(*value, if i < 3 { color_bits } else { alpha_bits });
+        }
+    }
+
+    let subset_of = |texel: usize| -> usize {
+        match mode.subsets {
+            1 => 0,
+            2 => (PARTITIONS_2[partition] >> texel & 1) as usize,
+            _ => (PARTITIONS_3[partition] >> (2 * texel) & 3) as usize,
+        }
+    };
+    let is_anchor = |texel: usize| -> bool {
+        texel == 0
+            || match mode.subsets {
+                2 => texel == ANCHORS_2[partition] as usize,
+                3 => ANCHORS_3[partition].contains(&(texel as u8)),
+                _ => false,
+            }
+    };
+
+    let mut indices = [0; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        *index = bits.read(mode.index_bits - is_anchor(texel) as u32);
+    }
+    let mut secondary = [0; 16];
+    if mode.secondary_index_bits > 0 {
+        for (texel, index) in secondary.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - (texel == 0) as u32);
+        }
+    }
+
+    for (texel, out) in out.iter_mut().enumerate() {
+        let subset = subset_of(texel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let mut color = [0; 4];
+        if mode.secondary_index_bits > 0 {
+            let (color_index, color_index_bits, alpha_index, alpha_index_bits) =
+                if index_selection == 0 {
+                    (
+                        indices[texel],
+                        mode.index_bits,
+                        secondary[texel],
+                        mode.secondary_index_bits,
+                    )
+                } else {
+                    (
+                        secondary[texel],
+                        mode.secondary_index_bits,
+                        indices[texel],
+                        mode.index_bits,
+                    )
+                };
+            for i in 0..3 {
+                color[i] = bc7_interpolate(e0[i], e1[i], color_index, color_index_bits);
+            }
+            color[3] = bc7_interpolate(e0[3], e1[3], alpha_index, alpha_index_bits);
+        } else {
+            for i in 0..4 {
+                color[i] = bc7_interpolate(e0[i], e1[i], indices[texel], mode.index_bits);
+            }
+        }
+
+        match rotation {
+            1 => color.swap(0, 3),
+            2 => color.swap(1, 3),
+            3 => color.swap(2, 3),
+            _ => {}
+        }
+        *out = color;
+    }
+}
+
+// BC6H endpoint components, `W` and `X` are the endpoints of the first region, `Y` and `Z`
+// those of the second
+const RW: usize = 0;
+const GW: usize = 1;
+const BW: usize = 2;
+const RX: usize = 3;
+const GX: usize = 4;
+const BX: usize = 5;
+const RY: usize = 6;
+const GY: usize = 7;
+const BY: usize = 8;
+const RZ: usize = 9;
+const GZ: usize = 10;
+const BZ: usize = 11;
+const PARTITION: usize = 12;
+
+/// Layout of a BC6H mode: the fields following the mode bits, in order, as the component
+/// they belong to, their lowest bit in it and their bit count.
+struct Bc6hMode {
+    mode: u32,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    fields: &'static [(usize, u32, u32)],
+}
+
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode { mode: 0b00, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], fields: &[
+        (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b01, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], fields: &[
+        (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1),
+        (GW, 0, 7), (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b00010, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4),
+        (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b00110, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4),
+        (GX, 0, 5), (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 0, 1), (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b01010, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4),
+        (GX, 0, 4), (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 1, 1), (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b01110, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], fields: &[
+        (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b10010, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], fields: &[
+        (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 3, 1), (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b10110, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], fields: &[
+        (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (GZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b11010, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], fields: &[
+        (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1),
+        (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b11110, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], fields: &[
+        (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1),
+        (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (PARTITION, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b00011, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10),
+    ] },
+    Bc6hMode { mode: 0b00111, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1),
+        (BX, 0, 9), (BW, 10, 1),
+    ] },
+    Bc6hMode { mode: 0b01011, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8),
+        (GW, 11, 1), (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1),
+    ] },
+    Bc6hMode { mode: 0b01111, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1),
+        (RW, 12, 1), (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1),
+        (GW, 12, 1), (GW, 11, 1), (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1),
+        (BW, 12, 1), (BW, 11, 1), (BW, 10, 1),
+    ] },
+];
+
+fn sign_extend(v: i32, bits: u32) -> i32 {
+    v << (32 - bits) >> (32 - bits)
+}
+
+/// Scales an endpoint component of `bits` to the full 16 bit range, or 15 bits and sign
+/// if `signed`.
+fn bc6h_unquantize(v: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 || v == 0 {
+            v
+        } else if v == (1 << bits) - 1 {
+            0xffff
+        } else {
+            ((v << 16) + 0x8000) >> bits
+        }
+    } else if bits >= 16 || v == 0 {
+        v
+    } else {
+        let magnitude = v.abs();
+        let unquantized = if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7fff
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        unquantized * v.signum()
+    }
+}
+
+/// Decodes a BC6H block to half precision RGB, in `[0, 65504]` or `[-65504, 65504]` if
+/// `signed`. Reserved modes decode to black.
+fn bc6h(block: &[u8], signed: bool, out: &mut [[f32; 4]]) {
+    let mut bits = Bits::new(block);
+    let mut mode = bits.read(2);
+    if mode > 1 {
+        mode |= bits.read(3) << 2;
+    }
+    let Some(mode) = BC6H_MODES.iter().find(|m| m.mode == mode) else {
+        out.fill([0., 0., 0., 1.]);
+        return;
+    };
+
+    let mut values = [0; 13];
+    for &(field, shift, count) in mode.fields {
+        values[field] |= (bits.read(count) << shift) as i32;
+    }
+    let partition = values[PARTITION] as usize;
+    // the one region modes are the ones ending in 0b11
+    let regions = if mode.mode & 3 == 3 { 1 } else { 2 };
+
+    // endpoints[region * 2 + end][channel]
+    let mut endpoints = [[0; 3]; 4];
+    let endpoint_bits = mode.endpoint_bits;
+    for (channel, &delta_bits) in mode.delta_bits.iter().enumerate() {
+        let base = values[channel];
+        for (i, endpoint) in endpoints[..regions * 2].iter_mut().enumerate() {
+            let mut v = values[i * 3 + channel];
+            if i > 0 && mode.transformed {
+                v = (base + sign_extend(v, delta_bits)) & ((1 << endpoint_bits) - 1);
+            }
+            if signed {
+                v = sign_extend(v, endpoint_bits);
+            }
+            endpoint[channel] = bc6h_unquantize(v, endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if regions == 2 { 3 } else { 4 };
+    for (texel, out) in out.iter_mut().enumerate() {
+        let anchor = texel == 0 || regions == 2 && texel == ANCHORS_2[partition] as usize;
+        let index = bits.read(index_bits - anchor as u32) as usize;
+        let weight = if regions == 2 {
+            WEIGHTS_3[index]
+        } else {
+            WEIGHTS_4[index]
+        } as i32;
+
+        let region = if regions == 2 {
+            (PARTITIONS_2[partition] >> texel & 1) as usize
+        } else {
+            0
+        };
+        let (e0, e1) = (endpoints[region * 2], endpoints[region * 2 + 1]);
+        let channel = |i: usize| {
+            let v = ((64 - weight) * e0[i] + weight * e1[i] + 32) >> 6;
+            let half = if !signed {
+                (v * 31) >> 6
+            } else if v < 0 {
+                0x8000 | (-v * 31) >> 5
+            } else {
+                (v * 31) >> 5
+            };
+            f16::from_bits(half as u16).to_f32()
+        };
+        *out = [channel(0), channel(1), channel(2), 1.];
+    }
+}
+
+pub(super) fn bc6h_ufloat(block: &[u8], out: &mut [[f32; 4]]) {
+    bc6h(block, false, out);
+}
+
+pub(super) fn bc6h_sfloat(block: &[u8], out: &mut [[f32; 4]]) {
+    bc6h(block, true, out);
+}
@@ -0,0 +1,178 @@
+use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+use super::{astc, bc, etc, ColorSpace, Texels};
+
+/// Maps a KTX2 format to the matching texture format and color space.
+pub(super) fn wgpu_format(format: ktx2::Format) -> Option<(TextureFormat, ColorSpace)> {
+    use ColorSpace::{Linear, Srgb};
+
+    let astc = |block, srgb| {
+        let channel = if srgb {
+            AstcChannel::UnormSrgb
+        } else {
+            AstcChannel::Unorm
+        };
+        let color_space = if srgb { Srgb } else { Linear };
+        Some((TextureFormat::Astc { block, channel }, color_space))
+    };
+
+    Some(match format {
+        ktx2::Format::R8G8B8A8_UNORM => (TextureFormat::Rgba8Unorm, Linear),
+        ktx2::Format::R8G8B8A8_SRGB => (TextureFormat::Rgba8UnormSrgb, Srgb),
+        ktx2::Format::R16G16B16A16_SFLOAT => (TextureFormat::Rgba16Float, Linear),
+        ktx2::Format::BC1_RGB_UNORM_BLOCK | ktx2::Format::BC1_RGBA_UNORM_BLOCK => {
+            (TextureFormat::Bc1RgbaUnorm, Linear)
+        }
+        ktx2::Format::BC1_RGB_SRGB_BLOCK | ktx2::Format::BC1_RGBA_SRGB_BLOCK => {
+            (TextureFormat::Bc1RgbaUnormSrgb, Srgb)
+        }
+        ktx2::Format::BC2_UNORM_BLOCK => (TextureFormat::Bc2RgbaUnorm, Linear),
+        ktx2::Format::BC2_SRGB_BLOCK => (TextureFormat::Bc2RgbaUnormSrgb, Srgb),
+        ktx2::Format::BC3_UNORM_BLOCK => (TextureFormat::Bc3RgbaUnorm, Linear),
+        ktx2::Format::BC3_SRGB_BLOCK => (TextureFormat::Bc3RgbaUnormSrgb, Srgb),
+        ktx2::Format::BC4_UNORM_BLOCK => (TextureFormat::Bc4RUnorm, Linear),
+        ktx2::Format::BC4_SNORM_BLOCK => (TextureFormat::Bc4RSnorm, Linear),
+        ktx2::Format::BC5_UNORM_BLOCK => (TextureFormat::Bc5RgUnorm, Linear),
+        ktx2::Format::BC5_SNORM_BLOCK => (TextureFormat::Bc5RgSnorm, Linear),
+        ktx2::Format::BC6H_UFLOAT_BLOCK => (TextureFormat::Bc6hRgbUfloat, Linear),
+        ktx2::Format::BC6H_SFLOAT_BLOCK => (TextureFormat::Bc6hRgbSfloat, Linear),
+        ktx2::Format::BC7_UNORM_BLOCK => (TextureFormat::Bc7RgbaUnorm, Linear),
+        ktx2::Format::BC7_SRGB_BLOCK => (TextureFormat::Bc7RgbaUnormSrgb, Srgb),
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => (TextureFormat::Etc2Rgb8Unorm, Linear),
+        ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK => (TextureFormat::Etc2Rgb8UnormSrgb, Srgb),
+        ktx2::Format::ETC2_R8G8B8A1_UNORM_BLOCK => (TextureFormat::Etc2Rgb8A1Unorm, Linear),
+        ktx2::Format::ETC2_R8G8B8A1_SRGB_BLOCK => (TextureFormat::Etc2Rgb8A1UnormSrgb, Srgb),
+        ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK => (TextureFormat::Etc2Rgba8Unorm, Linear),
+        ktx2::Format::ETC2_R8G8B8A8_SRGB_BLOCK => (TextureFormat::Etc2Rgba8UnormSrgb, Srgb),
+        ktx2::Format::EAC_R11_UNORM_BLOCK => (TextureFormat::EacR11Unorm, Linear),
+        ktx2::Format::EAC_R11_SNORM_BLOCK => (TextureFormat::EacR11Snorm, Linear),
+        ktx2::Format::EAC_R11G11_UNORM_BLOCK => (TextureFormat::EacRg11Unorm, Linear),
+        ktx2::Format::EAC_R11G11_SNORM_BLOCK => (TextureFormat::EacRg11Snorm, Linear),
+        ktx2::Format::ASTC_4x4_UNORM_BLOCK => return astc(AstcBlock::B4x4, false),
+        ktx2::Format::ASTC_4x4_SRGB_BLOCK => return astc(AstcBlock::B4x4, true),
+        ktx2::Format::ASTC_5x4_UNORM_BLOCK => return astc(AstcBlock::B5x4, false),
+        ktx2::Format::ASTC_5x4_SRGB_BLOCK => return astc(AstcBlock::B5x4, true),
+        ktx2::Format::ASTC_5x5_UNORM_BLOCK => return astc(AstcBlock::B5x5, false),
+        ktx2::Format::ASTC_5x5_SRGB_BLOCK => return astc(AstcBlock::B5x5, true),
+        ktx2::Format::ASTC_6x5_UNORM_BLOCK => return astc(AstcBlock::B6x5, false),
+        ktx2::Format::ASTC_6x5_SRGB_BLOCK => return astc(AstcBlock::B6x5, true),
+        ktx2::Format::ASTC_6x6_UNORM_BLOCK => return astc(AstcBlock::B6x6, false),
+        ktx2::Format::ASTC_6x6_SRGB_BLOCK => return astc(AstcBlock::B6x6, true),
+        ktx2::Format::ASTC_8x5_UNORM_BLOCK => return astc(AstcBlock::B8x5, false),
+        ktx2::Format::ASTC_8x5_SRGB_BLOCK => return astc(AstcBlock::B8x5, true),
+        ktx2::Format::ASTC_8x6_UNORM_BLOCK => return astc(AstcBlock::B8x6, false),
+        ktx2::Format::ASTC_8x6_SRGB_BLOCK => return astc(AstcBlock::B8x6, true),
+        ktx2::Format::ASTC_8x8_UNORM_BLOCK => return astc(AstcBlock::B8x8, false),
+        ktx2::Format::ASTC_8x8_SRGB_BLOCK => return astc(AstcBlock::B8x8, true),
+        ktx2::Format::ASTC_10x5_UNORM_BLOCK => return astc(AstcBlock::B10x5, false),
+        ktx2::Format::ASTC_10x5_SRGB_BLOCK => return astc(AstcBlock::B10x5, true),
+        ktx2::Format::ASTC_10x6_UNORM_BLOCK => return astc(AstcBlock::B10x6, false),
+        ktx2::Format::ASTC_10x6_SRGB_BLOCK => return astc(AstcBlock::B10x6, true),
+        ktx2::Format::ASTC_10x8_UNORM_BLOCK => return astc(AstcBlock::B10x8, false),
+        ktx2::Format::ASTC_10x8_SRGB_BLOCK => return astc(AstcBlock::B10x8, true),
+        ktx2::Format::ASTC_10x10_UNORM_BLOCK => return astc(AstcBlock::B10x10, false),
+        ktx2::Format::ASTC_10x10_SRGB_BLOCK => return astc(AstcBlock::B10x10, true),
+        ktx2::Format::ASTC_12x10_UNORM_BLOCK => return astc(AstcBlock::B12x10, false),
+        ktx2::Format::ASTC_12x10_SRGB_BLOCK => return astc(AstcBlock::B12x10, true),
+        ktx2::Format::ASTC_12x12_UNORM_BLOCK => return astc(AstcBlock::B12x12, false),
+        ktx2::Format::ASTC_12x12_SRGB_BLOCK => return astc(AstcBlock::B12x12, true),
+        _ => return None,
+    })
+}
+
+/// Decodes one level of a block compressed `format` on the CPU, for devices without the
+/// matching compression feature. Every format of [`wgpu_format`] has a decoder.
+pub(super) fn decode(format: TextureFormat, width: u32, height: u32, data: &[u8]) -> Texels {
+    type Unorm = fn(&[u8], &mut [[u8; 4]]);
+    type Float = fn(&[u8], &mut [[f32; 4]]);
+
+    let unorm: Option<Unorm> = match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => Some(bc::bc1),
+        TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => Some(bc::bc2),
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => Some(bc::bc3),
+        TextureFormat::Bc4RUnorm => Some(bc::bc4),
+        TextureFormat::Bc5RgUnorm => Some(bc::bc5),
+        TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => Some(bc::bc7),
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8UnormSrgb => Some(etc::etc2_rgb),
+        TextureFormat::Etc2Rgb8A1Unorm | TextureFormat::Etc2Rgb8A1UnormSrgb => {
+            Some(etc::etc2_rgb_a1)
+        }
+        TextureFormat::Etc2Rgba8Unorm | TextureFormat::Etc2Rgba8UnormSrgb => Some(etc::etc2_rgba),
+        _ => None,
+    };
+    let float: Option<Float> = match format {
+        TextureFormat::Bc4RSnorm => Some(bc::bc4_snorm),
+        TextureFormat::Bc5RgSnorm => Some(bc::bc5_snorm),
+        TextureFormat::Bc6hRgbUfloat => Some(bc::bc6h_ufloat),
+        TextureFormat::Bc6hRgbSfloat => Some(bc::bc6h_sfloat),
+        TextureFormat::EacR11Unorm => Some(etc::eac_r11),
+        TextureFormat::EacR11Snorm => Some(etc::eac_r11_snorm),
+        TextureFormat::EacRg11Unorm => Some(etc::eac_rg11),
+        TextureFormat::EacRg11Snorm => Some(etc::eac_rg11_snorm),
+        _ => None,
+    };
+
+    let info = format.describe();
+    let block = (
+        info.block_dimensions.0 as usize,
+        info.block_dimensions.1 as usize,
+        info.block_size as usize,
+    );
+
+    if let TextureFormat::Astc {
+        block: _,
+        channel: AstcChannel::Unorm | AstcChannel::UnormSrgb,
+    } = format
+    {
+        let (block_width, block_height, _) = block;
+        let texels = decode_blocks(width, height, block, data, |data, out| {
+            astc::decode(data, block_width, block_height, out)
+        });
+        Texels::Rgba8(texels.into_iter().flatten().collect())
+    } else if let Some(decode_block) = unorm {
+        let texels = decode_blocks(width, height, block, data, decode_block);
+        Texels::Rgba8(texels.into_iter().flatten().collect())
+    } else if let Some(decode_block) = float {
+        let texels = decode_blocks(width, height, block, data, decode_block);
+        Texels::Rgba32Float(texels.into_iter().flatten().collect())
+    } else {
+        unreachable!("{format:?} is not loaded from KTX2")
+    }
+}
+
+/// Runs `decode_block` over the blocks of `data` in row-major order, cropping the texels
+/// that fall outside the image. `data` holds at least the blocks covering the image, as
+/// checked when loading.
+fn decode_blocks<T, F>(
+    width: u32,
+    height: u32,
+    (block_width, block_height, block_size): (usize, usize, usize),
+    data: &[u8],
+    mut decode_block: F,
+) -> Vec<[T; 4]>
+where
+    T: Copy + Default,
+    F: FnMut(&[u8], &mut [[T; 4]]),
+{
+    let (width, height) = (width as usize, height as usize);
+    let blocks_x = width.div_ceil(block_width);
+    let blocks_y = height.div_ceil(block_height);
+
+    let mut texels = vec![[T::default(); 4]; width * height];
+    let mut block_texels = vec![[T::default(); 4]; block_width * block_height];
+    for (i, block) in data
+        .chunks_exact(block_size)
+        .take(blocks_x * blocks_y)
+        .enumerate()
+    {
+        decode_block(block, &mut block_texels);
+
+        let (bx, by) = (i % blocks_x * block_width, i / blocks_x * block_height);
+        for y in 0..block_height.min(height - by) {
+            for x in 0..block_width.min(width - bx) {
+                texels[(by + y) * width + bx + x] = block_texels[y * block_width + x];
+            }
+        }
+    }
+    texels
+}
@@ -0,0 +1,295 @@
+//! ETC2 and EAC block decoders, each block covers 4×4 texels in row-major order.
+//! Blocks are big endian and index their texels column by column.
+
+const MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+const DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+#[rustfmt::skip]
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn bits(block: u64, high: u32, low: u32) -> i32 {
+    (block >> low & ((1 << (high - low + 1)) - 1)) as i32
+}
+
+fn extend4(v: i32) -> i32 {
+    v << 4 | v
+}
+
+fn extend5(v: i32) -> i32 {
+    v << 3 | v >> 2
+}
+
+fn extend6(v: i32) -> i32 {
+    v << 2 | v >> 4
+}
+
+fn extend7(v: i32) -> i32 {
+    v << 1 | v >> 6
+}
+
+fn clamp8(v: i32) -> u8 {
+    v.clamp(0, 255) as u8
+}
+
+fn offset(color: [i32; 3], d: i32) -> [u8; 4] {
+    [
+        clamp8(color[0] + d),
+        clamp8(color[1] + d),
+        clamp8(color[2] + d),
+        255,
+    ]
+}
+
+/// Two bit index of the texel at `x`, `y`.
+fn texel_index(block: u64, x: usize, y: usize) -> usize {
+    let i = x * 4 + y;
+    ((block >> (16 + i) & 1) << 1 | block >> i & 1) as usize
+}
+
+/// Decodes an ETC2 RGB block. With `punch_through` the differential bit is instead an opaque
+/// flag, and index 2 selects transparent black in blocks that aren't opaque.
+fn rgb_block(block: &[u8], punch_through: bool, out: &mut [[u8; 4]]) {
+    let block = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let flag = block >> 33 & 1 == 1;
+    let (differential, opaque) = if punch_through {
+        (true, flag)
+    } else {
+        (flag, true)
+    };
+
+    if !differential {
+        let base = |shift: u32| {
+            [
+                extend4(bits(block, 63 - shift, 60 - shift)),
+                extend4(bits(block, 55 - shift, 52 - shift)),
+                extend4(bits(block, 47 - shift, 44 - shift)),
+            ]
+        };
+        individual_or_differential(block, [base(0), base(4)], true, out);
+        return;
+    }
+
+    let r = bits(block, 63, 59);
+    let g = bits(block, 55, 51);
+    let b = bits(block, 47, 43);
+    let signed = |v: i32| (v << 29) >> 29;
+    let r2 = r + signed(bits(block, 58, 56));
+    let g2 = g + signed(bits(block, 50, 48));
+    let b2 = b + signed(bits(block, 42, 40));
+
+    let mut palette = if !(0..32).contains(&r2) {
+        t_palette(block)
+    } else if !(0..32).contains(&g2) {
+        h_palette(block)
+    } else if !(0..32).contains(&b2) {
+        planar(block, out);
+        return;
+    } else {
+        let colors = [
+            [extend5(r), extend5(g), extend5(b)],
+            [extend5(r2), extend5(g2), extend5(b2)],
+        ];
+        individual_or_differential(block, colors, opaque, out);
+        return;
+    };
+
+    if !opaque {
+        palette[2] = [0; 4];
+    }
+    for y in 0..4 {
+        for x in 0..4 {
+            out[y * 4 + x] = palette[texel_index(block, x, y)];
+        }
+    }
+}
+
+fn individual_or_differential(
+    block: u64,
+    colors: [[i32; 3]; 2],
+    opaque: bool,
+    out: &mut [[u8; 4]],
+) {
+    let flip = block >> 32 & 1 == 1;
+    let tables = [bits(block, 39, 37) as usize, bits(block, 36, 34) as usize];
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let subblock = if flip { y >= 2 } else { x >= 2 } as usize;
+            let [small, large] = MODIFIERS[tables[subblock]];
+            let index = texel_index(block, x, y);
+            out[y * 4 + x] = match (index, opaque) {
+                (2, false) => [0; 4],
+                (0, false) => offset(colors[subblock], 0),
+                _ => offset(colors[subblock], [small, large, -small, -large][index]),
+            };
+        }
+    }
+}
+
+fn t_palette(block: u64) -> [[u8; 4]; 4] {
+    let c1 = [
+        extend4(bits(block, 60, 59) << 2 | bits(block, 57, 56)),
+        extend4(bits(block, 55, 52)),
+        extend4(bits(block, 51, 48)),
+    ];
+    let c2 = [
+        extend4(bits(block, 47, 44)),
+        extend4(bits(block, 43, 40)),
+        extend4(bits(block, 39, 36)),
+    ];
+    let d = DISTANCES[(bits(block, 35, 34) << 1 | bits(block, 32, 32)) as usize];
+
+    [offset(c1, 0), offset(c2, d), offset(c2, 0), offset(c2, -d)]
+}
+
+fn h_palette(block: u64) -> [[u8; 4]; 4] {
+    let c1 = [
+        bits(block, 62, 59),
+        bits(block, 58, 56) << 1 | bits(block, 52, 52),
+        bits(block, 51, 51) << 3 | bits(block, 49, 47),
+    ];
+    let c2 = [
+        bits(block, 46, 43),
+        bits(block, 42, 39),
+        bits(block, 38, 35),
+    ];
+    let value = |c: [i32; 3]| c[0] << 8 | c[1] << 4 | c[2];
+    let index =
+        bits(block, 34, 34) << 2 | bits(block, 32, 32) << 1 | (value(c1) >= value(c2)) as i32;
+    let d = DISTANCES[index as usize];
+
+    let c1 = c1.map(extend4);
+    let c2 = c2.map(extend4);
+    [offset(c1, d), offset(c1, -d), offset(c2, d), offset(c2, -d)]
+}
+
+fn planar(block: u64, out: &mut [[u8; 4]]) {
+    let o = [
+        extend6(bits(block, 62, 57)),
+        extend7(bits(block, 56, 56) << 6 | bits(block, 54, 49)),
+        extend6(bits(block, 48, 48) << 5 | bits(block, 44, 43) << 3 | bits(block, 41, 39)),
+    ];
+    let h = [
+        extend6(bits(block, 38, 34) << 1 | bits(block, 32, 32)),
+        extend7(bits(block, 31, 25)),
+        extend6(bits(block, 24, 19)),
+    ];
+    let v = [
+        extend6(bits(block, 18, 13)),
+        extend7(bits(block, 12, 6)),
+        extend6(bits(block, 5, 0)),
+    ];
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let c = |i: usize| {
+                clamp8((x as i32 * (h[i] - o[i]) + y as i32 * (v[i] - o[i]) + 4 * o[i] + 2) >> 2)
+            };
+            out[y * 4 + x] = [c(0), c(1), c(2), 255];
+        }
+    }
+}
+
+/// Decodes an EAC block to values in `[0, 1]`, or `[-1, 1]` if `signed`. `eleven_bit`
+/// selects the R11 and RG11 precision over the 8 bit alpha of ETC2 RGBA.
+fn eac_block(block: &[u8], eleven_bit: bool, signed: bool) -> [f32; 16] {
+    let block = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let base = bits(block, 63, 56);
+    let multiplier = bits(block, 55, 52);
+    let modifiers = EAC_MODIFIERS[bits(block, 51, 48) as usize];
+
+    let mut out = [0.; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let i = x * 4 + y;
+            let modifier = modifiers[(block >> (45 - 3 * i) & 7) as usize];
+            out[y * 4 + x] = match (eleven_bit, signed) {
+                (false, _) => (base + modifier * multiplier).clamp(0, 255) as f32 / 255.,
+                (true, false) => {
+                    let m = if multiplier == 0 { 1 } else { multiplier * 8 };
+                    (base * 8 + 4 + modifier * m).clamp(0, 2047) as f32 / 2047.
+                }
+                (true, true) => {
+                    let base = (base as u8 as i8).max(-127) as i32;
+                    let m = if multiplier == 0 { 1 } else { multiplier * 8 };
+                    (base * 8 + modifier * m).clamp(-1023, 1023) as f32 / 1023.
+                }
+            };
+        }
+    }
+    out
+}
+
+fn unorm8(v: f32) -> u8 {
+    (v * 255. + 0.5) as u8
+}
+
+pub(super) fn etc2_rgb(block: &[u8], out: &mut [[u8; 4]]) {
+    rgb_block(block, false, out);
+}
+
+pub(super) fn etc2_rgb_a1(block: &[u8], out: &mut [[u8; 4]]) {
+    rgb_block(block, true, out);
+}
+
+pub(super) fn etc2_rgba(block: &[u8], out: &mut [[u8; 4]]) {
+    rgb_block(&block[8..], false, out);
+    for (texel, alpha) in out.iter_mut().zip(eac_block(block, false, false)) {
+        texel[3] = unorm8(alpha);
+    }
+}
+
+pub(super) fn eac_r11(block: &[u8], out: &mut [[f32; 4]]) {
+    for (texel, r) in out.iter_mut().zip(eac_block(block, true, false)) {
+        *texel = [r, 0., 0., 1.];
+    }
+}
+
+pub(super) fn eac_r11_snorm(block: &[u8], out: &mut [[f32; 4]]) {
+    for (texel, r) in out.iter_mut().zip(eac_block(block, true, true)) {
+        *texel = [r, 0., 0., 1.];
+    }
+}
+
+pub(super) fn eac_rg11(block: &[u8], out: &mut [[f32; 4]]) {
+    let r = eac_block(block, true, false);
+    let g = eac_block(&block[8..], true, false);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = [r[i], g[i], 0., 1.];
+    }
+}
+
+pub(super) fn eac_rg11_snorm(block: &[u8], out: &mut [[f32; 4]]) {
+    let r = eac_block(block, true, true);
+    let g = eac_block(&block[8..], true, true);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = [r[i], g[i], 0., 1.];
+    }
+}
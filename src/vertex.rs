@@ -3,14 +3,27 @@
 pub struct Vertex {
     position: [f32; 3],
     normal: [f32; 3],
+    uv: [f32; 2],
 }
 
 impl Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+    const ATTRIBS: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
 
     pub fn new(position: [f32; 3], normal: [f32; 3]) -> Self {
-        Self { position, normal }
+        Self::with_uv(position, normal, [0., 0.])
+    }
+
+    pub fn with_uv(position: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> Self {
+        Self {
+            position,
+            normal,
+            uv,
+        }
+    }
+
+    pub(crate) fn position(&self) -> [f32; 3] {
+        self.position
     }
 
     pub(crate) fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+
+// Bind group layouts are cached process wide, so all tests share one device.
+static DEVICE: OnceCell<(Arc<wgpu::Device>, Arc<wgpu::Queue>)> = OnceCell::new();
+
+pub async fn init_device() -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+    if let Some(device) = DEVICE.get() {
+        return device.clone();
+    }
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = instance.request_adapter(&Default::default()).await.unwrap();
+
+    let (device, queue) = adapter
+        .request_device(&Default::default(), None)
+        .await
+        .unwrap();
+
+    DEVICE
+        .get_or_init(|| (Arc::new(device), Arc::new(queue)))
+        .clone()
+}
@@ -0,0 +1,53 @@
+use san::{
+    camera::{Camera, PerspectiveCamera, OPENGL_TO_WGPU_MATRIX},
+    cgmath::{assert_relative_eq, ortho, Matrix4, Point3, Transform, Vector3},
+};
+
+/// Implements only the projection, leaving the view and position to their defaults.
+struct ProjectionCamera(Matrix4<f32>);
+
+impl Camera for ProjectionCamera {
+    fn projection_matrix(&self) -> Matrix4<f32> {
+        self.0
+    }
+}
+
+#[test]
+fn test_camera_default_view() {
+    let camera = PerspectiveCamera {
+        eye: Point3::new(1., 2., 5.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.5,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    };
+    let projection = ProjectionCamera(camera.projection_matrix());
+
+    assert_relative_eq!(projection.position(), camera.eye, epsilon = 1e-3);
+    assert_relative_eq!(
+        projection.view_matrix(),
+        camera.view_matrix(),
+        epsilon = 1e-3
+    );
+}
+
+#[test]
+fn test_camera_default_view_orthographic() {
+    let view = Matrix4::look_at_rh(
+        Point3::new(0., 0., 5.),
+        Point3::new(0., 0., 0.),
+        Vector3::unit_y(),
+    );
+    let camera = ProjectionCamera(OPENGL_TO_WGPU_MATRIX * ortho(-2., 2., -1., 1., 1., 10.) * view);
+
+    // the center of the near plane
+    assert_relative_eq!(camera.position(), Point3::new(0., 0., 4.), epsilon = 1e-4);
+    let point = Point3::new(1., 1., -2.);
+    assert_relative_eq!(
+        camera.view_matrix().transform_point(point).z,
+        view.transform_point(point).z + 1.,
+        epsilon = 1e-4
+    );
+}
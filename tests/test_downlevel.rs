@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    geometry::Geometry,
+    light::{DirectionalLight, DirectionalShadow, PointLight, PointShadow},
+    material::LambertMaterial,
+    Mesh, RenderTarget, Rgb, Rgba, Scene,
+};
+
+// A device of its own, the bind group layouts are created for the first device.
+async fn init_downlevel_device() -> (Arc<wgpu::Device>, wgpu::Queue) {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = instance.request_adapter(&Default::default()).await.unwrap();
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::downlevel_webgl2_defaults(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    (Arc::new(device), queue)
+}
+
+#[async_std::test]
+async fn test_uniform_lights_render() {
+    let (device, queue) = init_downlevel_device().await;
+    assert_eq!(device.limits().max_storage_buffers_per_shader_stage, 0);
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_max_lights(100);
+    assert_eq!(scene.max_lights(), 64);
+    let mut sun = DirectionalLight::new(Rgb::new(1., 1., 1.), 1., Vector3::new(0., -1., -1.));
+    sun.shadow = Some(DirectionalShadow::default());
+    scene.add_light(sun);
+    let mut light = PointLight::new(Rgb::new(1., 1., 1.), 1., Point3::new(0., 2., 0.));
+    light.shadow = Some(PointShadow::default());
+    scene.add_light(light);
+    scene.add_mesh(Mesh::new(
+        Geometry::cuboid(1., 1., 1.),
+        LambertMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+
+    let mut target = RenderTarget::new(device, 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 0., 3.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 10.,
+    });
+    scene.render_to_target(&queue, &target);
+}
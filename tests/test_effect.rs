@@ -0,0 +1,119 @@
+mod common;
+
+use san::{
+    effect::{
+        Bloom, ColorGrading, Effect, EffectContext, Fxaa, ShaderEffect, ToneMapping,
+        ToneMappingOperator, Vignette, HDR_FORMAT,
+    },
+    texture::ColorSpace,
+    Texture, Transparency, WGPURendererOption,
+};
+
+const SIZE: (u32, u32) = (64, 48);
+
+fn create_view(device: &wgpu::Device) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: SIZE.0,
+                height: SIZE.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&Default::default())
+}
+
+fn assert_render<E>(device: &wgpu::Device, queue: &wgpu::Queue, effect: E)
+where
+    E: Effect,
+{
+    let (input, output) = (create_view(device), create_view(device));
+    let context = EffectContext {
+        device,
+        queue,
+        size: SIZE,
+    };
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    effect.render(&context, &input, &output, &mut encoder);
+    queue.submit(Some(encoder.finish()));
+}
+
+#[async_std::test]
+async fn test_effect_render() {
+    let (device, queue) = common::init_device().await;
+
+    assert_render(&device, &queue, Bloom::default());
+    assert_render(&device, &queue, Fxaa::new());
+    assert_render(&device, &queue, Vignette::new(0.8));
+    for operator in [
+        ToneMappingOperator::None,
+        ToneMappingOperator::Reinhard,
+        ToneMappingOperator::Aces,
+        ToneMappingOperator::AgX,
+    ] {
+        assert_render(&device, &queue, ToneMapping::new(operator));
+    }
+    assert_render(
+        &device,
+        &queue,
+        ColorGrading::new(ColorGrading::neutral_lut(16)),
+    );
+}
+
+#[async_std::test]
+async fn test_shader_effect_render() {
+    let (device, queue) = common::init_device().await;
+
+    let source = "
+        @group(0) @binding(2)
+        var<uniform> strength: vec4<f32>;
+        @group(0) @binding(3)
+        var overlay: texture_2d<f32>;
+
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let color = textureSample(input_texture, input_sampler, in.uv);
+            let top = textureSample(overlay, input_sampler, in.uv);
+            return mix(color, top, strength.x * top.a);
+        }
+    ";
+    let overlay = Texture::from_rgba8(1, 1, vec![255, 0, 0, 128], ColorSpace::Srgb);
+    let effect = ShaderEffect::new("overlay", source)
+        .with_params([0.5f32; 4])
+        .with_texture(overlay);
+
+    assert_render(&device, &queue, effect);
+}
+
+#[test]
+fn test_neutral_lut() {
+    let lut = ColorGrading::neutral_lut(4);
+
+    assert_eq!((lut.width(), lut.height()), (16, 4));
+    assert_eq!(lut.color_space(), ColorSpace::Linear);
+    assert_eq!(lut.mip_level_count(), 1);
+}
+
+#[test]
+fn test_renderer_option_tone_mapping() {
+    let option = WGPURendererOption::default();
+    assert_eq!(option.tone_mapping, ToneMappingOperator::None);
+    assert_eq!(option.exposure, 1.);
+    assert_eq!(option.transparency, Transparency::Sorted);
+
+    let option = option
+        .tone_mapping(ToneMappingOperator::AgX)
+        .exposure(2.)
+        .transparency(Transparency::WeightedBlended);
+    assert_eq!(option.tone_mapping, ToneMappingOperator::AgX);
+    assert_eq!(option.exposure, 2.);
+    assert_eq!(option.transparency, Transparency::WeightedBlended);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use san::{
+    cgmath::{Matrix4, SquareMatrix},
+    geometry::Geometry,
+    material::BasicMaterial,
+    mesh::MeshBase,
+    shader, Mesh, Rgba, Transparency,
+};
+
+// later than the copy, file systems may not tell writes in the same instant apart
+fn write(path: &Path, source: &str, seconds: u64) {
+    fs::write(path, source).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+        .unwrap();
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
+#[async_std::test]
+async fn test_hot_reload() {
+    let (device, queue) = common::init_device().await;
+    let draw = || {
+        let mesh = Mesh::new(
+            Geometry::sphere(1., 8, 4),
+            BasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+        );
+        mesh.gpu_data(
+            &device,
+            &queue,
+            Matrix4::identity(),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            None,
+            Transparency::Sorted,
+        );
+    };
+
+    let dir = std::env::temp_dir().join(format!("san_hot_reload_{}", std::process::id()));
+    copy_dir(
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders")),
+        &dir,
+    );
+    let basic = dir.join("basic.wgsl");
+    let original = fs::read_to_string(&basic).unwrap();
+
+    assert_eq!(shader::reload(), Ok(false));
+    shader::watch(&dir);
+    assert_eq!(shader::reload(), Ok(true));
+    assert_eq!(shader::reload(), Ok(false));
+    draw();
+
+    write(&basic, &format!("{original}\nfn broken("), 1);
+    let e = shader::reload().unwrap_err();
+    assert_eq!(e.file, "basic.wgsl");
+    assert_eq!(e.line, original.lines().count() + 2);
+    // reported once, the previous shaders are still drawn
+    assert_eq!(shader::reload(), Ok(false));
+    draw();
+
+    write(&basic, &original.replace("fs_main", "fs_renamed"), 2);
+    let e = shader::reload().unwrap_err();
+    assert!(e.message.contains("fs_main"));
+
+    write(&basic, &format!("// reloaded\n{original}"), 3);
+    assert_eq!(shader::reload(), Ok(true));
+    draw();
+
+    // chunks are validated with every shader including them
+    let camera = dir.join("chunks").join("camera.wgsl");
+    let chunk = fs::read_to_string(&camera).unwrap();
+    write(&camera, &chunk.replace("view_proj", "view_projection"), 4);
+    let e = shader::reload().unwrap_err();
+    assert_ne!(e.file, "camera.wgsl");
+    write(&camera, &format!("// reloaded\n{chunk}"), 5);
+    assert_eq!(shader::reload(), Ok(true));
+    draw();
+
+    // removed files revert to the compiled in sources
+    write(&basic, "fn broken(", 6);
+    assert!(shader::reload().is_err());
+    fs::remove_file(&basic).unwrap();
+    assert_eq!(shader::reload(), Ok(true));
+    assert_eq!(shader::reload(), Ok(false));
+    draw();
+
+    fs::remove_dir_all(&dir).unwrap();
+}
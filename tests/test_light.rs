@@ -0,0 +1,75 @@
+use san::{
+    camera::{Camera, PerspectiveCamera},
+    cgmath::{Point3, Vector3},
+    light::{
+        DirectionalLight, DirectionalShadow, Light, PointLight, PointShadow, SpotLight, SpotShadow,
+    },
+    Rgb,
+};
+
+fn camera() -> PerspectiveCamera {
+    PerspectiveCamera {
+        eye: Point3::new(0., 2., 5.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 100.,
+    }
+}
+
+#[test]
+fn test_directional_light_cascades() {
+    let camera = camera();
+    let mut light = DirectionalLight::new(Rgb::new(1., 1., 1.), 1., Vector3::new(0., -1., -1.));
+
+    let shadows = light.to_shadow_raws(&camera.projection_matrix(), camera.position());
+    assert!(shadows.is_empty());
+
+    light.shadow = Some(DirectionalShadow {
+        cascades: 4,
+        ..Default::default()
+    });
+    let shadows = light.to_shadow_raws(&camera.projection_matrix(), camera.position());
+    assert_eq!(shadows.len(), 4);
+}
+
+#[test]
+fn test_spot_light_shadow() {
+    let camera = camera();
+    let light = SpotLight {
+        shadow: Some(SpotShadow::default()),
+        ..SpotLight::new(
+            Rgb::new(1., 1., 1.),
+            1.,
+            Point3::new(0., 3., 0.),
+            Vector3::new(0., -1., 0.),
+            30.,
+        )
+    };
+
+    let shadows = light.to_shadow_raws(&camera.projection_matrix(), camera.position());
+    assert_eq!(shadows.len(), 1);
+}
+
+#[test]
+fn test_point_light_without_shadow() {
+    let camera = camera();
+    let light = PointLight::new(Rgb::new(1., 1., 1.), 1., Point3::new(0., 3., 0.));
+
+    let shadows = light.to_shadow_raws(&camera.projection_matrix(), camera.position());
+    assert!(shadows.is_empty());
+}
+
+#[test]
+fn test_point_light_cube_shadow() {
+    let camera = camera();
+    let light = PointLight {
+        shadow: Some(PointShadow::default()),
+        ..PointLight::new(Rgb::new(1., 1., 1.), 1., Point3::new(0., 3., 0.))
+    };
+
+    let shadows = light.to_shadow_raws(&camera.projection_matrix(), camera.position());
+    assert_eq!(shadows.len(), 6);
+}
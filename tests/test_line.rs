@@ -0,0 +1,170 @@
+mod common;
+
+use san::{
+    cgmath::{Matrix4, SquareMatrix},
+    geometry::Geometry,
+    line::{LineCap, LineJoin, LineMaterial, LineSegmentRaw},
+    material::LineBasicMaterial,
+    mesh::MeshBase,
+    Line2, Mesh, PipelineKey, Rgba, Transparency,
+};
+
+#[async_std::test]
+async fn test_line2_gpu_data() {
+    let (device, queue) = common::init_device().await;
+
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+    let colors = [Rgba::new(1., 0., 0., 1.), Rgba::new(0., 1., 0., 1.)];
+
+    for (cap, join) in [
+        (LineCap::Butt, LineJoin::Miter),
+        (LineCap::Square, LineJoin::Bevel),
+        (LineCap::Round, LineJoin::Round),
+    ] {
+        let material = LineMaterial::new(Rgba::new(1., 1., 1., 1.), 4.)
+            .cap(cap)
+            .join(join)
+            .dashed(0.1, 0.05);
+        let line = Line2::with_colors(&points, &colors, material);
+
+        line.gpu_data(
+            &device,
+            &queue,
+            Matrix4::identity(),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            None,
+            Transparency::Sorted,
+        );
+    }
+}
+
+#[test]
+fn test_geometry_topology() {
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+
+    assert_eq!(
+        Geometry::lines(&points).topology(),
+        wgpu::PrimitiveTopology::LineList
+    );
+    assert_eq!(
+        Geometry::polyline(&points).topology(),
+        wgpu::PrimitiveTopology::LineStrip
+    );
+    assert_eq!(
+        Geometry::points(&points).topology(),
+        wgpu::PrimitiveTopology::PointList
+    );
+    assert_eq!(
+        Geometry::plane(1., 1.).topology(),
+        wgpu::PrimitiveTopology::TriangleList
+    );
+}
+
+#[test]
+fn test_pipeline_key_topology() {
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let keys = [
+        wgpu::PrimitiveTopology::TriangleList,
+        wgpu::PrimitiveTopology::LineList,
+        wgpu::PrimitiveTopology::LineStrip,
+        wgpu::PrimitiveTopology::PointList,
+    ]
+    .map(|topology| PipelineKey::new(format, topology));
+
+    let unique: std::collections::HashSet<_> = keys.iter().collect();
+    assert_eq!(unique.len(), keys.len());
+}
+
+#[async_std::test]
+async fn test_polyline_gpu_data() {
+    let (device, queue) = common::init_device().await;
+
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.]];
+    let mesh = Mesh::new(
+        Geometry::polyline(&points),
+        LineBasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    );
+
+    mesh.gpu_data(
+        &device,
+        &queue,
+        Matrix4::identity(),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        None,
+        Transparency::Sorted,
+    );
+}
+
+#[test]
+fn test_line2_segments() {
+    let points = [[0., 0., 0.], [3., 0., 0.], [3., 4., 0.]];
+    let colors = [Rgba::new(1., 0., 0., 1.)];
+    let material = LineMaterial::new(Rgba::new(1., 1., 1., 1.), 2.);
+    let line = Line2::with_colors(&points, &colors, material);
+
+    assert_eq!(
+        line.segments(),
+        [
+            LineSegmentRaw {
+                prev: points[0],
+                start: points[0],
+                end: points[1],
+                next: points[2],
+                color_start: [1., 0., 0., 1.],
+                color_end: [1.; 4],
+                distances: [0., 3.],
+                flags: 0b10,
+            },
+            LineSegmentRaw {
+                prev: points[0],
+                start: points[1],
+                end: points[2],
+                next: points[2],
+                color_start: [1.; 4],
+                color_end: [1.; 4],
+                distances: [3., 7.],
+                flags: 0b01,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_line2_closed_segments() {
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 0., 0.]];
+    let material = LineMaterial::new(Rgba::new(1., 1., 1., 1.), 2.);
+    let segments = Line2::new(&points, material).segments().to_vec();
+
+    assert_eq!(segments.len(), 3);
+    assert!(segments.iter().all(|s| s.flags == 0b11));
+    assert_eq!(segments[0].prev, points[2]);
+    assert_eq!(segments[1].prev, points[0]);
+    assert_eq!(segments[1].next, points[3]);
+    assert_eq!(segments[2].next, points[1]);
+    assert_eq!(segments[2].distances, [2., 2. + 2f32.sqrt()]);
+}
+
+#[test]
+#[should_panic]
+fn test_line_material_zero_dash() {
+    LineMaterial::new(Rgba::new(1., 1., 1., 1.), 2.).dashed(0., 0.);
+}
+
+#[async_std::test]
+async fn test_lines_points_gpu_data() {
+    let (device, queue) = common::init_device().await;
+
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+    for geometry in [Geometry::lines(&points), Geometry::points(&points)] {
+        let mesh = Mesh::new(geometry, LineBasicMaterial::new(Rgba::new(1., 1., 1., 1.)));
+
+        mesh.gpu_data(
+            &device,
+            &queue,
+            Matrix4::identity(),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            None,
+            Transparency::Sorted,
+        );
+    }
+}
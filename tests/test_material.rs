@@ -0,0 +1,370 @@
+mod common;
+
+use san::{
+    cgmath::{Matrix4, SquareMatrix},
+    geometry::Geometry,
+    line::LineMaterial,
+    material::{
+        BasicMaterial, Blending, LambertMaterial, LineBasicMaterial, Material, PhongMaterial,
+        RenderState, ShaderMaterial, Side, StandardMaterial, UniformValue,
+    },
+    mesh::MeshBase,
+    texture::{ColorSpace, Texture},
+    Line2, Mesh, Rgb, Rgba, Sampler, Transparency,
+};
+
+fn assert_gpu_data<M>(device: &wgpu::Device, queue: &wgpu::Queue, material: M)
+where
+    M: Material + 'static,
+{
+    let mesh = Mesh::new(Geometry::sphere(1., 8, 4), material);
+    mesh.gpu_data(
+        device,
+        queue,
+        Matrix4::identity(),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        None,
+        Transparency::Sorted,
+    );
+}
+
+#[async_std::test]
+async fn test_material_gpu_data() {
+    let (device, queue) = common::init_device().await;
+    let color = Rgba::new(0.5, 0.5, 0.5, 1.);
+
+    assert_gpu_data(&device, &queue, BasicMaterial::new(color));
+    assert_gpu_data(
+        &device,
+        &queue,
+        LambertMaterial::new(color).emissive(Rgb::new(0.1, 0., 0.)),
+    );
+    assert_gpu_data(
+        &device,
+        &queue,
+        PhongMaterial::new(color)
+            .specular(Rgb::new(1., 1., 1.))
+            .shininess(60.),
+    );
+
+    let map = Texture::from_rgba8(2, 2, vec![128; 16], ColorSpace::Srgb);
+    let linear_map = Texture::from_rgba8(2, 2, vec![128; 16], ColorSpace::Linear);
+    let hdr_map = Texture::from_rgba32f(1, 1, vec![4., 2., 1., 1.]);
+    assert_gpu_data(
+        &device,
+        &queue,
+        BasicMaterial::new(color).with_map(
+            map.clone()
+                .with_mipmaps(false)
+                .with_sampler(Sampler::nearest()),
+        ),
+    );
+    assert_gpu_data(
+        &device,
+        &queue,
+        BasicMaterial::new(color).with_map(hdr_map.with_sampler(Sampler::linear().anisotropy(16))),
+    );
+    assert_gpu_data(&device, &queue, StandardMaterial::new(color));
+    assert_gpu_data(
+        &device,
+        &queue,
+        StandardMaterial::new(color)
+            .metallic(1.)
+            .roughness(0.3)
+            .base_color_map(map.clone())
+            .metallic_roughness_map(linear_map.clone())
+            .normal_map(linear_map.clone(), 1.)
+            .occlusion_map(linear_map, 1.)
+            .emissive_map(map.clone())
+            .env_map(map, 1.),
+    );
+}
+
+#[async_std::test]
+async fn test_render_state_gpu_data() {
+    let (device, queue) = common::init_device().await;
+    let color = Rgba::new(0.5, 0.5, 0.5, 0.5);
+    let custom = wgpu::BlendState {
+        color: wgpu::BlendComponent::OVER,
+        alpha: wgpu::BlendComponent::REPLACE,
+    };
+
+    for blending in [
+        Blending::Opaque,
+        Blending::Alpha,
+        Blending::Premultiplied,
+        Blending::Additive,
+        Blending::Multiply,
+        Blending::Custom(custom),
+    ] {
+        let state = RenderState::default().with_blending(blending);
+        assert_gpu_data(
+            &device,
+            &queue,
+            BasicMaterial::new(color).with_render_state(state),
+        );
+    }
+    for side in [Side::Front, Side::Back, Side::Double] {
+        let state = RenderState::default().with_side(side);
+        assert_gpu_data(
+            &device,
+            &queue,
+            LambertMaterial::new(color).with_render_state(state),
+        );
+        assert_gpu_data(
+            &device,
+            &queue,
+            PhongMaterial::new(color).with_render_state(state),
+        );
+        assert_gpu_data(
+            &device,
+            &queue,
+            StandardMaterial::new(color).with_render_state(state),
+        );
+    }
+
+    // filled when the device lacks the feature
+    let wireframe = RenderState::default()
+        .with_polygon_mode(wgpu::PolygonMode::Line)
+        .with_write_mask(wgpu::ColorWrites::COLOR);
+    assert_gpu_data(
+        &device,
+        &queue,
+        BasicMaterial::new(color).with_render_state(wireframe),
+    );
+    assert_gpu_data(
+        &device,
+        &queue,
+        shader_material().with_render_state(wireframe),
+    );
+}
+
+#[async_std::test]
+async fn test_oit_gpu_data() {
+    let (device, queue) = common::init_device().await;
+    let color = Rgba::new(0.5, 0.5, 0.5, 0.5);
+    let state = RenderState::default().with_transparent(true);
+    let sphere = || Geometry::sphere(1., 8, 4);
+
+    // shader materials are sorted instead
+    let meshes: Vec<Box<dyn MeshBase>> = vec![
+        Box::new(Mesh::new(
+            sphere(),
+            BasicMaterial::new(color).with_render_state(state),
+        )),
+        Box::new(Mesh::new(
+            sphere(),
+            LambertMaterial::new(color).with_render_state(state),
+        )),
+        Box::new(Mesh::new(
+            sphere(),
+            PhongMaterial::new(color).with_render_state(state),
+        )),
+        Box::new(Mesh::new(
+            sphere(),
+            StandardMaterial::new(color).with_render_state(state),
+        )),
+        Box::new(Mesh::new(
+            Geometry::polyline(&[[0., 0., 0.], [1., 0., 0.]]),
+            LineBasicMaterial::new(color).with_render_state(state),
+        )),
+        Box::new(Line2::new(
+            &[[0., 0., 0.], [1., 0., 0.], [1., 1., 0.]],
+            LineMaterial::new(color, 2.).with_render_state(state),
+        )),
+        Box::new(Mesh::new(
+            sphere(),
+            shader_material().with_render_state(state),
+        )),
+    ];
+    for mesh in meshes {
+        mesh.gpu_data(
+            &device,
+            &queue,
+            Matrix4::identity(),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            Some(wgpu::TextureFormat::Depth32Float),
+            Transparency::WeightedBlended,
+        );
+    }
+}
+
+#[test]
+fn test_render_state() {
+    let state = RenderState::default();
+    assert_eq!(state.blending, Blending::Alpha);
+    assert_eq!(state.side, Side::Front);
+    assert_eq!(state.polygon_mode, wgpu::PolygonMode::Fill);
+    assert_eq!(state.write_mask, wgpu::ColorWrites::ALL);
+    assert!(state.transparent);
+    assert_eq!(
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)).render_state(),
+        state
+    );
+    assert!(!state.with_blending(Blending::Opaque).transparent);
+    assert!(state.with_blending(Blending::Additive).transparent);
+    assert!(
+        !state
+            .with_blending(Blending::Alpha)
+            .with_transparent(false)
+            .transparent
+    );
+
+    assert_eq!(Blending::Opaque.blend_state(), None);
+    assert_eq!(
+        Blending::Alpha.blend_state(),
+        Some(wgpu::BlendState::ALPHA_BLENDING)
+    );
+    assert_eq!(Side::Front.cull_mode(), Some(wgpu::Face::Back));
+    assert_eq!(Side::Back.cull_mode(), Some(wgpu::Face::Front));
+    assert_eq!(Side::Double.cull_mode(), None);
+}
+
+const SHADER_MATERIAL_SOURCE: &str = "
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * model_matrix(instance) * vec4<f32>(model.position, 1.);
+    out.uv = model.uv + uniforms.offset;
+    out.world_normal = normal_matrix(instance) * model.normal;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light = max(dot(normalize(in.world_normal), uniforms.direction), 0.) * uniforms.intensity;
+    return uniforms.color * textureSample(map, map_sampler, in.uv) * light;
+}
+";
+
+fn shader_material() -> ShaderMaterial {
+    ShaderMaterial::new("test", SHADER_MATERIAL_SOURCE)
+        .with_uniform("intensity", 1f32)
+        .with_uniform("direction", [0., 1., 0.])
+        .with_uniform("offset", [0., 0.])
+        .with_uniform("color", Rgba::new(1., 0., 0., 1.))
+        .with_texture(
+            "map",
+            Texture::from_rgba8(1, 1, vec![255; 4], ColorSpace::Srgb),
+        )
+}
+
+#[async_std::test]
+async fn test_shader_material_gpu_data() {
+    let (device, queue) = common::init_device().await;
+
+    let mesh = Mesh::new(Geometry::sphere(1., 8, 4), shader_material());
+    mesh.gpu_data(
+        &device,
+        &queue,
+        Matrix4::identity(),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        None,
+        Transparency::Sorted,
+    );
+
+    mesh.material().set_uniform("intensity", 0.5f32);
+    mesh.gpu_data(
+        &device,
+        &queue,
+        Matrix4::identity(),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        None,
+        Transparency::Sorted,
+    );
+
+    assert_gpu_data(
+        &device,
+        &queue,
+        ShaderMaterial::new(
+            "lit",
+            "
+            #include \"lighting\"
+
+            struct VertexOutput {
+                @builtin(position) clip_position: vec4<f32>,
+                @location(0) world_position: vec3<f32>,
+                @location(1) world_normal: vec3<f32>,
+            }
+
+            @vertex
+            fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+                let world_position = model_matrix(instance) * vec4<f32>(model.position, 1.);
+                var out: VertexOutput;
+                out.clip_position = globals.view_proj * world_position;
+                out.world_position = world_position.xyz;
+                out.world_normal = normal_matrix(instance) * model.normal;
+                return out;
+            }
+
+            @fragment
+            fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+                var color = vec3<f32>(0.);
+                for (var i = 0u; i < globals.light_count; i += 1u) {
+                    let light = incident_light(lights[i], in.world_position, in.world_normal);
+                    color += light.ambient + light.radiance;
+                }
+            #ifdef TINT
+                color *= uniforms.tint;
+            #endif
+                return vec4<f32>(color, 1.);
+            }
+            ",
+        )
+        .with_uniform("tint", Rgb::new(1., 0.5, 0.5))
+        .with_define("TINT"),
+    );
+    assert_gpu_data(
+        &device,
+        &queue,
+        ShaderMaterial::new(
+            "no uniforms",
+            "
+            @vertex
+            fn vs_main(model: VertexInput) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(model.position, 1.);
+            }
+
+            @fragment
+            fn fs_main() -> @location(0) vec4<f32> {
+                return vec4<f32>(1.);
+            }
+            ",
+        ),
+    );
+}
+
+#[test]
+fn test_shader_material_uniform() {
+    let material = shader_material();
+    material.set_uniform("offset", san::cgmath::Vector2::new(0.5, 0.25));
+
+    assert_eq!(
+        material.uniform("offset"),
+        Some(UniformValue::Vec2([0.5, 0.25]))
+    );
+    assert_eq!(material.uniform("intensity"), Some(UniformValue::F32(1.)));
+    assert_eq!(material.uniform("map"), None);
+    assert_eq!(
+        material.clone().uniform("offset"),
+        Some(UniformValue::Vec2([0.5, 0.25]))
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_shader_material_uniform_type_mismatch() {
+    shader_material().set_uniform("intensity", 1u32);
+}
+
+#[test]
+#[should_panic]
+fn test_shader_material_duplicate_name() {
+    shader_material().with_uniform("map", 0f32);
+}
@@ -0,0 +1,495 @@
+mod common;
+
+use std::collections::HashSet;
+
+use san::{
+    camera::PerspectiveCamera,
+    cgmath::{Point3, Vector3},
+    geometry::Geometry,
+    light::{AmbientLight, DirectionalLight, DirectionalShadow, PointLight, PointShadow},
+    line::LineMaterial,
+    material::{BasicMaterial, LambertMaterial, RenderState, ShaderMaterial},
+    texture::{ColorSpace, CubeTexture},
+    Background, Instance, Line2, Mesh, RenderTarget, Rgb, Rgba, Scene, Transparency,
+};
+
+const CAMERA: PerspectiveCamera = PerspectiveCamera {
+    eye: Point3::new(0., 0., 3.),
+    target: Point3::new(0., 0., 0.),
+    up: Vector3::new(0., 1., 0.),
+    aspect: 1.,
+    fovy: 45.,
+    znear: 0.1,
+    zfar: 10.,
+};
+
+/// Copies the pixels of an `Rgba8Unorm` target, row by row.
+fn read_pixels(device: &wgpu::Device, queue: &wgpu::Queue, target: &RenderTarget) -> Vec<[u8; 4]> {
+    let (width, height) = (target.width(), target.height());
+    let padded = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (padded * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_texture_to_buffer(
+        target.color_texture().as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(padded),
+                rows_per_image: None,
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).unwrap()
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap().unwrap();
+
+    let data = slice.get_mapped_range();
+    data.chunks(padded as usize)
+        .flat_map(|row| row[..(width * 4) as usize].chunks(4))
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        .collect()
+}
+
+#[async_std::test]
+async fn test_render_target_texture() {
+    let (device, _) = common::init_device().await;
+
+    let target = RenderTarget::new(device, 64, 32, wgpu::TextureFormat::Rgba8UnormSrgb);
+    let texture = target.texture();
+
+    assert_eq!((texture.width(), texture.height()), (64, 32));
+    assert_eq!(texture.color_space(), ColorSpace::Srgb);
+    assert_eq!(texture.mip_level_count(), 1);
+    assert_eq!(texture.with_mipmaps(true).mip_level_count(), 1);
+    assert_eq!(
+        target.depth_format(),
+        Some(wgpu::TextureFormat::Depth32Float)
+    );
+    assert!(target.depth_view().is_some());
+
+    let target = target.with_depth_format(None);
+    assert!(target.depth_view().is_none());
+
+    let faces = [(); 6].map(|_| target.texture());
+    assert!(CubeTexture::from_faces(faces).is_err());
+}
+
+#[async_std::test]
+async fn test_render_target_resize() {
+    let (device, _) = common::init_device().await;
+
+    let mut target = RenderTarget::new(device, 64, 32, wgpu::TextureFormat::Rgba8Unorm);
+    let old = target.texture();
+    target.resize(16, 16);
+
+    assert_eq!((target.width(), target.height()), (16, 16));
+    assert_eq!(target.texture().width(), 16);
+    assert_eq!(old.width(), 64);
+}
+
+#[async_std::test]
+async fn test_scene_render_to_target() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Background::gradient(
+        Rgb::new(1., 0., 0.),
+        Rgb::new(0., 0., 1.),
+    ));
+    scene.add_mesh(Mesh::new(
+        Geometry::cuboid(1., 1., 1.),
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 0., 3.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 10.,
+    });
+    scene.render_to_target(&queue, &target);
+
+    // the first target is sampled while rendering into one without depth
+    let mut other = Scene::new(device.clone());
+    other.add_mesh(Mesh::new(
+        Geometry::plane(2., 2.),
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)).with_map(target.texture()),
+    ));
+    let second =
+        RenderTarget::new(device, 4, 4, wgpu::TextureFormat::Rgba16Float).with_depth_format(None);
+    other.render_to_target(&queue, &second);
+    scene.render_to_target(&queue, &second);
+}
+
+#[async_std::test]
+async fn test_scene_render_transparent() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    let transparent = RenderState::default().with_transparent(true);
+    for (z, color) in [
+        (0.5, Rgba::new(1., 0., 0., 0.5)),
+        (-0.5, Rgba::new(0., 1., 0., 0.5)),
+    ] {
+        let instance = Instance {
+            position: Vector3::new(0., 0., z),
+            ..Default::default()
+        };
+        scene.add_mesh(Mesh::with_instances(
+            Geometry::plane(1., 1.),
+            BasicMaterial::new(color).with_render_state(transparent),
+            vec![instance],
+        ));
+    }
+    let mut overlay = Mesh::new(
+        Geometry::plane(0.5, 0.5),
+        BasicMaterial::new(Rgba::new(0., 0., 1., 0.5)).with_render_state(transparent),
+    );
+    assert_eq!(overlay.render_order(), 0);
+    overlay.set_render_order(1);
+    assert_eq!(overlay.render_order(), 1);
+    scene.add_mesh(overlay);
+    scene.add_mesh(Mesh::new(
+        Geometry::cuboid(0.2, 0.2, 0.2),
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+    // blended after the composite with weighted blended transparency
+    let source = "
+        struct VertexOutput {
+            @builtin(position) clip_position: vec4<f32>,
+        }
+
+        @vertex
+        fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+            var out: VertexOutput;
+            out.clip_position = globals.view_proj * model_matrix(instance) * vec4<f32>(model.position, 1.);
+            return out;
+        }
+
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> {
+            return uniforms.color;
+        }
+    ";
+    scene.add_mesh(Mesh::new(
+        Geometry::plane(0.5, 0.5),
+        ShaderMaterial::new("tint", source)
+            .with_uniform("color", Rgba::new(1., 1., 0., 0.5))
+            .with_render_state(transparent),
+    ));
+
+    for transparency in [Transparency::Sorted, Transparency::WeightedBlended] {
+        let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm)
+            .with_transparency(transparency);
+        assert_eq!(target.transparency(), transparency);
+        target.set_camera(&PerspectiveCamera {
+            eye: Point3::new(0., 0., 3.),
+            target: Point3::new(0., 0., 0.),
+            up: Vector3::unit_y(),
+            aspect: 1.,
+            fovy: 45.,
+            znear: 0.1,
+            zfar: 10.,
+        });
+        scene.render_to_target(&queue, &target);
+    }
+}
+
+#[async_std::test]
+async fn test_scene_render_nodes() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    let group = scene.add_node(Instance {
+        position: Vector3::new(0., 0., -1.),
+        ..Default::default()
+    });
+    let child = scene.add_node(Instance::default());
+    scene.set_node_parent(&child, Some(&group)).unwrap();
+
+    let cube = scene.add_mesh(Mesh::new(
+        Geometry::cuboid(1., 1., 1.),
+        LambertMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+    scene.attach_mesh(&cube, Some(&child)).unwrap();
+    let mut light = PointLight::new(Rgb::new(1., 1., 1.), 1., Point3::new(0., 2., 0.));
+    light.shadow = Some(PointShadow::default());
+    let light = scene.add_light(light);
+    scene.attach_light(&light, Some(&child)).unwrap();
+    let mut sun = DirectionalLight::new(Rgb::new(1., 1., 1.), 1., Vector3::new(0., -1., 0.));
+    sun.shadow = Some(DirectionalShadow::default());
+    let sun = scene.add_light(sun);
+    scene.attach_light(&sun, Some(&group)).unwrap();
+
+    scene.set_camera(&PerspectiveCamera {
+        eye: Point3::new(0., 0., 3.),
+        target: Point3::new(0., 0., 0.),
+        up: Vector3::unit_y(),
+        aspect: 1.,
+        fovy: 45.,
+        znear: 0.1,
+        zfar: 10.,
+    });
+    scene.set_camera_node(Some(&group)).unwrap();
+
+    let target = RenderTarget::new(device, 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    scene.render_to_target(&queue, &target);
+
+    scene.remove_node(group).unwrap();
+    scene.render_to_target(&queue, &target);
+}
+
+#[async_std::test]
+async fn test_scene_render_transparent_blending() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Rgb::new(0., 0., 0.));
+    let transparent = RenderState::default().with_transparent(true);
+    // added front to back, drawn back to front
+    let [_, far] = [
+        (0.5, Rgba::new(1., 0., 0., 0.5)),
+        (-0.5, Rgba::new(0., 1., 0., 0.5)),
+    ]
+    .map(|(z, color)| {
+        scene.add_mesh(Mesh::with_instances(
+            Geometry::plane(2., 2.),
+            BasicMaterial::new(color).with_render_state(transparent),
+            vec![Instance {
+                position: Vector3::new(0., 0., z),
+                ..Default::default()
+            }],
+        ))
+    });
+
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&CAMERA);
+    let center = |pixels: Vec<[u8; 4]>| {
+        let [r, g, b, _] = pixels[2 * 4 + 2];
+        [r, g, b]
+    };
+    let assert_close = |actual: [u8; 3], expected: [u8; 3]| {
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(&a, e)| a.abs_diff(e) <= 2);
+        assert!(close, "{actual:?} != {expected:?}");
+    };
+
+    // red over green over black
+    scene.render_to_target(&queue, &target);
+    assert_close(center(read_pixels(&device, &queue, &target)), [128, 64, 0]);
+
+    // the render order wins over the depth
+    scene.get_mesh_mut(&far).set_render_order(1);
+    scene.render_to_target(&queue, &target);
+    assert_close(center(read_pixels(&device, &queue, &target)), [64, 128, 0]);
+}
+
+#[async_std::test]
+async fn test_scene_draw_order() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    let mut add = |position: [f32; 3], render_order: i32, transparent: bool| {
+        let mut mesh = Mesh::with_instances(
+            Geometry::plane(1., 1.),
+            BasicMaterial::new(Rgba::new(1., 1., 1., 0.5))
+                .with_render_state(RenderState::default().with_transparent(transparent)),
+            vec![Instance {
+                position: position.into(),
+                ..Default::default()
+            }],
+        );
+        mesh.set_render_order(render_order);
+        scene.add_mesh(mesh).untyped()
+    };
+    // view depths 5, 2, 2.5 and 4, the second is further from the eye than the third
+    let opaque_far = add([0., 0., -2.], 0, false);
+    let opaque_side = add([2., 0., 1.], 0, false);
+    let opaque_near = add([0., 0., 0.5], 0, false);
+    let opaque_first = add([0., 0., -1.], -1, false);
+    let transparent_far = add([0., 0., -1.], 0, true);
+    let transparent_near = add([0., 0., 0.5], 0, true);
+    let transparent_first = add([0., 0., 0.5], -1, true);
+
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&CAMERA);
+    assert_eq!(
+        scene.draw_order(&queue, &target),
+        [
+            opaque_first,
+            opaque_side,
+            opaque_near,
+            opaque_far,
+            transparent_first,
+            transparent_far,
+            transparent_near,
+        ]
+    );
+
+    // without a depth test opaque meshes keep their slot order
+    let target = target.with_depth_format(None);
+    assert_eq!(
+        scene.draw_order(&queue, &target)[..4],
+        [opaque_first, opaque_far, opaque_side, opaque_near]
+    );
+
+    let target = target
+        .with_depth_format(Some(wgpu::TextureFormat::Depth32Float))
+        .with_transparency(Transparency::WeightedBlended);
+    let order = scene.draw_order(&queue, &target);
+    assert_eq!(
+        order[..4],
+        [opaque_first, opaque_side, opaque_near, opaque_far]
+    );
+    // accumulated in any order
+    assert_eq!(
+        order[4..].iter().copied().collect::<HashSet<_>>(),
+        HashSet::from([transparent_far, transparent_near, transparent_first])
+    );
+}
+
+#[async_std::test]
+async fn test_scene_render_weighted_blended_sizes() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Rgb::new(0., 0., 0.));
+    scene.add_mesh(Mesh::new(
+        Geometry::plane(2., 2.),
+        BasicMaterial::new(Rgba::new(1., 0., 0., 0.5))
+            .with_render_state(RenderState::default().with_transparent(true)),
+    ));
+
+    // the targets of both sizes are accumulated into alternately
+    let targets = [4, 8, 4].map(|size| {
+        let mut target =
+            RenderTarget::new(device.clone(), size, size, wgpu::TextureFormat::Rgba8Unorm)
+                .with_transparency(Transparency::WeightedBlended);
+        target.set_camera(&CAMERA);
+        target
+    });
+    for target in &targets {
+        scene.render_to_target(&queue, target);
+        let size = target.width() as usize;
+        let [r, g, b, _] = read_pixels(&device, &queue, target)[size / 2 * size + size / 2];
+        assert!(r.abs_diff(128) <= 2 && g == 0 && b == 0, "{:?}", [r, g, b]);
+    }
+}
+
+#[async_std::test]
+async fn test_scene_render_moved_node() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Rgb::new(0., 0., 0.));
+    let node = scene.add_node(Instance::default());
+    let plane = scene.add_mesh(Mesh::new(
+        Geometry::plane(2., 2.),
+        BasicMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+    scene.attach_mesh(&plane, Some(&node)).unwrap();
+
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&CAMERA);
+    let center = |target: &RenderTarget| read_pixels(&device, &queue, target)[2 * 4 + 2];
+
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255; 4]);
+
+    // only the last transform is rendered
+    for x in [1., 2., 3.] {
+        let transform = Instance {
+            position: Vector3::new(x, 0., 0.),
+            ..Default::default()
+        };
+        scene.set_node_transform(&node, transform).unwrap();
+    }
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target)[..3], [0; 3]);
+
+    scene.attach_mesh(&plane, None).unwrap();
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255; 4]);
+}
+
+#[async_std::test]
+async fn test_scene_render_without_lights() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Rgb::new(0., 0., 0.));
+    scene.add_mesh(Mesh::new(
+        Geometry::plane(2., 2.),
+        LambertMaterial::new(Rgba::new(1., 1., 1., 1.)),
+    ));
+    scene.add_light(AmbientLight::new(Rgb::new(1., 1., 1.), 1.));
+
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&CAMERA);
+    let center = |target: &RenderTarget| read_pixels(&device, &queue, target)[2 * 4 + 2];
+
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255; 4]);
+
+    scene.set_max_lights(0);
+    assert_eq!(scene.max_lights(), 0);
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target)[..3], [0; 3]);
+
+    scene.set_max_lights(1);
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255; 4]);
+}
+
+#[async_std::test]
+async fn test_scene_render_line2() {
+    let (device, queue) = common::init_device().await;
+
+    let mut scene = Scene::new(device.clone());
+    scene.set_background(Rgb::new(0., 0., 0.));
+    let white = LineMaterial::new(Rgba::new(1., 1., 1., 1.), 4.);
+    let line = scene.add_mesh(Line2::new(&[[-2., 0., 0.], [2., 0., 0.]], white));
+
+    let mut target = RenderTarget::new(device.clone(), 4, 4, wgpu::TextureFormat::Rgba8Unorm);
+    target.set_camera(&CAMERA);
+    let center = |target: &RenderTarget| read_pixels(&device, &queue, target)[2 * 4 + 2];
+
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255; 4]);
+
+    // the points are placed by the transform
+    scene.get_mesh_mut(&line).set_transform(Instance {
+        position: Vector3::new(0., 3., 0.),
+        ..Default::default()
+    });
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target)[..3], [0; 3]);
+
+    scene.get_mesh_mut(&line).set_transform(Instance::default());
+    *scene.get_mesh_mut(&line).material_mut() = LineMaterial::new(Rgba::new(1., 0., 0., 1.), 4.);
+    scene.render_to_target(&queue, &target);
+    assert_eq!(center(&target), [255, 0, 0, 255]);
+}
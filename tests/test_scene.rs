@@ -1,6 +1,13 @@
-use std::{any::Any, sync::Arc};
+mod common;
 
-use san::{geometry::GeometryGpuData, material::MaterialGpuData, mesh::MeshBase, AsAny, Scene};
+use std::{any::Any, collections::HashSet};
+
+use san::{
+    cgmath::{Matrix4, Point3, SquareMatrix, Vector3, Vector4},
+    light::{AmbientLight, DirectionalLight, PointLight},
+    mesh::{MeshBase, MeshGpuData},
+    AsAny, Background, Instance, Rgb, Scene, SceneError, Transparency,
+};
 
 #[derive(Debug)]
 struct DummyMesh {
@@ -27,23 +34,47 @@ impl MeshBase for DummyMesh {
     fn gpu_data(
         &self,
         _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _world: Matrix4<f32>,
         _format: wgpu::TextureFormat,
-    ) -> (Arc<GeometryGpuData>, Arc<MaterialGpuData>) {
+        _depth_format: Option<wgpu::TextureFormat>,
+        _transparency: Transparency,
+    ) -> MeshGpuData {
         unimplemented!()
     }
 }
 
-async fn init_scene() -> Scene {
-    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
-    let adapter = instance.request_adapter(&Default::default()).await.unwrap();
+#[derive(Debug)]
+struct OtherMesh;
 
-    let (device, _) = adapter
-        .request_device(&Default::default(), None)
-        .await
-        .unwrap();
+impl AsAny for OtherMesh {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 
-    // TextureFormat is dummy
-    Scene::new(Arc::new(device), wgpu::TextureFormat::Bc1RgbaUnorm)
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl MeshBase for OtherMesh {
+    fn gpu_data(
+        &self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _world: Matrix4<f32>,
+        _format: wgpu::TextureFormat,
+        _depth_format: Option<wgpu::TextureFormat>,
+        _transparency: Transparency,
+    ) -> MeshGpuData {
+        unimplemented!()
+    }
+}
+
+async fn init_scene() -> Scene {
+    let (device, _) = common::init_device().await;
+
+    Scene::new(device)
 }
 
 #[async_std::test]
@@ -67,6 +98,195 @@ async fn test_scene_remove_mesh() {
     assert_eq!(scene.meshes_len(), 1);
 }
 
+#[async_std::test]
+async fn test_scene_stale_mesh_id() {
+    let mut scene = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    let copy = mesh1;
+    assert_eq!(copy, mesh1);
+    assert!(scene.contains(&mesh1));
+
+    scene.remove_mesh(mesh1);
+    assert!(!scene.contains(&mesh1));
+
+    let mesh2 = scene.add_mesh(DummyMesh::new("mesh2"));
+    assert_ne!(mesh1, mesh2);
+    assert!(!scene.contains(&mesh1));
+    assert!(scene.contains(&mesh2));
+
+    let ids = HashSet::from([mesh1, mesh2, copy]);
+    assert_eq!(ids.len(), 2);
+}
+
+#[async_std::test]
+#[should_panic]
+async fn test_scene_get_stale_mesh() {
+    let mut scene = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    scene.remove_mesh(mesh1);
+    scene.add_mesh(DummyMesh::new("mesh2"));
+    scene.get_mesh_ref(&mesh1);
+}
+
+#[async_std::test]
+async fn test_scene_try_get_mesh() {
+    let mut scene = init_scene().await;
+    let mut other = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    let mesh2 = other.add_mesh(DummyMesh::new("mesh2"));
+
+    assert_eq!(scene.try_get_mesh(&mesh1).unwrap().label, "mesh1");
+    assert_eq!(scene.try_get_mesh_mut(&mesh1).unwrap().label, "mesh1");
+    assert_eq!(
+        scene.try_get_mesh(&mesh2).unwrap_err(),
+        SceneError::WrongScene
+    );
+    assert_eq!(scene.try_remove_mesh(mesh2), Err(SceneError::WrongScene));
+
+    assert_eq!(scene.try_remove_mesh(mesh1), Ok(()));
+    assert_eq!(scene.try_remove_mesh(mesh1), Err(SceneError::Removed));
+    assert_eq!(
+        scene.try_get_mesh_mut(&mesh1).unwrap_err(),
+        SceneError::Removed
+    );
+    assert_eq!(other.meshes_len(), 1);
+}
+
+#[async_std::test]
+async fn test_scene_iter_meshes() {
+    let mut scene = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    let mesh2 = scene.add_mesh(DummyMesh::new("mesh2"));
+    let other = scene.add_mesh(OtherMesh);
+    scene.remove_mesh(mesh2);
+
+    let ids: HashSet<_> = scene.meshes().map(|(id, _)| id).collect();
+    assert_eq!(ids, HashSet::from([mesh1.untyped(), other.untyped()]));
+    assert_eq!(scene.meshes_mut().count(), 2);
+
+    let dummies: Vec<_> = scene.meshes_of::<DummyMesh>().collect();
+    assert_eq!(dummies.len(), 1);
+    assert_eq!(dummies[0].0, mesh1);
+    assert_eq!(dummies[0].1.label, "mesh1");
+    for (_, mesh) in scene.meshes_of_mut::<DummyMesh>() {
+        mesh.label = "renamed";
+    }
+    assert_eq!(scene.get_mesh_ref(&mesh1).label, "renamed");
+
+    let wrong = other.untyped().typed::<DummyMesh>();
+    assert_eq!(
+        scene.try_get_mesh(&wrong).unwrap_err(),
+        SceneError::TypeMismatch
+    );
+    assert_eq!(scene.try_remove_mesh(wrong), Err(SceneError::TypeMismatch));
+    assert_eq!(scene.try_remove_mesh(other.untyped()), Ok(()));
+}
+
+#[async_std::test]
+async fn test_scene_retain_drain_meshes() {
+    let mut scene = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    let mesh2 = scene.add_mesh(DummyMesh::new("mesh2"));
+    let other = scene.add_mesh(OtherMesh);
+
+    scene.retain_meshes(|_, mesh| mesh.as_any().is::<DummyMesh>());
+    assert!(!scene.contains(&other));
+    assert_eq!(scene.meshes_len(), 2);
+
+    let drained: HashSet<_> = scene.drain_meshes().map(|(id, _)| id).collect();
+    assert_eq!(drained, HashSet::from([mesh1.untyped(), mesh2.untyped()]));
+    assert_eq!(scene.meshes_len(), 0);
+    assert!(!scene.contains(&mesh1));
+
+    scene.add_mesh(OtherMesh);
+    scene.clear_meshes();
+    assert_eq!(scene.meshes().count(), 0);
+}
+
+#[async_std::test]
+async fn test_scene_mesh_info() {
+    let mut scene = init_scene().await;
+
+    let mesh1 = scene.add_mesh(DummyMesh::new("mesh1"));
+    let mesh2 = scene.add_mesh(DummyMesh::new("mesh2"));
+
+    scene
+        .set_mesh_name(&mesh1, Some("floor".to_owned()))
+        .unwrap();
+    assert_eq!(scene.mesh_name(&mesh1), Some("floor"));
+    assert_eq!(scene.mesh_name(&mesh2), None);
+    assert_eq!(scene.find_mesh("floor"), Some(mesh1.untyped()));
+    assert_eq!(scene.find_mesh("wall"), None);
+
+    scene.add_mesh_tag(&mesh1, "static").unwrap();
+    scene.add_mesh_tag(&mesh1, "static").unwrap();
+    scene.add_mesh_tag(&mesh2, "static").unwrap();
+    scene.add_mesh_tag(&mesh2, "selected").unwrap();
+    assert_eq!(scene.mesh_tags(&mesh1).unwrap(), ["static"]);
+    assert_eq!(scene.meshes_with_tag("static").count(), 2);
+    scene.remove_mesh_tag(&mesh2, "static").unwrap();
+    let tagged: Vec<_> = scene.meshes_with_tag("static").collect();
+    assert_eq!(tagged, [mesh1.untyped()]);
+
+    scene.set_mesh_user_data(&mesh1, 42u32).unwrap();
+    *scene.mesh_user_data_mut::<_, u32>(&mesh1).unwrap() += 1;
+    assert_eq!(scene.mesh_user_data::<_, u32>(&mesh1), Some(&43));
+    assert_eq!(scene.mesh_user_data::<_, String>(&mesh1), None);
+    assert_eq!(scene.mesh_user_data::<_, u32>(&mesh2), None);
+
+    scene.remove_mesh(mesh1);
+    let mesh3 = scene.add_mesh(DummyMesh::new("mesh3"));
+    assert_eq!(scene.mesh_name(&mesh3), None);
+    assert_eq!(scene.mesh_tags(&mesh3), Ok(&[][..]));
+    assert_eq!(scene.find_mesh("floor"), None);
+}
+
+#[async_std::test]
+async fn test_scene_stale_mesh_info() {
+    let mut scene = init_scene().await;
+    let mut other = init_scene().await;
+
+    let removed = scene.add_mesh(DummyMesh::new("removed"));
+    scene
+        .set_mesh_name(&removed, Some("removed".to_owned()))
+        .unwrap();
+    scene.set_mesh_user_data(&removed, 1u32).unwrap();
+    scene.remove_mesh(removed);
+    // takes the slot of the removed mesh
+    let mesh = scene.add_mesh(DummyMesh::new("mesh"));
+    scene.set_mesh_name(&mesh, Some("mesh".to_owned())).unwrap();
+    scene.add_mesh_tag(&mesh, "tag").unwrap();
+    scene.set_mesh_user_data(&mesh, 2u32).unwrap();
+    let foreign = other.add_mesh(DummyMesh::new("foreign"));
+    other
+        .set_mesh_name(&foreign, Some("foreign".to_owned()))
+        .unwrap();
+
+    for (id, error) in [
+        (removed, SceneError::Removed),
+        (foreign, SceneError::WrongScene),
+    ] {
+        assert_eq!(scene.mesh_name(&id), None);
+        assert_eq!(scene.set_mesh_name(&id, None), Err(error));
+        assert_eq!(scene.mesh_tags(&id), Err(error));
+        assert_eq!(scene.add_mesh_tag(&id, "other"), Err(error));
+        assert_eq!(scene.remove_mesh_tag(&id, "tag"), Err(error));
+        assert_eq!(scene.mesh_user_data::<_, u32>(&id), None);
+        assert_eq!(scene.mesh_user_data_mut::<_, u32>(&id), None);
+        assert_eq!(scene.set_mesh_user_data(&id, 3u32), Err(error));
+    }
+
+    // the mesh in the slot is left alone
+    assert_eq!(scene.mesh_name(&mesh), Some("mesh"));
+    assert_eq!(scene.mesh_tags(&mesh).unwrap(), ["tag"]);
+    assert_eq!(scene.mesh_user_data::<_, u32>(&mesh), Some(&2));
+}
+
 #[async_std::test]
 async fn test_scene_get_mesh_ref() {
     let mut scene = init_scene().await;
@@ -88,3 +308,216 @@ async fn test_scene_get_mesh_mut() {
     assert_eq!(scene.get_mesh_mut(&mesh1).label, "mesh1");
     assert_eq!(scene.get_mesh_mut(&mesh2).label, "mesh2");
 }
+
+#[async_std::test]
+async fn test_scene_add_remove_light() {
+    let mut scene = init_scene().await;
+
+    let ambient = scene.add_light(AmbientLight::new(Rgb::new(1., 1., 1.), 0.1));
+    scene.add_light(DirectionalLight::new(
+        Rgb::new(1., 1., 1.),
+        1.,
+        Vector3::new(0., -1., 0.),
+    ));
+    assert_eq!(scene.lights_len(), 2);
+
+    scene.remove_light(ambient);
+    assert_eq!(scene.lights_len(), 1);
+}
+
+#[async_std::test]
+async fn test_scene_get_light() {
+    let mut scene = init_scene().await;
+
+    let point = scene.add_light(PointLight::new(
+        Rgb::new(1., 1., 1.),
+        1.,
+        Point3::new(0., 0., 0.),
+    ));
+
+    scene.get_light_mut(&point).range = 5.;
+    assert_eq!(scene.get_light_ref(&point).range, 5.);
+}
+
+#[async_std::test]
+async fn test_scene_stale_light_id() {
+    let mut scene = init_scene().await;
+    let mut other = init_scene().await;
+
+    let ambient = scene.add_light(AmbientLight::new(Rgb::new(1., 1., 1.), 0.1));
+    let foreign = other.add_light(AmbientLight::new(Rgb::new(1., 1., 1.), 0.1));
+    assert!(scene.contains_light(&ambient));
+    assert_eq!(
+        scene.try_get_light(&foreign).unwrap_err(),
+        SceneError::WrongScene
+    );
+
+    assert_eq!(scene.try_remove_light(ambient), Ok(()));
+    let point = scene.add_light(PointLight::new(
+        Rgb::new(1., 1., 1.),
+        1.,
+        Point3::new(0., 0., 0.),
+    ));
+    assert!(!scene.contains_light(&ambient));
+    assert!(scene.contains_light(&point));
+    assert_eq!(
+        scene.try_get_light_mut(&ambient).unwrap_err(),
+        SceneError::Removed
+    );
+    assert_eq!(scene.try_remove_light(ambient), Err(SceneError::Removed));
+    assert_eq!(HashSet::from([point, point]).len(), 1);
+}
+
+#[async_std::test]
+async fn test_scene_set_max_lights() {
+    let mut scene = init_scene().await;
+
+    scene.set_max_lights(4);
+    assert_eq!(scene.max_lights(), 4);
+}
+
+#[async_std::test]
+async fn test_scene_set_background() {
+    let mut scene = init_scene().await;
+    assert!(matches!(scene.background(), Background::Color(c) if *c == wgpu::Color::WHITE));
+
+    scene.set_background(Rgb::new(0., 0., 1.));
+    assert!(matches!(scene.background(), Background::Color(c) if *c == wgpu::Color::BLUE));
+
+    scene.set_background(Background::gradient(
+        wgpu::Color::BLUE,
+        Rgb::new(0., 0., 0.),
+    ));
+    assert!(matches!(
+        scene.background(),
+        Background::Gradient { top, bottom } if *top == wgpu::Color::BLUE && *bottom == wgpu::Color::BLACK
+    ));
+}
+
+#[async_std::test]
+async fn test_scene_set_shadow_map_size() {
+    let mut scene = init_scene().await;
+
+    scene.set_shadow_map_size(256);
+    assert_eq!(scene.shadow_map_size(), 256);
+}
+
+#[async_std::test]
+async fn test_scene_set_max_point_shadows() {
+    let mut scene = init_scene().await;
+
+    scene.set_max_point_shadows(1);
+    assert_eq!(scene.max_point_shadows(), 1);
+}
+
+fn translation(x: f32, y: f32, z: f32) -> Instance {
+    Instance {
+        position: Vector3::new(x, y, z),
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn test_scene_node_world_matrix() {
+    let mut scene = init_scene().await;
+
+    let parent = scene.add_node(translation(1., 0., 0.));
+    let child = scene.add_node(translation(0., 2., 0.));
+    scene.set_node_parent(&child, Some(&parent)).unwrap();
+    assert_eq!(scene.node_parent(&child), Ok(Some(parent)));
+    assert_eq!(
+        scene.node_children(&parent).unwrap().collect::<Vec<_>>(),
+        [child]
+    );
+    assert_eq!(
+        scene.node_world_matrix(&child).unwrap().w,
+        Vector4::new(1., 2., 0., 1.)
+    );
+
+    // only the parent changes, the child follows
+    let mut transform = scene.node_transform(&parent).unwrap();
+    transform.set_scale(2.);
+    scene.set_node_transform(&parent, transform).unwrap();
+    assert_eq!(
+        scene.node_world_matrix(&child).unwrap().w,
+        Vector4::new(1., 4., 0., 1.)
+    );
+
+    scene.set_node_parent(&child, None).unwrap();
+    assert_eq!(scene.node_parent(&child), Ok(None));
+    assert_eq!(
+        scene.node_world_matrix(&child),
+        Ok(Matrix4::from_translation(Vector3::new(0., 2., 0.)))
+    );
+}
+
+#[async_std::test]
+async fn test_scene_remove_node() {
+    let mut scene = init_scene().await;
+
+    let group = scene.add_node(translation(1., 0., 0.));
+    let child = scene.add_node(Instance::default());
+    scene.set_node_parent(&child, Some(&group)).unwrap();
+    scene.remove_node(group).unwrap();
+
+    assert_eq!(scene.nodes_len(), 1);
+    assert_eq!(scene.node_parent(&child), Ok(None));
+    assert_eq!(scene.node_world_matrix(&child), Ok(Matrix4::identity()));
+}
+
+#[async_std::test]
+async fn test_scene_stale_node_id() {
+    let mut scene = init_scene().await;
+    let mut other = init_scene().await;
+
+    let removed = scene.add_node(Instance::default());
+    scene.remove_node(removed).unwrap();
+    // takes the slot of the removed node
+    let node = scene.add_node(Instance::default());
+    let foreign = other.add_node(Instance::default());
+    let mesh = scene.add_mesh(DummyMesh::new("a"));
+    let light = scene.add_light(AmbientLight::new(Rgb::new(1., 1., 1.), 1.));
+
+    assert!(scene.contains_node(&node));
+    assert!(!scene.contains_node(&removed));
+    assert!(!scene.contains_node(&foreign));
+    for (id, error) in [
+        (removed, SceneError::Removed),
+        (foreign, SceneError::WrongScene),
+    ] {
+        assert_eq!(scene.remove_node(id), Err(error));
+        assert_eq!(scene.node_transform(&id).err(), Some(error));
+        assert_eq!(
+            scene.set_node_transform(&id, Instance::default()),
+            Err(error)
+        );
+        assert_eq!(scene.node_world_matrix(&id), Err(error));
+        assert_eq!(scene.node_parent(&id), Err(error));
+        assert!(scene.node_children(&id).is_err());
+        assert_eq!(scene.set_node_parent(&id, None), Err(error));
+        assert_eq!(scene.set_node_parent(&node, Some(&id)), Err(error));
+        assert_eq!(scene.attach_mesh(&mesh, Some(&id)), Err(error));
+        assert_eq!(scene.attach_light(&light, Some(&id)), Err(error));
+        assert_eq!(scene.set_camera_node(Some(&id)), Err(error));
+    }
+    assert_eq!(scene.nodes_len(), 1);
+    assert_eq!(scene.node_transform(&node).unwrap().scale, (1., 1., 1.));
+}
+
+#[async_std::test]
+async fn test_scene_node_cycle() {
+    let mut scene = init_scene().await;
+
+    let parent = scene.add_node(Instance::default());
+    let child = scene.add_node(Instance::default());
+    scene.set_node_parent(&child, Some(&parent)).unwrap();
+    assert_eq!(
+        scene.set_node_parent(&parent, Some(&child)),
+        Err(SceneError::Cycle)
+    );
+    assert_eq!(
+        scene.set_node_parent(&parent, Some(&parent)),
+        Err(SceneError::Cycle)
+    );
+    assert_eq!(scene.node_parent(&parent), Ok(None));
+}
@@ -0,0 +1,153 @@
+use san::{
+    effect::ShaderEffect,
+    material::ShaderMaterial,
+    shader::{compile, preprocess, PreprocessError, ShaderFeatures},
+};
+
+#[test]
+fn test_preprocess_conditionals() {
+    let source = "\
+a
+#ifdef A
+b
+#ifndef B
+c
+#else
+d
+#endif
+#else
+e
+#endif
+#define B
+#ifdef B
+f
+#endif
+";
+
+    assert_eq!(preprocess(source, ["A"]).unwrap(), "a\nb\nc\nf\n");
+    assert_eq!(preprocess(source, ["A", "B"]).unwrap(), "a\nb\nd\nf\n");
+    assert_eq!(preprocess(source, []).unwrap(), "a\ne\nf\n");
+}
+
+#[test]
+fn test_preprocess_include() {
+    let source =
+        "#include \"lighting\"\n#include \"camera\"\n#ifdef X\n#include \"nothing\"\n#endif\n";
+    let out = preprocess(source, []).unwrap();
+
+    assert_eq!(out.matches("struct GlobalParams").count(), 1);
+    assert_eq!(out.matches("struct Light {").count(), 1);
+    assert!(out.contains("fn incident_light"));
+    assert!(!out.contains('#'));
+}
+
+#[test]
+fn test_preprocess_error() {
+    assert_eq!(
+        preprocess("x\n#include \"nothing\"", []),
+        Err(PreprocessError::UnknownChunk {
+            line: 2,
+            name: "nothing".to_owned()
+        })
+    );
+    assert_eq!(
+        preprocess("#endif", []),
+        Err(PreprocessError::InvalidDirective {
+            line: 1,
+            directive: "#endif".to_owned()
+        })
+    );
+    assert!(matches!(
+        preprocess("#pragma once", []),
+        Err(PreprocessError::InvalidDirective { line: 1, .. })
+    ));
+    assert_eq!(
+        preprocess("#ifdef A\n#ifdef B\n#endif", []),
+        Err(PreprocessError::Unterminated { line: 1 })
+    );
+}
+
+#[test]
+fn test_shader_features() {
+    let mut features = ShaderFeatures::MAP | ShaderFeatures::ENV_MAP;
+    assert!(features.contains(ShaderFeatures::MAP));
+    assert!(!features.contains(ShaderFeatures::NORMAL_MAP));
+    assert_eq!(features.defines().collect::<Vec<_>>(), ["MAP", "ENV_MAP"]);
+
+    features.set(ShaderFeatures::MAP, false);
+    features.set(ShaderFeatures::ENV_MAP, false);
+    assert!(features.is_empty());
+}
+
+#[test]
+fn test_builtin_shaders() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+    let mut count = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "wgsl")
+        {
+            continue;
+        }
+        let file = path.file_name().unwrap().to_str().unwrap();
+        let source = std::fs::read_to_string(&path).unwrap();
+
+        for variant in ShaderFeatures::variants() {
+            if let Err(e) = compile(file, &source, variant.defines()) {
+                panic!("{variant:?}: {e}");
+            }
+        }
+        count += 1;
+    }
+
+    assert!(count > 0);
+}
+
+#[test]
+fn test_compile_error() {
+    let source = "\
+#include \"camera\"
+
+fn f() -> f32 {
+    return globals.missing;
+}
+";
+    let e = compile("broken.wgsl", source, []).unwrap_err();
+    assert_eq!((e.file.as_str(), e.line, e.column), ("broken.wgsl", 4, 20));
+    assert!(e.excerpt.starts_with("4 |     return globals.missing;\n"));
+    assert!(e.excerpt.ends_with("|                    ^"));
+    assert!(e.to_string().starts_with("broken.wgsl:4:20: "));
+
+    let e = compile("broken.wgsl", "fn f() {}\n#ifdef A\n", []).unwrap_err();
+    assert_eq!((e.line, e.column), (2, 1));
+    assert!(e.message.contains("#endif"));
+
+    // validation errors are located at the function
+    let source = "#include \"camera\"\nfn f() -> f32 {\n  return 1u;\n}\n";
+    let e = compile("broken.wgsl", source, []).unwrap_err();
+    assert_eq!((e.file.as_str(), e.line, e.column), ("broken.wgsl", 2, 1));
+    assert!(e.message.contains("return"));
+}
+
+#[test]
+fn test_validate() {
+    let effect = ShaderEffect::new("broken", "fn fs_main( {}");
+    let e = effect.validate().unwrap_err();
+    assert_eq!((e.file.as_str(), e.line), ("broken", 1));
+
+    let source = "
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> {
+            return vec4<f32>(uniforms.color, 1.);
+        }
+    ";
+    let material = ShaderMaterial::new("tint", source).with_uniform("color", 0f32);
+    let e = material.validate().unwrap_err();
+    assert_eq!((e.file.as_str(), e.line), ("tint", 4));
+
+    let material = ShaderMaterial::new("tint", source).with_uniform("color", [0f32; 3]);
+    assert_eq!(material.validate(), Ok(()));
+}
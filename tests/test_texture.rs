@@ -0,0 +1,458 @@
+mod common;
+
+use half::f16;
+use san::{
+    effect::{Effect, EffectContext, ShaderEffect, HDR_FORMAT},
+    texture::{ColorSpace, TextureError},
+    wgpu, CubeTexture, Sampler, Texture,
+};
+
+// 2x1 RGBA image: opaque red, half transparent blue
+#[rustfmt::skip]
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01,
+    0x08, 0x06, 0x00, 0x00, 0x00, 0xf4, 0x22, 0x7f, 0x8a, 0x00, 0x00, 0x00,
+    0x0e, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0x00,
+    0x42, 0x0d, 0x00, 0x0f, 0x7a, 0x03, 0x7e, 0x77, 0xe9, 0x7f, 0x97, 0x00,
+    0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+#[test]
+fn test_texture_from_png() {
+    let texture = Texture::from_image_bytes(PNG, ColorSpace::Srgb).unwrap();
+
+    assert_eq!(texture.width(), 2);
+    assert_eq!(texture.height(), 1);
+    assert_eq!(texture.color_space(), ColorSpace::Srgb);
+}
+
+#[test]
+fn test_texture_decode_error() {
+    let result = Texture::from_image_bytes(&[0, 1, 2, 3], ColorSpace::Srgb);
+
+    assert!(matches!(result, Err(TextureError::Decode(_))));
+}
+
+/// Minimal KTX2 container with the given Vulkan format and mip levels, largest first.
+fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+    let mut bytes = b"\xabKTX 20\xbb\r\n\x1a\n".to_vec();
+    for v in [vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, 0] {
+        bytes.extend(v.to_le_bytes());
+    }
+    // empty data format descriptor, key/value data and supercompression global data
+    bytes.extend([0; 32]);
+
+    let mut offset = 80 + 24 * levels.len() as u64;
+    for level in levels {
+        let len = level.len() as u64;
+        for v in [offset, len, len] {
+            bytes.extend(v.to_le_bytes());
+        }
+        offset += len;
+    }
+    for level in levels {
+        bytes.extend(*level);
+    }
+    bytes
+}
+
+const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+const VK_FORMAT_BC1_RGBA_UNORM_BLOCK: u32 = 133;
+const VK_FORMAT_BC6H_UFLOAT_BLOCK: u32 = 143;
+const VK_FORMAT_BC6H_SFLOAT_BLOCK: u32 = 144;
+const VK_FORMAT_BC7_UNORM_BLOCK: u32 = 145;
+const VK_FORMAT_ETC2_R8G8B8_UNORM_BLOCK: u32 = 147;
+const VK_FORMAT_ASTC_4X4_UNORM_BLOCK: u32 = 157;
+
+/// Texels of a single level KTX2 file as sampled on a device without texture compression
+/// features, where the blocks are decoded on the CPU.
+async fn decode_ktx2(vk_format: u32, width: u32, height: u32, blocks: &[u8]) -> Vec<[f32; 4]> {
+    let (device, queue) = common::init_device().await;
+    let texture = Texture::from_ktx2(&ktx2(vk_format, width, height, &[blocks])).unwrap();
+
+    let source = "
+        @group(0) @binding(3)
+        var blocks: texture_2d<f32>;
+
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            return textureLoad(blocks, vec2<i32>(in.clip_position.xy), 0);
+        }
+    ";
+    let effect = ShaderEffect::new("copy", source).with_texture(texture.with_mipmaps(false));
+
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let create_texture = |usage| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | usage,
+            view_formats: &[],
+        })
+    };
+    let input = create_texture(wgpu::TextureUsages::TEXTURE_BINDING);
+    let output = create_texture(wgpu::TextureUsages::COPY_SRC);
+    let context = EffectContext {
+        device: &device,
+        queue: &queue,
+        size: (width, height),
+    };
+
+    let padded = (width * 8).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (padded * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    effect.render(
+        &context,
+        &input.create_view(&Default::default()),
+        &output.create_view(&Default::default()),
+        &mut encoder,
+    );
+    encoder.copy_texture_to_buffer(
+        output.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(padded),
+                rows_per_image: None,
+            },
+        },
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).unwrap()
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap().unwrap();
+
+    let data = slice.get_mapped_range();
+    data.chunks(padded as usize)
+        .flat_map(|row| row[..(width * 8) as usize].chunks(8))
+        .map(|texel| {
+            [0, 1, 2, 3].map(|i| f16::from_le_bytes([texel[2 * i], texel[2 * i + 1]]).to_f32())
+        })
+        .collect()
+}
+
+/// [`decode_ktx2`] for formats decoded to 8 bit unsigned normalized texels.
+async fn decode_ktx2_unorm(vk_format: u32, width: u32, height: u32, blocks: &[u8]) -> Vec<[u8; 4]> {
+    let texels = decode_ktx2(vk_format, width, height, blocks).await;
+    texels
+        .into_iter()
+        .map(|texel| texel.map(|v| (v * 255.).round() as u8))
+        .collect()
+}
+
+#[test]
+fn test_texture_from_ktx2() {
+    let bytes = ktx2(VK_FORMAT_R8G8B8A8_SRGB, 2, 1, &[&[255; 8]]);
+    let texture = Texture::from_ktx2(&bytes).unwrap();
+
+    assert_eq!(texture.width(), 2);
+    assert_eq!(texture.height(), 1);
+    assert_eq!(texture.color_space(), ColorSpace::Srgb);
+    assert_eq!(texture.mip_level_count(), 2);
+}
+
+#[test]
+fn test_texture_from_ktx2_compressed() {
+    let bytes = ktx2(VK_FORMAT_BC1_RGBA_UNORM_BLOCK, 8, 8, &[&[0; 32], &[0; 8]]);
+    let texture = Texture::from_ktx2(&bytes).unwrap();
+
+    assert_eq!(texture.width(), 8);
+    assert_eq!(texture.color_space(), ColorSpace::Linear);
+    // only the levels stored in the file
+    assert_eq!(texture.mip_level_count(), 2);
+}
+
+#[test]
+fn test_texture_from_ktx2_error() {
+    assert!(matches!(
+        Texture::from_ktx2(&[0; 100]),
+        Err(TextureError::Ktx2(_))
+    ));
+
+    let truncated = ktx2(VK_FORMAT_BC1_RGBA_UNORM_BLOCK, 8, 8, &[&[0; 8]]);
+    assert!(matches!(
+        Texture::from_ktx2(&truncated),
+        Err(TextureError::Ktx2(_))
+    ));
+
+    // VK_FORMAT_R8_UNORM
+    let unsupported = ktx2(9, 1, 1, &[&[0]]);
+    assert!(matches!(
+        Texture::from_ktx2(&unsupported),
+        Err(TextureError::UnsupportedKtx2(_))
+    ));
+
+    // VK_FORMAT_ASTC_4x4_SFLOAT_BLOCK, HDR ASTC has no CPU decoder
+    let hdr = ktx2(1000066000, 4, 4, &[&[0; 16]]);
+    assert!(matches!(
+        Texture::from_ktx2(&hdr),
+        Err(TextureError::UnsupportedKtx2(_))
+    ));
+}
+
+#[async_std::test]
+async fn test_texture_decode_bc1() {
+    #[rustfmt::skip]
+    let blocks = [
+        // black and gray with punch-through alpha, as the first color is not greater
+        0x00, 0x00, 0x10, 0x84, 0xe4, 0xe4, 0xe4, 0xe4,
+        // white and black with two interpolated colors
+        0xff, 0xff, 0x00, 0x00, 0xe4, 0xe4, 0xe4, 0xe4,
+    ];
+    let texels = decode_ktx2_unorm(VK_FORMAT_BC1_RGBA_UNORM_BLOCK, 8, 4, &blocks).await;
+
+    let row = [
+        [0, 0, 0, 255],
+        [132, 130, 132, 255],
+        [66, 65, 66, 255],
+        [0, 0, 0, 0],
+        [255, 255, 255, 255],
+        [0, 0, 0, 255],
+        [170, 170, 170, 255],
+        [85, 85, 85, 255],
+    ];
+    for y in 0..4 {
+        assert_eq!(texels[y * 8..y * 8 + 8], row);
+    }
+}
+
+#[async_std::test]
+async fn test_texture_decode_bc6h() {
+    // mode 11: one region with 10 bit endpoints from black to (1023, 512, 0), and four bit
+    // indices counting up
+    let block = [
+        0x03, 0x00, 0x00, 0x00, 0xf8, 0x1f, 0x40, 0x00, 0x10, 0x32, 0x54, 0x76, 0x98, 0xba, 0xdc,
+        0xfe,
+    ];
+    let texels = decode_ktx2(VK_FORMAT_BC6H_UFLOAT_BLOCK, 4, 4, &block).await;
+    let half = |texel: [f32; 4]| texel.map(|v| f16::from_f32(v).to_bits());
+
+    assert_eq!(half(texels[0]), [0, 0, 0, 0x3c00]);
+    assert_eq!(half(texels[8]), [0x41df, 0x20f8, 0, 0x3c00]);
+    assert_eq!(half(texels[15]), [0x7bff, 0x3e0f, 0, 0x3c00]);
+
+    // the same endpoint bits are negative when signed
+    let texels = decode_ktx2(VK_FORMAT_BC6H_SFLOAT_BLOCK, 4, 4, &block).await;
+    assert_eq!(texels[0], [0., 0., 0., 1.]);
+    assert!(texels[15][0] < 0.);
+    assert_eq!(texels[15][1], -65504.);
+}
+
+#[async_std::test]
+async fn test_texture_decode_bc7() {
+    #[rustfmt::skip]
+    let blocks = [
+        // mode 6: one subset with alpha and four bit indices counting up
+        0x40, 0xc8, 0x1f, 0x04, 0x00, 0x92, 0xff, 0x7f,
+        0x11, 0x32, 0x54, 0x76, 0x98, 0xba, 0xdc, 0xfe,
+        // mode 5: separate alpha indices, with red and alpha swapped
+        0x60, 0x80, 0xff, 0x1f, 0x00, 0x04, 0x02, 0xfc,
+        0xcb, 0xc9, 0xc9, 0xc9, 0x19, 0x1b, 0x1b, 0x1b,
+        // mode 1: two subsets split into columns by partition 0
+        0x02, 0x3f, 0x00, 0xfc, 0xc0, 0x0f, 0xfc, 0x00,
+        0xf0, 0x03, 0x01, 0x00, 0x3f, 0x00, 0x00, 0xc0,
+        // reserved mode
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let texels = decode_ktx2_unorm(VK_FORMAT_BC7_UNORM_BLOCK, 16, 4, &blocks).await;
+    let texel = |block: usize, x: usize, y: usize| texels[y * 16 + block * 4 + x];
+
+    assert_eq!(texel(0, 0, 0), [32, 64, 128, 254]);
+    assert_eq!(texel(0, 0, 2), [150, 31, 167, 255]);
+    assert_eq!(texel(0, 3, 3), [255, 1, 201, 255]);
+
+    assert_eq!(texel(1, 0, 0), [0, 255, 129, 0]);
+    assert_eq!(texel(1, 1, 0), [171, 171, 129, 84]);
+    assert_eq!(texel(1, 3, 0), [0, 0, 129, 255]);
+    assert_eq!(texel(1, 0, 1), [255, 255, 129, 0]);
+
+    assert_eq!(texel(2, 0, 0), [255, 2, 2, 255]);
+    assert_eq!(texel(2, 1, 1), [2, 255, 2, 255]);
+    assert_eq!(texel(2, 2, 0), [0, 0, 253, 255]);
+    assert_eq!(texel(2, 2, 1), [253, 253, 0, 255]);
+    assert_eq!(texel(2, 3, 3), [107, 107, 146, 255]);
+
+    for y in 0..4 {
+        for x in 0..4 {
+            assert_eq!(texel(3, x, y), [0; 4]);
+        }
+    }
+}
+
+#[async_std::test]
+async fn test_texture_decode_etc2() {
+    #[rustfmt::skip]
+    let blocks = [
+        // T mode, red overflows
+        0xf2, 0x50, 0x37, 0xcb, 0xff, 0x00, 0xf0, 0xf0,
+        // H mode, green overflows
+        0x43, 0xeb, 0x12, 0x33, 0xff, 0x00, 0xf0, 0xf0,
+        // planar mode, blue overflows
+        0x41, 0x00, 0xf2, 0x7f, 0xfe, 0x00, 0x00, 0x3f,
+    ];
+    let texels = decode_ktx2_unorm(VK_FORMAT_ETC2_R8G8B8_UNORM_BLOCK, 12, 4, &blocks).await;
+    let texel = |block: usize, x: usize, y: usize| texels[y * 12 + block * 4 + x];
+
+    // the T and H blocks use the palette entry of their column
+    let t = [[170, 85, 0], [83, 151, 236], [51, 119, 204], [19, 87, 172]];
+    let h = [[152, 118, 254], [120, 86, 222], [50, 84, 118], [18, 52, 86]];
+    for y in 0..4 {
+        for x in 0..4 {
+            let [r, g, b] = t[x];
+            assert_eq!(texel(0, x, y), [r, g, b, 255]);
+            let [r, g, b] = h[x];
+            assert_eq!(texel(1, x, y), [r, g, b, 255]);
+        }
+    }
+
+    assert_eq!(texel(2, 0, 0), [130, 129, 81, 255]);
+    assert_eq!(texel(2, 3, 0), [224, 224, 20, 255]);
+    assert_eq!(texel(2, 0, 3), [33, 32, 212, 255]);
+    assert_eq!(texel(2, 3, 3), [126, 127, 151, 255]);
+}
+
+#[async_std::test]
+async fn test_texture_decode_astc() {
+    #[rustfmt::skip]
+    let blocks = [
+        // void extent
+        0xfc, 0xfd, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0x80, 0x80, 0x40, 0x40, 0xff, 0xff, 0xff, 0xff,
+        // luminance from black to white, with a 4x4 grid of two bit weights counting up
+        // along each row
+        0x42, 0x00, 0x00, 0xfe, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x27, 0x27, 0x27, 0x27,
+    ];
+    let texels = decode_ktx2_unorm(VK_FORMAT_ASTC_4X4_UNORM_BLOCK, 8, 4, &blocks).await;
+
+    for y in 0..4 {
+        let row = &texels[y * 8..y * 8 + 8];
+        assert_eq!(row[..4], [[128, 64, 255, 255]; 4]);
+        assert_eq!(
+            row[4..].iter().map(|texel| texel[0]).collect::<Vec<_>>(),
+            [0, 84, 171, 255]
+        );
+        assert!(row[4..]
+            .iter()
+            .all(|&[r, g, b, a]| r == g && g == b && a == 255));
+    }
+}
+
+#[test]
+fn test_texture_from_rgba32f() {
+    let texture = Texture::from_rgba32f(1, 1, vec![4., 2., 1., 1.]);
+
+    assert_eq!(texture.color_space(), ColorSpace::Linear);
+}
+
+#[test]
+fn test_texture_from_hdr() {
+    let mut bytes = Vec::new();
+    image::codecs::hdr::HdrEncoder::new(&mut bytes)
+        .encode(&[image::Rgb([4., 2., 1.]); 2], 2, 1)
+        .unwrap();
+    let texture = Texture::from_image_bytes(&bytes, ColorSpace::Srgb).unwrap();
+
+    assert_eq!(texture.width(), 2);
+    assert_eq!(texture.color_space(), ColorSpace::Linear);
+}
+
+#[test]
+fn test_cube_texture_from_faces() {
+    let face = |size: u32, color_space| {
+        Texture::from_rgba8(
+            size,
+            size,
+            vec![255; (size * size * 4) as usize],
+            color_space,
+        )
+    };
+    let faces = || [(); 6].map(|_| face(4, ColorSpace::Srgb));
+
+    let cube = CubeTexture::from_faces(faces()).unwrap();
+    assert_eq!(cube.size(), 4);
+
+    let mut mismatched = faces();
+    mismatched[3] = face(2, ColorSpace::Srgb);
+    assert!(matches!(
+        CubeTexture::from_faces(mismatched),
+        Err(TextureError::CubeFace(3))
+    ));
+
+    let mut mismatched = faces();
+    mismatched[5] = face(4, ColorSpace::Linear);
+    assert!(matches!(
+        CubeTexture::from_faces(mismatched),
+        Err(TextureError::CubeFace(5))
+    ));
+
+    let mut not_square = faces();
+    not_square[0] = Texture::from_rgba8(4, 2, vec![255; 32], ColorSpace::Srgb);
+    assert!(CubeTexture::from_faces(not_square).is_err());
+}
+
+#[test]
+fn test_cube_texture_from_equirect() {
+    let panorama = Texture::from_rgba32f(4, 2, vec![1.; 32]);
+
+    assert_eq!(CubeTexture::from_equirect(panorama, 16).size(), 16);
+}
+
+#[test]
+fn test_texture_mip_level_count() {
+    let texture = Texture::from_rgba8(300, 200, vec![255; 300 * 200 * 4], ColorSpace::Srgb);
+
+    assert_eq!(texture.mip_level_count(), 9);
+    assert_eq!(texture.clone().with_mipmaps(false).mip_level_count(), 1);
+    assert_eq!(
+        Texture::from_image_bytes(PNG, ColorSpace::Srgb)
+            .unwrap()
+            .mip_level_count(),
+        2
+    );
+}
+
+#[test]
+fn test_texture_with_sampler() {
+    let sampler = Sampler::nearest().wrap(wgpu::AddressMode::ClampToEdge);
+    let texture = Texture::from_rgba8(1, 1, vec![255; 4], ColorSpace::Srgb);
+    let clamped = texture.clone().with_sampler(sampler.clone());
+
+    assert_eq!(texture.sampler(), &Sampler::default());
+    assert_eq!(clamped.sampler(), &sampler);
+}
+
+#[test]
+fn test_sampler_anisotropy() {
+    assert_eq!(
+        Sampler::linear().anisotropy(5),
+        Sampler::linear().anisotropy(4)
+    );
+    assert_eq!(
+        Sampler::linear().anisotropy(64),
+        Sampler::linear().anisotropy(16)
+    );
+    assert_ne!(Sampler::linear().anisotropy(8), Sampler::linear());
+}